@@ -0,0 +1,166 @@
+// small-signal ac analysis: the circuit is linearized around its operating point and solved with
+// complex phasors for every frequency of a sweep, sources only contribute their ac value
+//
+// the bode plot of a node is its phasor divided by the ac value of an input source, so the gain of a
+// filter driven by a voltage source is a plain voltage ratio
+
+use std::collections::BTreeMap;
+use std::f64::consts::SQRT_2;
+use nalgebra::Complex;
+use serde::{Deserialize, Serialize};
+use crate::circuit::Circuit;
+use crate::schematic::Parameters;
+
+// ac value of an independent source, the phase is in degrees
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AcSource {
+    pub magnitude: f64,
+    pub phase: f64,
+}
+
+impl AcSource {
+    pub fn phasor(&self) -> Complex<f64> {
+        Complex::from_polar(self.magnitude, self.phase.to_radians())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SweepScale {
+    // points is the total number of frequencies
+    Linear,
+    // points per decade or octave, as in spice
+    Decade,
+    Octave,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Sweep {
+    pub scale: SweepScale,
+    pub points: u32,
+    pub start: f64,
+    pub stop: f64,
+}
+
+impl Sweep {
+    pub fn frequencies(&self) -> Vec<f64> {
+        let points = self.points.max(1);
+        let ratio = match self.scale {
+            SweepScale::Linear => {
+                if points == 1 {
+                    return vec![self.start];
+                }
+                let step = (self.stop - self.start) / (points - 1) as f64;
+                return (0..points).map(|index| self.start + step * index as f64).collect();
+            }
+            SweepScale::Decade => 10.0f64,
+            SweepScale::Octave => 2.0f64,
+        };
+        if self.start <= 0.0 || self.stop < self.start {
+            return Vec::new();
+        }
+        // the stop frequency is included even if it is slightly off because of rounding
+        let count = ((self.stop / self.start).log(ratio) * points as f64 + 1.0e-9).floor() as u32 + 1;
+        (0..count).map(|index| self.start * ratio.powf(index as f64 / points as f64)).collect()
+    }
+}
+
+// phasors of one frequency of a sweep
+#[derive(Debug, Clone, Default)]
+pub struct AcSolution {
+    pub frequency: f64,
+    pub(crate) voltages: BTreeMap<u32, Complex<f64>>,
+    pub(crate) branch_currents: BTreeMap<u32, Complex<f64>>,
+}
+
+impl AcSolution {
+    pub fn voltage(&self, node: u32) -> Complex<f64> {
+        self.voltages.get(&node).copied().unwrap_or_default()
+    }
+
+    pub fn voltages(&self) -> &BTreeMap<u32, Complex<f64>> {
+        &self.voltages
+    }
+
+    // current from the first to the second node of a voltage source or inductor
+    pub fn branch_current(&self, element: u32) -> Option<Complex<f64>> {
+        self.branch_currents.get(&element).copied()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BodePoint {
+    pub frequency: f64,
+    pub magnitude: f64,
+    // in degrees, unwrapped along the sweep so it does not jump between -180 and 180
+    pub phase: f64,
+}
+
+impl BodePoint {
+    pub fn decibels(&self) -> f64 {
+        20.0 * self.magnitude.log10()
+    }
+}
+
+// ac value of a source, None if the element is not a source or has no ac value
+pub fn input_phasor(circuit: &Circuit, input: u32) -> Option<Complex<f64>> {
+    match circuit.element(input)?.parameters() {
+        Parameters::DCVoltageSource { ac, .. } | Parameters::CurrentSource { ac, .. } => ac.map(|ac| ac.phasor()),
+        _ => None,
+    }
+}
+
+// transfer function from the input source to a node, None if the input has no ac value
+pub fn bode(circuit: &Circuit, solutions: &[AcSolution], output: u32, input: u32) -> Option<Vec<BodePoint>> {
+    let input = input_phasor(circuit, input).filter(|phasor| phasor.norm() > 0.0)?;
+    let mut points: Vec<BodePoint> = Vec::new();
+    for solution in solutions {
+        let transfer = solution.voltage(output) / input;
+        let mut phase = transfer.arg().to_degrees();
+        if let Some(last) = points.last() {
+            phase -= 360.0 * ((phase - last.phase) / 360.0).round();
+        }
+        points.push(BodePoint { frequency: solution.frequency, magnitude: transfer.norm(), phase });
+    }
+    Some(points)
+}
+
+// interpolates between two points of a sweep, on a log scale where the frequencies allow it
+fn interpolate(a: &BodePoint, b: &BodePoint, fraction: f64) -> (f64, f64) {
+    let frequency = if a.frequency > 0.0 && b.frequency > 0.0 {
+        (a.frequency.ln() + (b.frequency.ln() - a.frequency.ln()) * fraction).exp()
+    } else {
+        a.frequency + (b.frequency - a.frequency) * fraction
+    };
+    (frequency, a.phase + (b.phase - a.phase) * fraction)
+}
+
+// frequencies where the magnitude crosses the given level in decibels
+fn crossings(points: &[BodePoint], level: f64) -> Vec<(f64, f64)> {
+    points.windows(2)
+        .filter(|pair| (pair[0].decibels() - level) * (pair[1].decibels() - level) < 0.0 || pair[1].decibels() == level)
+        .map(|pair| {
+            let fraction = (level - pair[0].decibels()) / (pair[1].decibels() - pair[0].decibels());
+            interpolate(&pair[0], &pair[1], fraction)
+        })
+        .collect()
+}
+
+// frequencies where the gain is 3 dB (a factor of sqrt 2) below its maximum in the sweep
+pub fn cutoff_frequencies(points: &[BodePoint]) -> Vec<f64> {
+    let Some(peak) = points.iter().map(BodePoint::decibels).max_by(f64::total_cmp) else {
+        return Vec::new();
+    };
+    crossings(points, peak - 20.0 * SQRT_2.log10()).into_iter().map(|(frequency, _)| frequency).collect()
+}
+
+// phase margin of a loop gain: 180 degrees plus the phase where the gain falls through 0 dB,
+// returns the frequency of that crossing and the margin in degrees
+pub fn phase_margin(points: &[BodePoint]) -> Option<(f64, f64)> {
+    points.windows(2)
+        .position(|pair| pair[0].decibels() > 0.0 && pair[1].decibels() <= 0.0)
+        .map(|index| {
+            let (a, b) = (&points[index], &points[index + 1]);
+            let (frequency, phase) = interpolate(a, b, a.decibels() / (a.decibels() - b.decibels()));
+            (frequency, 180.0 + phase)
+        })
+}
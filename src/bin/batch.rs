@@ -0,0 +1,464 @@
+// solves a schematic or a spice deck without the editor and prints the node voltages and element currents,
+// for scripts and regression checks
+//
+// exit codes: 0 solved, 1 bad arguments, 2 the file could not be loaded, 3 the circuit is singular,
+// 4 the newton iterations did not converge
+// errors are written to stderr as one line, a json object with the json format
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::process::ExitCode;
+use serde_json::json;
+use rusty_circuit::ElementType;
+use rusty_circuit::ac::{Sweep, SweepScale};
+use rusty_circuit::circuit::{Circuit, StepInfo};
+use rusty_circuit::circuit_solver::{NetTable, SolverError, Unknown};
+use rusty_circuit::dc_sweep::{dc_sweep, DcSweep, DcSweepError, Trace};
+use rusty_circuit::schematic::{Schematic, View};
+use rusty_circuit::spice::{self, Analysis};
+
+const USAGE: &str = "usage: rusty_circuit_batch <schematic.json | deck.cir> [analysis...] [--format table|json|csv]
+
+analyses, a spice deck runs its own .op, .tran and .ac lines if none are given:
+  --op                                                operating point, the default
+  --dc <element> <parameter> <start> <stop> <points>  element is an id or a spice name
+  --tran <step> <stop>                                starts from the operating point, as spice without uic
+  --ac <dec|oct|lin> <points> <start> <stop>";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Table,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Job {
+    OperatingPoint,
+    // the element is resolved once the circuit is loaded
+    DcSweep { element: String, sweep: DcSweep },
+    Transient { step: f64, stop: f64 },
+    Ac(Sweep),
+}
+
+#[derive(Debug)]
+enum Failure {
+    Usage(String),
+    Load(String),
+    Solver { analysis: &'static str, at: Option<f64>, error: SolverError },
+}
+
+impl Failure {
+    fn exit_code(&self) -> u8 {
+        match self {
+            Failure::Usage(_) => 1,
+            Failure::Load(_) => 2,
+            Failure::Solver { error: SolverError::NoConvergence { .. }, .. } => 4,
+            Failure::Solver { .. } => 3,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Failure::Usage(_) => "usage",
+            Failure::Load(_) => "load",
+            Failure::Solver { error, .. } => match error {
+                SolverError::FloatingSubnet { .. } => "floating_subnet",
+                SolverError::VoltageSourceLoop { .. } => "voltage_source_loop",
+                SolverError::CurrentSourceOpen { .. } => "current_source_open",
+                SolverError::SingularMatrix { .. } => "singular_matrix",
+                SolverError::NoConvergence { .. } => "no_convergence",
+                SolverError::InvalidControl { .. } => "invalid_control",
+                SolverError::InvalidSweep { .. } => "invalid_sweep",
+            },
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            Failure::Usage(message) | Failure::Load(message) => message.clone(),
+            Failure::Solver { analysis, at: Some(at), error } => format!("{} at {}: {}", analysis, at, error),
+            Failure::Solver { analysis, at: None, error } => format!("{}: {}", analysis, error),
+        }
+    }
+
+    fn report(&self, format: Format, labels: Option<&Labels>) {
+        if format != Format::Json {
+            eprintln!("error[{}]: {}", self.kind(), self.message());
+            if let Failure::Usage(_) = self {
+                eprintln!("{}", USAGE);
+            }
+            return;
+        }
+
+        let mut report = json!({ "error": self.kind(), "message": self.message() });
+        if let Failure::Solver { analysis, at, error } = self {
+            report["analysis"] = json!(analysis);
+            report["at"] = json!(at);
+            // the nodes and elements the error is about, by their names in the file
+            let node = |node: &u32| labels.map_or(node.to_string(), |labels| labels.node(*node));
+            let element = |element: &u32| labels.map_or(element.to_string(), |labels| labels.element(*element));
+            let unknown = |unknown: &Unknown| match unknown {
+                Unknown::NodeVoltage(id) => json!({ "node": node(id) }),
+                Unknown::BranchCurrent(id) => json!({ "element": element(id) }),
+            };
+            match error {
+                SolverError::FloatingSubnet { nodes } => report["nodes"] = json!(nodes.iter().map(node).collect::<Vec<_>>()),
+                SolverError::VoltageSourceLoop { elements } => report["elements"] = json!(elements.iter().map(element).collect::<Vec<_>>()),
+                SolverError::CurrentSourceOpen { element: source, nodes } => {
+                    report["elements"] = json!([element(source)]);
+                    report["nodes"] = json!(nodes.iter().map(node).collect::<Vec<_>>());
+                }
+                SolverError::SingularMatrix { unknown: singular } => report["unknown"] = unknown(singular),
+                SolverError::NoConvergence { iterations, unknown: unsettled } => {
+                    report["iterations"] = json!(iterations);
+                    report["unknown"] = unknown(unsettled);
+                }
+                SolverError::InvalidControl { element: source, control } => report["elements"] = json!([element(source), element(control)]),
+                SolverError::InvalidSweep { .. } => {}
+            }
+        }
+        eprintln!("{}", report);
+    }
+}
+
+// names of the columns: the spice names for a deck, the ids for a schematic
+struct Labels {
+    nets: NetTable,
+    // net -> name, ground is left out
+    nodes: BTreeMap<u32, String>,
+    // elements that get a current column, wires and ground symbols are left out
+    elements: BTreeMap<u32, String>,
+}
+
+impl Labels {
+    fn node(&self, node: u32) -> String {
+        let net = self.nets.net_of(node).unwrap_or(node);
+        match self.nodes.get(&net) {
+            Some(name) => name.clone(),
+            None if net == 0 => "0".to_string(),
+            None => node.to_string(),
+        }
+    }
+
+    fn element(&self, element: u32) -> String {
+        self.elements.get(&element).cloned().unwrap_or(element.to_string())
+    }
+
+    // an element by its id or its name
+    fn find_element(&self, name: &str) -> Option<u32> {
+        self.elements.iter()
+            .find(|(_, label)| label.eq_ignore_ascii_case(name))
+            .map(|(id, _)| *id)
+            .or_else(|| name.parse().ok())
+    }
+
+    fn traces(&self) -> Vec<(String, Trace)> {
+        let voltages = self.nodes.iter().map(|(net, name)| (format!("V({})", name), Trace::Voltage(*net)));
+        let currents = self.elements.iter().map(|(id, name)| (format!("I({})", name), Trace::Current(*id)));
+        voltages.chain(currents).collect()
+    }
+}
+
+struct Table {
+    analysis: &'static str,
+    columns: Vec<String>,
+    // values a column does not have are None
+    rows: Vec<Vec<Option<f64>>>,
+}
+
+impl Table {
+    fn new(analysis: &'static str, columns: Vec<String>) -> Self {
+        Table { analysis, columns, rows: Vec::new() }
+    }
+
+    fn write_text(&self, output: &mut String) {
+        let cells: Vec<Vec<String>> = self.rows.iter()
+            .map(|row| row.iter().map(|value| value.map_or("-".to_string(), |value| format!("{:.6e}", value))).collect())
+            .collect();
+        let widths: Vec<usize> = self.columns.iter().enumerate()
+            .map(|(index, column)| cells.iter().map(|row| row[index].len()).chain([column.len()]).max().unwrap_or(0))
+            .collect();
+        writeln!(output, "{}", self.analysis).unwrap();
+        let header: Vec<String> = self.columns.iter().zip(widths.iter()).map(|(column, width)| format!("{:>width$}", column, width = width)).collect();
+        writeln!(output, "{}", header.join("  ")).unwrap();
+        for row in cells {
+            let row: Vec<String> = row.iter().zip(widths.iter()).map(|(cell, width)| format!("{:>width$}", cell, width = width)).collect();
+            writeln!(output, "{}", row.join("  ")).unwrap();
+        }
+    }
+
+    fn write_csv(&self, output: &mut String) {
+        writeln!(output, "{}", self.columns.join(",")).unwrap();
+        for row in self.rows.iter() {
+            let row: Vec<String> = row.iter().map(|value| value.map_or(String::new(), |value| value.to_string())).collect();
+            writeln!(output, "{}", row.join(",")).unwrap();
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        json!({ "analysis": self.analysis, "columns": self.columns, "rows": self.rows })
+    }
+}
+
+fn parse_number(args: &[String], index: usize, flag: &str) -> Result<f64, Failure> {
+    let arg = args.get(index).ok_or_else(|| Failure::Usage(format!("{} is missing a value", flag)))?;
+    spice::parse_value(arg).ok_or_else(|| Failure::Usage(format!("{} is not a valid value for {}", arg, flag)))
+}
+
+// a number of points has to be a whole number of at least 1
+fn parse_count(args: &[String], index: usize, flag: &str) -> Result<u32, Failure> {
+    let value = parse_number(args, index, flag)?;
+    if value < 1.0 || value.fract() != 0.0 || value > u32::MAX as f64 {
+        return Err(Failure::Usage(format!("{} is not a valid number of points for {}", args[index], flag)));
+    }
+    Ok(value as u32)
+}
+
+// the file, the analyses in the order they were given and the output format
+fn parse_args(args: &[String]) -> Result<(String, Vec<Job>, Format), Failure> {
+    let mut file = None;
+    let mut jobs = Vec::new();
+    let mut format = Format::Table;
+    let mut index = 0;
+    while index < args.len() {
+        let arg = args[index].as_str();
+        let number = |offset: usize| parse_number(args, index + offset, arg);
+        let count = |offset: usize| parse_count(args, index + offset, arg);
+        index += match arg {
+            "--op" => {
+                jobs.push(Job::OperatingPoint);
+                1
+            }
+            "--dc" => {
+                let element = args.get(index + 1).ok_or_else(|| Failure::Usage("--dc is missing the element".to_string()))?;
+                let parameter = args.get(index + 2).ok_or_else(|| Failure::Usage("--dc is missing the parameter".to_string()))?;
+                let sweep = DcSweep { element: 0, parameter: parameter.clone(), start: number(3)?, stop: number(4)?, points: count(5)? };
+                jobs.push(Job::DcSweep { element: element.clone(), sweep });
+                6
+            }
+            "--tran" => {
+                let (step, stop) = (number(1)?, number(2)?);
+                if step <= 0.0 {
+                    return Err(Failure::Usage("the --tran step has to be positive".to_string()));
+                }
+                jobs.push(Job::Transient { step, stop });
+                3
+            }
+            "--ac" => {
+                let scale = match args.get(index + 1).map(|scale| scale.to_ascii_lowercase()).as_deref() {
+                    Some("dec") => SweepScale::Decade,
+                    Some("oct") => SweepScale::Octave,
+                    Some("lin") => SweepScale::Linear,
+                    _ => return Err(Failure::Usage("--ac needs a dec, oct or lin scale".to_string())),
+                };
+                jobs.push(Job::Ac(Sweep { scale, points: count(2)?, start: number(3)?, stop: number(4)? }));
+                5
+            }
+            "--format" => {
+                format = match args.get(index + 1).map(String::as_str) {
+                    Some("table") => Format::Table,
+                    Some("json") => Format::Json,
+                    Some("csv") => Format::Csv,
+                    _ => return Err(Failure::Usage("--format is one of table, json or csv".to_string())),
+                };
+                2
+            }
+            _ if arg.starts_with("--") => return Err(Failure::Usage(format!("unknown option {}", arg))),
+            _ if file.is_none() => {
+                file = Some(arg.to_string());
+                1
+            }
+            _ => return Err(Failure::Usage(format!("only one file can be solved, {} is a second one", arg))),
+        };
+    }
+    let file = file.ok_or_else(|| Failure::Usage("no file given".to_string()))?;
+    Ok((file, jobs, format))
+}
+
+// a spice deck keeps the names of its nodes and elements and brings its own analyses
+fn load(file: &str) -> Result<(Circuit, Labels, Vec<Job>), Failure> {
+    let path = Path::new(file);
+    let is_deck = path.extension().is_some_and(|extension| ["cir", "sp", "spice", "net"].iter().any(|deck| extension.eq_ignore_ascii_case(deck)));
+    let (circuit, names, jobs) = if is_deck {
+        let netlist = spice::load(path).map_err(|error| Failure::Load(error.to_string()))?;
+        let schematic = netlist.to_schematic(View { offset: [0.0, 0.0], grid_step: 20.0 });
+        let jobs = netlist.analyses.iter().map(|analysis| match analysis {
+            Analysis::OperatingPoint => Job::OperatingPoint,
+            Analysis::Transient { step, stop } => Job::Transient { step: *step, stop: *stop },
+            Analysis::Ac(sweep) => Job::Ac(*sweep),
+        }).collect();
+        (schematic.to_circuit(), Some(netlist.names(&schematic)), jobs)
+    } else {
+        let schematic = Schematic::load(path).map_err(|error| Failure::Load(error.to_string()))?;
+        (schematic.to_circuit(), None, Vec::new())
+    };
+
+    let nets = circuit.simplify(&mut String::new()).nets;
+    let labels = match names {
+        Some((nodes, elements)) => Labels {
+            nodes: nodes.into_iter().map(|(node, name)| (nets.net_of(node).unwrap_or(node), name)).collect(),
+            elements,
+            nets,
+        },
+        None => Labels {
+            // a net is named after its smallest node, so it is a node of the schematic
+            nodes: nets.iter().filter(|(_, net)| *net != 0).map(|(_, net)| (net, net.to_string())).collect(),
+            elements: circuit.elements().iter()
+                .filter(|(_, device)| !matches!(device.get_type(), ElementType::Wire | ElementType::Ground))
+                .map(|(id, _)| (*id, id.to_string()))
+                .collect(),
+            nets,
+        },
+    };
+    Ok((circuit, labels, jobs))
+}
+
+fn run(circuit: &Circuit, labels: &Labels, job: &Job) -> Result<Table, Failure> {
+    let traces = labels.traces();
+    let columns = |first: Option<String>| first.into_iter().chain(traces.iter().map(|(name, _)| name.clone())).collect();
+    let solver_failure = |analysis: &'static str, at: Option<f64>| move |error: SolverError| Failure::Solver { analysis, at, error };
+
+    match job {
+        Job::OperatingPoint => {
+            let solution = circuit.operating_point().map_err(solver_failure("op", None))?;
+            let mut table = Table::new("op", columns(None));
+            table.rows.push(traces.iter().map(|(_, trace)| trace.value(&solution)).collect());
+            Ok(table)
+        }
+        Job::DcSweep { element, sweep } => {
+            let id = labels.find_element(element).ok_or_else(|| Failure::Usage(format!("there is no element {} to sweep", element)))?;
+            let sweep = DcSweep { element: id, ..sweep.clone() };
+            let points = dc_sweep(circuit, &sweep).map_err(|error| match error {
+                DcSweepError::Solver { value, error } => Failure::Solver { analysis: "dc", at: Some(value), error },
+                error => Failure::Usage(error.to_string()),
+            })?;
+            let mut table = Table::new("dc", columns(Some(format!("{} {}", sweep.parameter, element))));
+            for point in points {
+                table.rows.push([Some(point.value)].into_iter().chain(traces.iter().map(|(_, trace)| trace.value(&point.solution))).collect());
+            }
+            Ok(table)
+        }
+        Job::Transient { step, stop } => {
+            let mut circuit = circuit.clone();
+            let mut table = Table::new("tran", columns(Some("time".to_string())));
+            let solution = circuit.initialize().map_err(solver_failure("tran", Some(0.0)))?;
+            table.rows.push([Some(0.0)].into_iter().chain(traces.iter().map(|(_, trace)| trace.value(&solution))).collect());
+            let mut time = 0.0;
+            // as in the editor, the steps are shortened to end on the breakpoints of the sources
+            while time < stop - step * 1.0e-6 {
+                let end = circuit.next_breakpoint(time).map_or(*stop, |breakpoint| breakpoint.min(*stop));
+                let timestep = step.min(end - time);
+                let info = StepInfo { timestep, time: time + timestep };
+                let solution = circuit.step(&info).map_err(solver_failure("tran", Some(info.time)))?;
+                time = info.time;
+                table.rows.push([Some(time)].into_iter().chain(traces.iter().map(|(_, trace)| trace.value(&solution))).collect());
+            }
+            Ok(table)
+        }
+        Job::Ac(sweep) => {
+            let solutions = circuit.ac_sweep(sweep).map_err(solver_failure("ac", None))?;
+            // only voltage sources and inductors have a current in the small-signal solution
+            let currents: Vec<(u32, &String)> = labels.elements.iter()
+                .filter(|(id, _)| solutions.first().is_some_and(|solution| solution.branch_current(**id).is_some()))
+                .map(|(id, name)| (*id, name))
+                .collect();
+            let mut names = vec!["frequency".to_string()];
+            for (_, name) in labels.nodes.iter() {
+                names.extend([format!("|V({})|", name), format!("ph V({})", name)]);
+            }
+            for (_, name) in currents.iter() {
+                names.extend([format!("|I({})|", name), format!("ph I({})", name)]);
+            }
+
+            let mut table = Table::new("ac", names);
+            for solution in solutions.iter() {
+                let mut row = vec![Some(solution.frequency)];
+                for net in labels.nodes.keys() {
+                    let voltage = solution.voltage(*net);
+                    row.extend([Some(voltage.norm()), Some(voltage.arg().to_degrees())]);
+                }
+                for (id, _) in currents.iter() {
+                    let current = solution.branch_current(*id);
+                    row.extend([current.map(|current| current.norm()), current.map(|current| current.arg().to_degrees())]);
+                }
+                table.rows.push(row);
+            }
+            Ok(table)
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    let (file, jobs, format) = match parse_args(&args) {
+        Ok(parsed) => parsed,
+        Err(failure) => {
+            // the format is not known if the arguments do not parse
+            let format = if args.windows(2).any(|pair| pair[0] == "--format" && pair[1] == "json") { Format::Json } else { Format::Table };
+            failure.report(format, None);
+            return ExitCode::from(failure.exit_code());
+        }
+    };
+    let (circuit, labels, deck_jobs) = match load(&file) {
+        Ok(loaded) => loaded,
+        Err(failure) => {
+            failure.report(format, None);
+            return ExitCode::from(failure.exit_code());
+        }
+    };
+
+    let jobs = if !jobs.is_empty() {
+        jobs
+    } else if !deck_jobs.is_empty() {
+        deck_jobs
+    } else {
+        vec![Job::OperatingPoint]
+    };
+    // the tables of the analyses before a failure are still printed
+    let mut tables = Vec::new();
+    let mut failure = None;
+    for job in jobs.iter() {
+        match run(&circuit, &labels, job) {
+            Ok(table) => tables.push(table),
+            Err(error) => {
+                failure = Some(error);
+                break;
+            }
+        }
+    }
+
+    let mut output = String::new();
+    match format {
+        Format::Json => {
+            let analyses: Vec<serde_json::Value> = tables.iter().map(Table::to_json).collect();
+            writeln!(output, "{}", serde_json::to_string_pretty(&json!({ "file": file, "analyses": analyses })).unwrap()).unwrap();
+        }
+        Format::Table | Format::Csv => {
+            for (index, table) in tables.iter().enumerate() {
+                if index > 0 {
+                    output.push('\n');
+                }
+                if format == Format::Csv {
+                    table.write_csv(&mut output);
+                } else {
+                    table.write_text(&mut output);
+                }
+            }
+        }
+    }
+    print!("{}", output);
+
+    match failure {
+        Some(failure) => {
+            failure.report(format, Some(&labels));
+            ExitCode::from(failure.exit_code())
+        }
+        None => ExitCode::SUCCESS,
+    }
+}
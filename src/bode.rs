@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use eframe::egui;
+use eframe::egui::{Align2, Color32, FontId, Pos2, Rect, Sense, Shape, Stroke, Vec2};
+use rusty_circuit::ac::{bode, cutoff_frequencies, input_phasor, phase_margin, BodePoint, Sweep, SweepScale};
+use rusty_circuit::circuit::Circuit;
+use crate::Node;
+
+const GRID_LINES: usize = 8;
+const MAGNITUDE_COLOR: Color32 = Color32::YELLOW;
+const PHASE_COLOR: Color32 = Color32::LIGHT_BLUE;
+
+// 1.59 kHz, 10 MHz
+fn format_frequency(frequency: f64) -> String {
+    let (scale, prefix) = [(1.0e9, "G"), (1.0e6, "M"), (1.0e3, "k")].into_iter()
+        .find(|(scale, _)| frequency.abs() >= *scale)
+        .unwrap_or((1.0, ""));
+    format!("{:.3} {}Hz", frequency / scale, prefix)
+}
+
+// magnitude and phase of a node against an ac source over a frequency sweep
+pub struct BodePlot {
+    pub open: bool,
+    pub sweep: Sweep,
+    // source the response is divided by
+    input: Option<u32>,
+    // grid point of the output node
+    output: Option<(i32, i32)>,
+    points: Vec<BodePoint>,
+    error: Option<String>,
+}
+
+impl BodePlot {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            sweep: Sweep { scale: SweepScale::Decade, points: 20, start: 1.0, stop: 1.0e6 },
+            input: None,
+            output: None,
+            points: Vec::new(),
+            error: None,
+        }
+    }
+
+    pub fn draw(&mut self, ctx: &egui::Context, nodes: &HashMap<(i32, i32), Node>, circuit: &Circuit) {
+        let mut open = self.open;
+        egui::Window::new("Bode plot").open(&mut open).default_size([600.0, 450.0]).show(ctx, |ui| {
+            self.draw_settings(ui, nodes, circuit);
+
+            if let Some(error) = &self.error {
+                ui.colored_label(Color32::RED, error);
+            }
+            if self.points.is_empty() {
+                return;
+            }
+
+            let height = ((ui.available_height() - 50.0) / 2.0).max(100.0);
+            let size = Vec2::new(ui.available_width(), height);
+            let (magnitude_rect, _) = ui.allocate_exact_size(size, Sense::hover());
+            self.draw_plot(ui, magnitude_rect, |point| point.decibels(), "dB", MAGNITUDE_COLOR);
+            let (phase_rect, _) = ui.allocate_exact_size(size, Sense::hover());
+            self.draw_plot(ui, phase_rect, |point| point.phase, "deg", PHASE_COLOR);
+
+            let cutoffs = cutoff_frequencies(&self.points);
+            if cutoffs.is_empty() {
+                ui.label("-3 dB: not in the sweep");
+            } else {
+                let cutoffs: Vec<String> = cutoffs.into_iter().map(format_frequency).collect();
+                ui.label(format!("-3 dB: {}", cutoffs.join(", ")));
+            }
+            match phase_margin(&self.points) {
+                Some((frequency, margin)) => ui.label(format!("Phase margin: {:.1} deg at {}", margin, format_frequency(frequency))),
+                None => ui.label("Phase margin: the gain does not fall through 0 dB"),
+            };
+        });
+        self.open = open;
+    }
+
+    fn draw_settings(&mut self, ui: &mut egui::Ui, nodes: &HashMap<(i32, i32), Node>, circuit: &Circuit) {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("sweep scale")
+                .selected_text(format!("{:?}", self.sweep.scale))
+                .show_ui(ui, |ui| {
+                    for scale in [SweepScale::Linear, SweepScale::Decade, SweepScale::Octave] {
+                        ui.selectable_value(&mut self.sweep.scale, scale, format!("{:?}", scale));
+                    }
+                });
+            let points = if self.sweep.scale == SweepScale::Linear { "Points: " } else { "Points/step: " };
+            ui.add(egui::DragValue::new(&mut self.sweep.points).range(1..=1000).prefix(points));
+            // a sweep by decades or octaves can not start at 0 Hz
+            let lowest = if self.sweep.scale == SweepScale::Linear { 0.0 } else { 1.0e-3 };
+            let start_speed = self.sweep.start.abs().max(1.0e-3) * 0.01;
+            ui.add(egui::DragValue::new(&mut self.sweep.start).speed(start_speed).range(lowest..=self.sweep.stop).prefix("Start: ").suffix(" Hz"));
+            let stop_speed = self.sweep.stop.abs().max(1.0e-3) * 0.01;
+            ui.add(egui::DragValue::new(&mut self.sweep.stop).speed(stop_speed).range(self.sweep.start..=1.0e12).prefix("Stop: ").suffix(" Hz"));
+        });
+
+        ui.horizontal(|ui| {
+            // only sources with an ac value can be the input
+            let sources: Vec<u32> = circuit.elements().keys().copied().filter(|id| input_phasor(circuit, *id).is_some()).collect();
+            egui::ComboBox::from_label("Input")
+                .selected_text(self.input.map_or("-".to_string(), |id| format!("Source {}", id)))
+                .show_ui(ui, |ui| {
+                    for id in sources {
+                        ui.selectable_value(&mut self.input, Some(id), format!("Source {}", id));
+                    }
+                });
+
+            let mut positions: Vec<_> = nodes.iter().collect();
+            positions.sort_by_key(|(_, node)| node.id);
+            egui::ComboBox::from_label("Output")
+                .selected_text(self.output.map_or("-".to_string(), |(x, y)| format!("V({}, {})", x, y)))
+                .show_ui(ui, |ui| {
+                    egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                        for (position, node) in positions {
+                            ui.selectable_value(&mut self.output, Some(*position), format!("Node {} at ({}, {})", node.id, position.0, position.1));
+                        }
+                    });
+                });
+
+            if ui.button("Sweep").clicked() {
+                self.run(nodes, circuit);
+            }
+        });
+    }
+
+    fn run(&mut self, nodes: &HashMap<(i32, i32), Node>, circuit: &Circuit) {
+        self.points.clear();
+        let (Some(input), Some(node)) = (self.input, self.output.and_then(|position| nodes.get(&position))) else {
+            self.error = Some("Pick an input source and an output node".to_string());
+            return;
+        };
+        if self.sweep.frequencies().is_empty() {
+            self.error = Some("The sweep has no frequencies, the start has to be above 0 Hz and below the stop".to_string());
+            return;
+        }
+        self.error = match circuit.ac_sweep(&self.sweep) {
+            Ok(solutions) => match bode(circuit, &solutions, node.id, input) {
+                Some(points) => {
+                    self.points = points;
+                    None
+                }
+                None => Some(format!("Source {} has no ac value", input)),
+            },
+            Err(error) => Some(error.to_string()),
+        };
+    }
+
+    // frequency on a log axis unless the sweep is linear
+    fn frequency_axis(&self) -> impl Fn(f64) -> f64 {
+        let logarithmic = self.sweep.scale != SweepScale::Linear;
+        move |frequency: f64| if logarithmic { frequency.max(f64::MIN_POSITIVE).log10() } else { frequency }
+    }
+
+    fn draw_plot(&self, ui: &mut egui::Ui, rect: Rect, value: impl Fn(&BodePoint) -> f64, unit: &str, color: Color32) {
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, Color32::BLACK);
+
+        let axis = self.frequency_axis();
+        let first = axis(self.points[0].frequency);
+        let last = axis(self.points[self.points.len() - 1].frequency);
+        let low = self.points.iter().map(&value).fold(f64::INFINITY, f64::min);
+        let high = self.points.iter().map(&value).fold(f64::NEG_INFINITY, f64::max);
+        // a flat response still gets a visible range
+        let (low, high) = if high - low < 1.0e-9 { (low - 1.0, high + 1.0) } else { (low, high) };
+        let width = if last > first { last - first } else { 1.0 };
+
+        let to_screen = |frequency: f64, value: f64| Pos2::new(
+            rect.left() + ((axis(frequency) - first) / width) as f32 * rect.width(),
+            rect.bottom() - ((value - low) / (high - low)) as f32 * rect.height(),
+        );
+
+        for i in 0..=GRID_LINES {
+            let fraction = i as f32 / GRID_LINES as f32;
+            let x = rect.left() + fraction * rect.width();
+            let y = rect.top() + fraction * rect.height();
+            painter.line_segment([Pos2::new(x, rect.top()), Pos2::new(x, rect.bottom())], Stroke::new(0.5, Color32::from_gray(60)));
+            painter.line_segment([Pos2::new(rect.left(), y), Pos2::new(rect.right(), y)], Stroke::new(0.5, Color32::from_gray(60)));
+        }
+
+        let points: Vec<Pos2> = self.points.iter().map(|point| to_screen(point.frequency, value(point))).collect();
+        painter.add(Shape::line(points, Stroke::new(1.5, color)));
+
+        for frequency in cutoff_frequencies(&self.points) {
+            let x = to_screen(frequency, low).x;
+            painter.line_segment([Pos2::new(x, rect.top()), Pos2::new(x, rect.bottom())], Stroke::new(1.0, Color32::GRAY));
+        }
+
+        let font = FontId::monospace(11.0);
+        painter.text(rect.left_top() + Vec2::new(4.0, 2.0), Align2::LEFT_TOP, format!("{:.2} {}", high, unit), font.clone(), Color32::GRAY);
+        painter.text(rect.left_bottom() + Vec2::new(4.0, -2.0), Align2::LEFT_BOTTOM, format!("{:.2} {}", low, unit), font.clone(), Color32::GRAY);
+        painter.text(rect.right_bottom() - Vec2::new(4.0, 2.0), Align2::RIGHT_BOTTOM, format!("{} - {}", format_frequency(self.points[0].frequency), format_frequency(self.points[self.points.len() - 1].frequency)), font, Color32::GRAY);
+    }
+}
@@ -0,0 +1,619 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use nalgebra::{Complex, DVector};
+use crate::ac::{AcSolution, Sweep};
+use crate::circuit_solver::{check, diagnose, simplify_graph, unknown_at, NetTable, Node, SolverError};
+use crate::sparse_matrix::{LuDecomposition, SparseMatrix};
+use crate::{Device, ElementType};
+
+// size of the time step that is being solved and the time at its end
+// elements with memory (capacitors, inductors) need the size to build their companion model, sources
+// with a waveform take their value at the time
+#[derive(Debug, Clone, Copy)]
+pub struct StepInfo {
+    pub timestep: f64,
+    pub time: f64,
+}
+
+impl StepInfo {
+    // an infinitely long step is the dc operating point: capacitors are open and inductors are shorted
+    pub const OPERATING_POINT: StepInfo = StepInfo { timestep: f64::INFINITY, time: 0.0 };
+
+    // what an ac frequency is checked like: a step of one period, so capacitors only block at 0 Hz
+    pub fn period_of(frequency: f64) -> StepInfo {
+        StepInfo { timestep: 1.0 / frequency, time: 0.0 }
+    }
+}
+
+// when the newton iterations of a nonlinear circuit count as converged: every unknown changed by less
+// than reltol times its value plus vntol (node voltages) or abstol (branch currents)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NewtonOptions {
+    pub abstol: f64,
+    pub reltol: f64,
+    pub vntol: f64,
+    pub max_iterations: u32,
+}
+
+impl Default for NewtonOptions {
+    // the defaults of spice
+    fn default() -> Self {
+        NewtonOptions { abstol: 1.0e-12, reltol: 1.0e-3, vntol: 1.0e-6, max_iterations: 100 }
+    }
+}
+
+impl NewtonOptions {
+    // the unknown that is furthest from converging, None if all of them are converged
+    // the first `node_count` unknowns are node voltages, the rest branch currents
+    fn worst_change(&self, values: &DVector<f64>, previous: &DVector<f64>, node_count: usize) -> Option<usize> {
+        (0..values.len())
+            .map(|index| {
+                let absolute = if index < node_count { self.vntol } else { self.abstol };
+                let tolerance = self.reltol * values[index].abs().max(previous[index].abs()) + absolute;
+                (index, (values[index] - previous[index]).abs() / tolerance)
+            })
+            .filter(|(_, ratio)| *ratio > 1.0)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index)
+    }
+}
+
+// intermediate results of a solve, for debugging
+#[derive(Debug, Clone, Default)]
+pub struct SolverInfo {
+    pub simplification: String,
+    pub nets: NetTable,
+    pub matrix: Option<SparseMatrix>,
+    pub vector: Option<DVector<f64>>,
+    pub solution: Option<DVector<f64>>,
+    // newton iterations, 0 for a linear circuit
+    pub iterations: u32,
+    // the matrix was the same as in the last solve and its factorization was used again
+    pub reused_factorization: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Solution {
+    voltages: BTreeMap<u32, f64>,
+    branch_currents: BTreeMap<u32, f64>,
+    currents: BTreeMap<u32, f64>,
+    powers: BTreeMap<u32, f64>,
+}
+
+impl Solution {
+    // voltage of a node against ground, nodes that are not part of the circuit read 0 V
+    pub fn voltage(&self, node: u32) -> f64 {
+        self.voltages.get(&node).copied().unwrap_or(0.0)
+    }
+
+    pub fn voltages(&self) -> &BTreeMap<u32, f64> {
+        &self.voltages
+    }
+
+    // current from the first to the second node of an element whose current is an unknown of the system
+    // (voltage sources and inductors)
+    pub fn branch_current(&self, element: u32) -> Option<f64> {
+        self.branch_currents.get(&element).copied()
+    }
+
+    // current from the first to the second node of any element, None for wires and closed switches
+    // that form a loop, their current can not be told apart
+    pub fn current(&self, element: u32) -> Option<f64> {
+        self.currents.get(&element).copied()
+    }
+
+    // power taken by the element, negative if it delivers power to the circuit
+    pub fn power(&self, element: u32) -> Option<f64> {
+        self.powers.get(&element).copied()
+    }
+}
+
+// a problem found before solving, with the elements and original nodes it is about
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub error: SolverError,
+    pub elements: BTreeSet<u32>,
+    pub nodes: BTreeSet<u32>,
+}
+
+// the circuit after the wires are merged and the grounded nodes are moved to the ground node
+#[derive(Debug, Clone)]
+pub struct SimplifiedCircuit {
+    // the ground node comes first
+    pub nodes: Vec<Node>,
+    pub elements: BTreeMap<u32, Box<dyn Device>>,
+    pub nets: NetTable,
+}
+
+impl SimplifiedCircuit {
+    // node of the simplified circuit that an original node ended up in
+    pub fn net_of(&self, node: u32) -> Option<u32> {
+        self.nets.net_of(node)
+    }
+}
+
+// the simplified circuit with the unknowns of its matrix numbered: the nodes first, then the branch
+// currents
+#[derive(Debug)]
+struct System {
+    nodes: Vec<Node>,
+    elements: BTreeMap<u32, Box<dyn Device>>,
+    nets: NetTable,
+    node_indices: HashMap<u32, usize>,
+    // element -> index of its first branch current among the branch currents
+    branches: HashMap<u32, u32>,
+    size: usize,
+}
+
+impl System {
+    // row of an original node, through the net it was merged into
+    fn index_of(&self, node: u32) -> Option<usize> {
+        self.node_indices.get(&self.nets.net_of(node)?).copied()
+    }
+
+    // row of the branch current of every element that has one
+    fn branch_indices(&self) -> impl Iterator<Item = (u32, usize)> + '_ {
+        self.branches.iter().map(|(id, branch)| (*id, self.nodes.len() + *branch as usize))
+    }
+
+    // numbers the branch currents and tells the elements where theirs and the ones controlling them are
+    fn assign_branches(&mut self) -> Result<(), SolverError> {
+        let mut voltage_nodes: u32 = 0;
+        self.branches.clear();
+        for (id, element) in self.elements.iter_mut() {
+            if element.get_voltage_source_count() > 0 {
+                element.set_voltage_node(voltage_nodes);
+                self.branches.insert(*id, voltage_nodes);
+                voltage_nodes += element.get_voltage_source_count();
+            }
+        }
+
+        // current controlled sources read the branch current of their controlling element
+        for (id, element) in self.elements.iter_mut() {
+            if let Some(control) = element.control_element() {
+                let branch = self.branches.get(&control).ok_or(SolverError::InvalidControl { element: *id, control })?;
+                element.set_control_branch(*branch);
+            }
+        }
+
+        // 1 for the ground node
+        self.size = self.nodes.len() + voltage_nodes as usize;
+        Ok(())
+    }
+
+    // takes the values and state of the elements of a circuit with the same topology, their nodes
+    // stay on the nets they were merged into
+    fn reload(&mut self, circuit: &Circuit) -> Result<(), SolverError> {
+        for (id, element) in self.elements.iter_mut() {
+            let nodes = element.get_nodes();
+            *element = circuit.elements[id].clone();
+            element.set_nodes(nodes);
+        }
+        self.assign_branches()
+    }
+
+    // solves the system, nonlinear elements are left linearized around the solution
+    // the factorization of the last matrix is used again if the matrix did not change
+    fn solve(
+        &mut self,
+        step: &StepInfo,
+        newton: &NewtonOptions,
+        factorization: &mut Option<(SparseMatrix, LuDecomposition)>,
+        info: &mut SolverInfo,
+    ) -> Result<DVector<f64>, SolverError> {
+        let System { nodes, elements, nets, node_indices, size, .. } = self;
+        let nonlinear = elements.values().any(|element| element.is_nonlinear());
+        let mut previous: Option<DVector<f64>> = None;
+        let mut iteration = 0;
+
+        // a linear circuit is done after the first solve
+        loop {
+            let mut admittance_matrix = SparseMatrix::new(*size);
+            let mut currents = DVector::<f64>::zeros(*size);
+
+            for element in elements.values() {
+                element.stamp_matrix(&mut admittance_matrix, &mut currents, node_indices, step);
+            }
+
+            // the ground node is the reference, its row and column are left out of the system
+            let reduced = admittance_matrix.minor(0);
+            info.reused_factorization = factorization.as_ref().is_some_and(|(matrix, _)| *matrix == reduced);
+            if !info.reused_factorization {
+                let lu = reduced.lu().map_err(|unknown| diagnose(nodes, elements, nets, step, unknown + 1))?;
+                *factorization = Some((reduced, lu));
+            }
+            let (_, lu) = factorization.as_ref().unwrap();
+            let values = lu.solve(&currents.clone().remove_row(0)).insert_row(0, 0.0);
+
+            info.matrix = Some(admittance_matrix);
+            info.vector = Some(currents);
+            if !nonlinear {
+                return Ok(values);
+            }
+
+            let iterate = Solution {
+                voltages: node_indices.iter().map(|(id, index)| (*id, values[*index])).collect(),
+                ..Solution::default()
+            };
+            let mut limited = false;
+            for element in elements.values_mut().filter(|element| element.is_nonlinear()) {
+                limited |= element.linearize(&iterate);
+            }
+
+            iteration += 1;
+            info.iterations = iteration;
+            let worst = previous.as_ref().and_then(|previous| newton.worst_change(&values, previous, nodes.len()));
+            if previous.is_some() && worst.is_none() && !limited {
+                return Ok(values);
+            }
+            if iteration >= newton.max_iterations {
+                let unknown = unknown_at(nodes, elements, worst.unwrap_or(0));
+                return Err(SolverError::NoConvergence { iterations: iteration, unknown });
+            }
+            previous = Some(values);
+        }
+    }
+}
+
+// what has to stay the same for the simplified circuit to be used again: the elements, their type,
+// their nodes and whether they are shorted
+type Topology = Vec<(u32, ElementType, Vec<u32>, bool)>;
+
+fn topology_of(circuit: &Circuit) -> Topology {
+    circuit.elements.iter()
+        .map(|(id, device)| (*id, device.get_type(), device.get_nodes(), device.shorted()))
+        .collect()
+}
+
+// solves one circuit after the other and keeps what it can of the last solve: the simplified circuit
+// while the topology stays the same, and the factorized matrix while the matrix stays the same, which
+// is the case when only source values change or a linear circuit is stepped with a fixed time step
+#[derive(Debug, Default)]
+pub struct Solver {
+    topology: Topology,
+    system: Option<System>,
+    simplification: String,
+    factorization: Option<(SparseMatrix, LuDecomposition)>,
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn solve(&mut self, circuit: &Circuit, step: &StepInfo, info: &mut SolverInfo) -> Result<Solution, SolverError> {
+        let topology = topology_of(circuit);
+        match self.system.as_mut() {
+            Some(system) if topology == self.topology => system.reload(circuit)?,
+            _ => {
+                self.system = None;
+                self.factorization = None;
+                self.simplification.clear();
+                self.system = Some(circuit.system(step, &mut self.simplification)?);
+                self.topology = topology;
+            }
+        }
+        info.simplification = self.simplification.clone();
+        let system = self.system.as_mut().unwrap();
+        info.nets = system.nets.clone();
+        let values = system.solve(step, &circuit.newton, &mut self.factorization, info)?;
+
+        // map the nodes back to the original nodes
+        let mut solution = Solution::default();
+        for (id, _) in system.nets.iter() {
+            solution.voltages.insert(id, system.index_of(id).map_or(0.0, |index| values[index]));
+        }
+        for (id, index) in system.branch_indices() {
+            solution.branch_currents.insert(id, values[index]);
+        }
+
+        info.solution = Some(values);
+        circuit.element_currents(&system.elements, &mut solution, step);
+        Ok(solution)
+    }
+}
+
+// node 0 is ground, any other node id can be used freely
+// nodes of Ground elements are connected to ground too
+#[derive(Debug, Clone, Default)]
+pub struct Circuit {
+    elements: BTreeMap<u32, Box<dyn Device>>,
+    pub newton: NewtonOptions,
+}
+
+impl Circuit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // adds an element with the next free id and returns the id
+    pub fn add(&mut self, device: impl Device + 'static) -> u32 {
+        let id = self.elements.keys().next_back().map_or(1, |id| id + 1);
+        self.elements.insert(id, Box::new(device));
+        id
+    }
+
+    pub fn insert(&mut self, id: u32, device: Box<dyn Device>) {
+        self.elements.insert(id, device);
+    }
+
+    pub fn remove(&mut self, id: u32) -> Option<Box<dyn Device>> {
+        self.elements.remove(&id)
+    }
+
+    pub fn element(&self, id: u32) -> Option<&dyn Device> {
+        self.elements.get(&id).map(|device| device.as_ref())
+    }
+
+    pub fn element_mut(&mut self, id: u32) -> Option<&mut Box<dyn Device>> {
+        self.elements.get_mut(&id)
+    }
+
+    pub fn elements(&self) -> &BTreeMap<u32, Box<dyn Device>> {
+        &self.elements
+    }
+
+    pub fn operating_point(&self) -> Result<Solution, SolverError> {
+        self.solve(&StepInfo::OPERATING_POINT)
+    }
+
+    pub fn solve(&self, step: &StepInfo) -> Result<Solution, SolverError> {
+        self.solve_with_info(step, &mut SolverInfo::default())
+    }
+
+    // solves one time step and lets the elements remember the result for the next one
+    pub fn step(&mut self, step: &StepInfo) -> Result<Solution, SolverError> {
+        let solution = self.solve(step)?;
+        for device in self.elements.values_mut() {
+            device.update_state(&solution, step);
+        }
+        Ok(solution)
+    }
+
+    // solves the dc operating point and lets the elements start the next time step from it, as a spice
+    // transient analysis does without uic
+    pub fn initialize(&mut self) -> Result<Solution, SolverError> {
+        let solution = self.operating_point()?;
+        for (id, device) in self.elements.iter_mut() {
+            device.set_operating_point(&solution, solution.branch_current(*id));
+        }
+        Ok(solution)
+    }
+
+    // moves the operating point of the nonlinear elements to a solution, newton starts from there the
+    // next time the circuit is solved
+    pub fn linearize(&mut self, solution: &Solution) {
+        for device in self.elements.values_mut().filter(|device| device.is_nonlinear()) {
+            device.linearize(solution);
+        }
+    }
+
+    // first time after the given one that a step should end on
+    pub fn next_breakpoint(&self, time: f64) -> Option<f64> {
+        self.elements.values().filter_map(|device| device.next_breakpoint(time)).min_by(f64::total_cmp)
+    }
+
+    pub fn reset_state(&mut self) {
+        for device in self.elements.values_mut() {
+            device.reset_state();
+        }
+    }
+
+    pub fn simplify(&self, debug_info: &mut String) -> SimplifiedCircuit {
+        let mut connections: BTreeMap<u32, BTreeSet<u32>> = BTreeMap::from([(0, BTreeSet::new())]);
+        for (id, device) in self.elements.iter() {
+            for node in device.get_nodes() {
+                connections.entry(node).or_default().insert(*id);
+            }
+        }
+        // the ground node comes first
+        let mut nodes: Vec<Node> = connections.iter()
+            .map(|(id, connections)| Node { id: *id, connections: connections.clone() })
+            .collect();
+
+        let mut elements = self.elements.clone();
+        let nets = simplify_graph(&mut nodes, &mut elements, debug_info);
+        SimplifiedCircuit { nodes, elements, nets }
+    }
+
+    // simplifies the circuit and numbers the unknowns of its matrix
+    fn unchecked_system(&self, debug_info: &mut String) -> Result<System, SolverError> {
+        let SimplifiedCircuit { nodes, elements, nets } = self.simplify(debug_info);
+        let node_indices: HashMap<u32, usize> = nodes.iter().enumerate().map(|(index, node)| (node.id, index)).collect();
+        let mut system = System { nodes, elements, nets, node_indices, branches: HashMap::new(), size: 0 };
+        system.assign_branches()?;
+        Ok(system)
+    }
+
+    // a circuit that can not have a solution is not solved at all, instead of waiting for its matrix
+    // to turn out singular
+    fn system(&self, step: &StepInfo, debug_info: &mut String) -> Result<System, SolverError> {
+        let system = self.unchecked_system(debug_info)?;
+        match check(&system.nodes, &system.elements, &system.nets, step).into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(system),
+        }
+    }
+
+    // everything that keeps the operating point from being solved whatever the values, empty if it can be solved
+    pub fn check(&self) -> Vec<Warning> {
+        self.check_at(&StepInfo::OPERATING_POINT)
+    }
+
+    // the same for a time step, a node reached only through capacitors is not floating there
+    pub fn check_at(&self, step: &StepInfo) -> Vec<Warning> {
+        match self.unchecked_system(&mut String::new()) {
+            Ok(system) => check(&system.nodes, &system.elements, &system.nets, step).into_iter()
+                .map(|error| self.warning(error, &system.nets))
+                .collect(),
+            Err(error) => vec![self.warning(error, &NetTable::default())],
+        }
+    }
+
+    fn warning(&self, error: SolverError, nets: &NetTable) -> Warning {
+        // elements with a node in the part of the circuit
+        let touching = |nodes: &BTreeSet<u32>| -> BTreeSet<u32> {
+            self.elements.iter()
+                .filter(|(_, device)| device.get_nodes().iter().any(|node| nodes.contains(node)))
+                .map(|(id, _)| *id)
+                .collect()
+        };
+        let (elements, nodes) = match &error {
+            SolverError::FloatingSubnet { nodes } => {
+                let nodes: BTreeSet<u32> = nodes.iter().copied().collect();
+                (touching(&nodes), nodes)
+            }
+            SolverError::CurrentSourceOpen { element, nodes } => {
+                let nodes: BTreeSet<u32> = nodes.iter().copied().collect();
+                let mut elements = touching(&nodes);
+                elements.insert(*element);
+                (elements, nodes)
+            }
+            // the wires and closed switches that short the sources are merged into their nets
+            SolverError::VoltageSourceLoop { elements: sources } => {
+                let source_nets: BTreeSet<u32> = sources.iter()
+                    .filter_map(|id| self.elements.get(id))
+                    .flat_map(|device| device.get_nodes())
+                    .filter_map(|node| nets.net_of(node))
+                    .collect();
+                let nodes: BTreeSet<u32> = nets.iter().filter(|(_, net)| source_nets.contains(net)).map(|(node, _)| node).collect();
+                let shorts = self.elements.iter()
+                    .filter(|(_, device)| device.shorted() && device.get_nodes().iter().all(|node| nodes.contains(node)))
+                    .map(|(id, _)| *id);
+                (sources.iter().copied().chain(shorts).collect(), nodes)
+            }
+            SolverError::InvalidControl { element, control } => (BTreeSet::from([*element, *control]), BTreeSet::new()),
+            SolverError::SingularMatrix { .. } | SolverError::NoConvergence { .. } | SolverError::InvalidSweep { .. } => (BTreeSet::new(), BTreeSet::new()),
+        };
+        Warning { error, elements, nodes }
+    }
+
+    // builds and solves the admittance matrix for one time step
+    pub fn solve_with_info(&self, step: &StepInfo, info: &mut SolverInfo) -> Result<Solution, SolverError> {
+        Solver::new().solve(self, step, info)
+    }
+
+    // small-signal solve at every frequency of the sweep, nonlinear elements are linearized around the
+    // operating point first
+    pub fn ac_sweep(&self, sweep: &Sweep) -> Result<Vec<AcSolution>, SolverError> {
+        let frequencies = sweep.frequencies();
+        if sweep.points == 0 || frequencies.is_empty() || frequencies.iter().any(|frequency| !frequency.is_finite() || *frequency < 0.0) {
+            return Err(SolverError::InvalidSweep { sweep: *sweep });
+        }
+        let step = StepInfo::OPERATING_POINT;
+        let nonlinear = self.elements.values().any(|element| element.is_nonlinear());
+        // without an operating point to solve, the circuit only has to hold together at the frequencies
+        let lowest = frequencies.iter().copied().fold(f64::INFINITY, f64::min);
+        let checked = if nonlinear { step } else { StepInfo::period_of(lowest) };
+        let mut system = self.system(&checked, &mut String::new())?;
+        if nonlinear {
+            system.solve(&step, &self.newton, &mut None, &mut SolverInfo::default())?;
+        }
+
+        // the dc values of the sources are not part of the small-signal circuit
+        let mut operating_point = SparseMatrix::new(system.size);
+        let mut unused = DVector::<f64>::zeros(system.size);
+        for element in system.elements.values() {
+            element.stamp_matrix(&mut operating_point, &mut unused, &system.node_indices, &step);
+        }
+
+        let mut solutions = Vec::new();
+        for frequency in frequencies {
+            let mut matrix = operating_point.to_complex();
+            let mut vector = DVector::<Complex<f64>>::zeros(system.size);
+            for element in system.elements.values() {
+                element.stamp_ac(&mut matrix, &mut vector, &system.node_indices, frequency);
+            }
+
+            let lu = matrix.minor(0).lu()
+                .map_err(|unknown| diagnose(&system.nodes, &system.elements, &system.nets, &StepInfo::period_of(frequency), unknown + 1))?;
+            let values = lu.solve(&vector.remove_row(0)).insert_row(0, Complex::default());
+
+            let mut solution = AcSolution { frequency, ..AcSolution::default() };
+            for (id, _) in system.nets.iter() {
+                solution.voltages.insert(id, system.index_of(id).map_or(Complex::default(), |index| values[index]));
+            }
+            for (id, index) in system.branch_indices() {
+                solution.branch_currents.insert(id, values[index]);
+            }
+            solutions.push(solution);
+        }
+        Ok(solutions)
+    }
+
+    fn element_currents(&self, simplified: &BTreeMap<u32, Box<dyn Device>>, solution: &mut Solution, step: &StepInfo) {
+        // current leaving every node through the elements whose current is known
+        let mut outflow: HashMap<u32, f64> = HashMap::new();
+        let mut shorted = Vec::new();
+        // elements without nodes carry no current
+        for (id, device) in self.elements.iter().filter(|(_, device)| !device.get_nodes().is_empty()) {
+            let (first, second) = device.current_nodes();
+            if device.shorted() || device.get_type() == ElementType::Ground {
+                shorted.push((*id, first, second));
+                continue;
+            }
+            // the simplifier can leave elements out, their current is unknown
+            let Some(model) = simplified.get(id) else {
+                continue;
+            };
+            let current = solution.branch_current(*id).unwrap_or_else(|| model.current(solution, step));
+            solution.currents.insert(*id, current);
+            let terminal_currents = model.terminal_currents(solution, step);
+            for (node, current) in device.get_nodes().into_iter().zip(terminal_currents.iter().flatten()) {
+                *outflow.entry(node).or_default() += current;
+            }
+            let between = if terminal_currents.is_some() { solution.branch_current(*id) } else { Some(current) };
+            if let Some(current) = between {
+                *outflow.entry(first).or_default() += current;
+                *outflow.entry(second).or_default() -= current;
+            }
+        }
+
+        // shorted elements form trees inside their nets, their currents are found from the leaves inwards:
+        // the only unsolved element at a leaf carries whatever the other elements leave there
+        let mut incident: BTreeMap<u32, Vec<usize>> = BTreeMap::new();
+        for (index, (_, first, second)) in shorted.iter().enumerate().filter(|(_, (_, first, second))| first != second) {
+            incident.entry(*first).or_default().push(index);
+            incident.entry(*second).or_default().push(index);
+        }
+        let mut degree: HashMap<u32, usize> = incident.iter().map(|(node, elements)| (*node, elements.len())).collect();
+        let mut leaves: VecDeque<u32> = incident.iter().filter(|(_, elements)| elements.len() == 1).map(|(node, _)| *node).collect();
+        let mut solved = vec![false; shorted.len()];
+        while let Some(leaf) = leaves.pop_front() {
+            // both ends of the last element of a tree can be queued
+            let Some(index) = incident[&leaf].iter().copied().find(|index| !solved[*index]) else {
+                continue;
+            };
+            solved[index] = true;
+
+            let (id, first, second) = shorted[index];
+            let current = if leaf == first {
+                -outflow.get(&first).copied().unwrap_or(0.0)
+            } else {
+                outflow.get(&second).copied().unwrap_or(0.0)
+            };
+            solution.currents.insert(id, current);
+            *outflow.entry(first).or_default() += current;
+            *outflow.entry(second).or_default() -= current;
+
+            let other = if leaf == first { second } else { first };
+            let remaining = degree.get_mut(&other).unwrap();
+            *remaining -= 1;
+            if *remaining == 1 {
+                leaves.push_back(other);
+            }
+        }
+
+        for (id, device) in self.elements.iter().filter(|(_, device)| !device.get_nodes().is_empty()) {
+            let (first, second) = device.current_nodes();
+            let across = solution.voltage(first) - solution.voltage(second);
+            // every terminal current takes its power from the voltage of its node, a branch current next
+            // to them from the voltage between the current nodes
+            if let Some(currents) = simplified.get(id).and_then(|simplified| simplified.terminal_currents(solution, step)) {
+                let terminals: f64 = device.get_nodes().into_iter().zip(currents).map(|(node, current)| solution.voltage(node) * current).sum();
+                solution.powers.insert(*id, terminals + solution.branch_current(*id).map_or(0.0, |current| across * current));
+            } else if let Some(current) = solution.current(*id) {
+                solution.powers.insert(*id, across * current);
+            }
+        }
+    }
+}
@@ -1,141 +1,381 @@
-use std::collections::{HashMap, HashSet, BTreeSet, BTreeMap};
-use crate::{CircuitElement, ElementType, Node};
+use std::collections::{HashMap, BTreeSet, BTreeMap, VecDeque};
+use std::fmt;
+use crate::{Device, ElementType};
+use crate::ac::Sweep;
+use crate::circuit::StepInfo;
 
-// does the following things:
-// - remove dangling nodes and elements
-// - simplify wires (interconnected wires should be a single node)
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub id: u32,
+    // ids of the elements connected to the node
+    pub connections: BTreeSet<u32>,
+}
+
+// every original node and the net it ended up in, a net is named after the smallest node id in it,
+// so the ground node 0 is always the name of the grounded net
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetTable {
+    nets: BTreeMap<u32, u32>,
+}
+
+impl NetTable {
+    pub fn net_of(&self, node: u32) -> Option<u32> {
+        self.nets.get(&node).copied()
+    }
+
+    // (original node, net) pairs, ordered by the original node
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.nets.iter().map(|(node, net)| (*node, *net))
+    }
+
+    // net -> the other nodes that were merged into it, nets that are a single node are left out
+    pub fn merged(&self) -> BTreeMap<u32, BTreeSet<u32>> {
+        let mut merged: BTreeMap<u32, BTreeSet<u32>> = BTreeMap::new();
+        for (node, net) in self.iter().filter(|(node, net)| node != net) {
+            merged.entry(net).or_default().insert(node);
+        }
+        merged
+    }
+}
+
+// disjoint sets of node ids, the root of a set is its smallest id
+#[derive(Debug, Default)]
+struct UnionFind {
+    parent: HashMap<u32, u32>,
+}
+
+impl UnionFind {
+    fn find(&mut self, node: u32) -> u32 {
+        let mut node = node;
+        loop {
+            let parent = *self.parent.entry(node).or_insert(node);
+            if parent == node {
+                return node;
+            }
+            // path halving, every visited node skips to its grandparent
+            let grandparent = *self.parent.entry(parent).or_insert(parent);
+            self.parent.insert(node, grandparent);
+            node = grandparent;
+        }
+    }
+
+    fn union(&mut self, a: u32, b: u32) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent.insert(a.max(b), a.min(b));
+        }
+    }
+}
+
+// merges the nodes that are shorted together (wires, closed switches) into nets and the nodes of
+// ground elements into the ground net, in a single pass over the elements:
+// - the elements are moved onto the nets, shorted elements end up with the same net on all their nodes
+// - elements without nodes are removed
+// - nodes is replaced by the nets, each with the connections of all its nodes, the ground net (0)
+//   comes first
 pub fn simplify_graph(
     nodes: &mut Vec<Node>,
-    elements: &mut BTreeMap<u32, Box<dyn CircuitElement>>,
+    elements: &mut BTreeMap<u32, Box<dyn Device>>,
     debug_info: &mut String,
-) -> BTreeMap<u32, BTreeSet<u32>> {
-    let mut pass = 0;
-    let mut changed = true;
+) -> NetTable {
+    let mut sets = UnionFind::default();
+    for element in elements.values() {
+        let element_nodes = element.get_nodes();
+        if element.shorted() {
+            for pair in element_nodes.windows(2) {
+                sets.union(pair[0], pair[1]);
+            }
+        } else if element.get_type() == ElementType::Ground {
+            for node in element_nodes {
+                sets.union(node, 0);
+            }
+        }
+    }
+
+    for element in elements.values_mut() {
+        let element_nodes = element.get_nodes();
+        let nets: Vec<u32> = element_nodes.iter().map(|node| sets.find(*node)).collect();
+        if nets != element_nodes {
+            element.set_nodes(nets);
+        }
+    }
 
-    let mut nodes_map: BTreeMap<u32, BTreeSet<u32>> = BTreeMap::new();
+    let count = elements.len();
+    elements.retain(|_, element| !element.get_nodes().is_empty());
+    if elements.len() != count {
+        *debug_info += format!("Removed {} elements\n", count - elements.len()).as_str();
+    }
 
-    while changed {
-        pass += 1;
+    let mut table = NetTable::default();
+    let mut nets: BTreeMap<u32, BTreeSet<u32>> = BTreeMap::from([(0, BTreeSet::new())]);
+    for node in nodes.drain(..) {
+        let net = sets.find(node.id);
+        if net != node.id {
+            *debug_info += format!("Merging node {} -> {}\n", node.id, net).as_str();
+        }
+        table.nets.insert(node.id, net);
+        nets.entry(net).or_default().extend(node.connections.into_iter().filter(|id| elements.contains_key(id)));
+    }
+    *nodes = nets.into_iter().map(|(id, connections)| Node { id, connections }).collect();
 
-        *debug_info += format!("\nPass {}:\n", pass).as_str();
+    table
+}
 
-        changed = false;
+#[derive(Debug, Clone, PartialEq)]
+pub enum Unknown {
+    NodeVoltage(u32),
+    BranchCurrent(u32),
+}
 
-        // simplify wires
-        let mut nodes_to_remove = HashSet::new();
-        let mut node_connections: HashMap<u32, Vec<u32>> = HashMap::new();
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolverError {
+    // nodes that have no conductive path to ground
+    FloatingSubnet { nodes: Vec<u32> },
+    // voltage sources that form a loop (also two sources in parallel or a source shorted by a wire), at
+    // the operating point inductors take part like sources of 0 V
+    VoltageSourceLoop { elements: Vec<u32> },
+    // current source that drives current into a part of the circuit it can not leave
+    CurrentSourceOpen { element: u32, nodes: Vec<u32> },
+    // singular matrix that none of the checks above explain
+    SingularMatrix { unknown: Unknown },
+    // the newton iterations of a nonlinear circuit did not settle, unknown changed the most in the last one
+    NoConvergence { iterations: u32, unknown: Unknown },
+    // current controlled source whose controlling element has no branch current (or does not exist)
+    InvalidControl { element: u32, control: u32 },
+    // ac sweep without frequencies, or with some that are negative or not finite
+    InvalidSweep { sweep: Sweep },
+}
 
-        // collect nodes and connections to update
-        for i in 1..nodes.len() {
-            let node = &nodes[i];
-            if nodes_to_remove.contains(&node.id) {
-                continue;
-            }
-            for j in 1..nodes.len() {
-                if i == j {
-                    continue;
-                }
-                let other = &nodes[j];
-                if nodes_to_remove.contains(&other.id) {
-                    continue;
-                }
-                let common_connections: Vec<u32> = node.connections.iter()
-                    .filter(|&conn| other.connections.contains(conn))
-                    .copied()
-                    .collect();
-                for connection in common_connections {
-                    if let Some(element) = elements.get(&connection) {
-                        if element.shorted() {
-                            *debug_info += format!("Merging node {} -> {}\n", other.id, node.id).as_str();
-                            merge_nodes(node, other, &mut nodes_map, elements, &mut node_connections, &mut nodes_to_remove, debug_info);
-                            changed = true;
-                        }
-                    }
-                }
-            }
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolverError::FloatingSubnet { nodes } => write!(f, "Nodes {:?} are not connected to ground", nodes),
+            SolverError::VoltageSourceLoop { elements } if elements.len() == 1 => write!(f, "Voltage source {} is shorted", elements[0]),
+            SolverError::VoltageSourceLoop { elements } => write!(f, "Voltage sources or inductors {:?} form a loop or are connected in parallel", elements),
+            SolverError::CurrentSourceOpen { element, nodes } => write!(f, "Current source {} has no return path from nodes {:?}", element, nodes),
+            SolverError::SingularMatrix { unknown: Unknown::NodeVoltage(node) } => write!(f, "The voltage of node {} can not be determined", node),
+            SolverError::SingularMatrix { unknown: Unknown::BranchCurrent(element) } => write!(f, "The current of element {} can not be determined", element),
+            SolverError::NoConvergence { iterations, unknown: Unknown::NodeVoltage(node) } => write!(f, "No solution found after {} iterations, the voltage of node {} does not settle", iterations, node),
+            SolverError::NoConvergence { iterations, unknown: Unknown::BranchCurrent(element) } => write!(f, "No solution found after {} iterations, the current of element {} does not settle", iterations, element),
+            SolverError::InvalidControl { element, control } => write!(f, "Element {} can not control source {}, only voltage sources and inductors can", control, element),
+            SolverError::InvalidSweep { sweep } => write!(f, "The sweep of {} points from {} Hz to {} Hz has no valid frequencies", sweep.points, sweep.start, sweep.stop),
         }
+    }
+}
 
-        // apply node connections updates
-        for node in nodes.iter_mut() {
-            if let Some(connections) = node_connections.remove(&node.id) {
-                node.connections.extend(connections);
-                *debug_info += format!("Updated node {} connections: {:?}\n", node.id, node.connections).as_str();
-            }
+// explains why the matrix of the simplified circuit is singular
+// `unknown` is the row of the matrix (ground node included) that had no pivot
+pub fn diagnose(
+    nodes: &[Node],
+    elements: &BTreeMap<u32, Box<dyn Device>>,
+    nets: &NetTable,
+    step: &StepInfo,
+    unknown: usize,
+) -> SolverError {
+    check(nodes, elements, nets, step).into_iter().next()
+        .unwrap_or_else(|| SolverError::SingularMatrix { unknown: unknown_at(nodes, elements, unknown) })
+}
+
+// every problem of the simplified circuit that makes its matrix singular whatever the element values:
+// voltage source loops, and parts of the circuit without a path to ground, with or without a current
+// source driving them, in a step of the given size
+pub fn check(nodes: &[Node], elements: &BTreeMap<u32, Box<dyn Device>>, nets: &NetTable, step: &StepInfo) -> Vec<SolverError> {
+    let mut errors = Vec::new();
+    let original_nodes = |ids: &BTreeSet<u32>| -> Vec<u32> {
+        nets.iter().filter(|(_, net)| ids.contains(net)).map(|(node, _)| node).collect()
+    };
+
+    // voltage sources whose nodes are already connected by other voltage sources close a loop, at the
+    // operating point inductors are shorts and count as sources of 0 V
+    let mut source_edges: HashMap<u32, Vec<(u32, u32)>> = HashMap::new();
+    for (id, element) in elements.iter().filter(|(_, element)| match element.get_type() {
+        ElementType::DCVoltageSource | ElementType::Vcvs | ElementType::Ccvs | ElementType::OpAmp => true,
+        ElementType::Inductor => step.timestep.is_infinite(),
+        _ => false,
+    }) {
+        let (node1, node2) = element.current_nodes();
+        // the source that closes a loop is left out of the graph, so every loop is found once
+        if let Some(mut path) = find_path(&source_edges, node1, node2) {
+            path.push(*id);
+            errors.push(SolverError::VoltageSourceLoop { elements: path });
+            continue;
         }
+        source_edges.entry(node1).or_default().push((node2, *id));
+        source_edges.entry(node2).or_default().push((node1, *id));
+    }
 
-        // remove nodes
-        nodes.retain(|node| {
-            !nodes_to_remove.contains(&node.id)
-        });
+    // everything that conducts in the step, grouped into connected parts
+    let mut edges: HashMap<u32, Vec<(u32, u32)>> = HashMap::new();
+    for (id, element) in elements.iter().filter(|(_, element)| element.is_conductive_at(step)) {
+        let element_nodes = element.conductive_nodes();
+        for pair in element_nodes.windows(2) {
+            edges.entry(pair[0]).or_default().push((pair[1], *id));
+            edges.entry(pair[1]).or_default().push((pair[0], *id));
+        }
+    }
 
-        // remove dangling nodes
-        /*let len_old = nodes.len();
-        nodes.retain(|node| {
-            node.connections.len() > 1
-        });
+    let grounded = reachable(&edges, nodes[0].id);
+    let mut visited = grounded.clone();
+    for node in nodes.iter() {
+        if visited.contains(&node.id) {
+            continue;
+        }
+        let subnet = reachable(&edges, node.id);
+        visited.extend(subnet.iter().copied());
 
-        if nodes.len() != len_old {
-            *debug_info += format!("Removed {} dangling nodes\n", len_old - nodes.len()).as_str();
-            changed = true;
-        }*/
-
-        // remove elements that are not connected to any nodes
-        let len_old = elements.len();
-        elements.retain(|id, element| {
-            nodes.iter().filter(|node| {
-                node.connections.contains(&id)
-            }).take(2).count() > 0
+        let current_source = elements.iter().find(|(_, element)| {
+            matches!(element.get_type(), ElementType::CurrentSource | ElementType::Vccs | ElementType::Cccs)
+                && element.conductive_nodes().iter().any(|id| subnet.contains(id))
         });
+        errors.push(match current_source {
+            Some((id, _)) => SolverError::CurrentSourceOpen { element: *id, nodes: original_nodes(&subnet) },
+            None => SolverError::FloatingSubnet { nodes: original_nodes(&subnet) },
+        });
+    }
+    errors
+}
+
+// what a row of the matrix of the simplified circuit (ground node included) stands for
+pub fn unknown_at(nodes: &[Node], elements: &BTreeMap<u32, Box<dyn Device>>, index: usize) -> Unknown {
+    if index < nodes.len() {
+        return Unknown::NodeVoltage(nodes[index].id);
+    }
 
-        if elements.len() != len_old {
-            *debug_info += format!("Removed {} elements\n", len_old - elements.len()).as_str();
-            changed = true;
-        }
-
-        // remove connections in nodes whose elements were removed
-        for node in nodes.iter_mut() {
-            let len_old = node.connections.len();
-            node.connections.retain(|connection| {
-                elements.keys().any(|id| {
-                    *id == *connection
-                })
-            });
-            if node.connections.len() != len_old {
-                *debug_info += format!("Removed {} connections in node {}\n", len_old - node.connections.len(), node.id).as_str();
-                changed = true;
+    let mut voltage_node = nodes.len() as u32;
+    for (id, element) in elements.iter() {
+        voltage_node += element.get_voltage_source_count();
+        if index < voltage_node as usize {
+            return Unknown::BranchCurrent(*id);
+        }
+    }
+    Unknown::NodeVoltage(nodes[0].id)
+}
+
+fn reachable(edges: &HashMap<u32, Vec<(u32, u32)>>, start: u32) -> BTreeSet<u32> {
+    let mut visited = BTreeSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        for (next, _) in edges.get(&node).into_iter().flatten() {
+            if visited.insert(*next) {
+                queue.push_back(*next);
             }
         }
+    }
+    visited
+}
+
+// elements on a path between two nodes, None if they are not connected
+fn find_path(edges: &HashMap<u32, Vec<(u32, u32)>>, from: u32, to: u32) -> Option<Vec<u32>> {
+    let mut previous: HashMap<u32, (u32, u32)> = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    while let Some(node) = queue.pop_front() {
+        if node == to {
+            let mut path = Vec::new();
+            let mut node = to;
+            while node != from {
+                let (before, element) = previous[&node];
+                path.push(element);
+                node = before;
+            }
+            return Some(path);
+        }
+        for (next, element) in edges.get(&node).into_iter().flatten() {
+            if *next != from && !previous.contains_key(next) {
+                previous.insert(*next, (node, *element));
+                queue.push_back(*next);
+            }
+        }
+    }
+    None
+}
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::{Ground, Resistor, Wire};
 
-        if !changed {
-            *debug_info += "Nothing to do\n";
+    // the nodes of the elements, each connected to the elements that touch it
+    fn nodes_of(elements: &BTreeMap<u32, Box<dyn Device>>) -> Vec<Node> {
+        let mut nodes: BTreeMap<u32, BTreeSet<u32>> = BTreeMap::new();
+        for (id, element) in elements.iter() {
+            for node in element.get_nodes() {
+                nodes.entry(node).or_default().insert(*id);
+            }
         }
+        nodes.into_iter().map(|(id, connections)| Node { id, connections }).collect()
     }
 
-    nodes_map
-}
+    #[test]
+    fn union_find_roots_are_the_smallest_ids() {
+        let mut sets = UnionFind::default();
+        sets.union(5, 3);
+        sets.union(3, 7);
+        sets.union(9, 8);
+        assert_eq!([sets.find(3), sets.find(5), sets.find(7)], [3, 3, 3]);
+        assert_eq!([sets.find(8), sets.find(9)], [8, 8]);
+        // a node that was never merged is a set of its own
+        assert_eq!(sets.find(4), 4);
 
-fn merge_nodes(node: &Node, other: &Node, nodes_map: &mut BTreeMap<u32, BTreeSet<u32>>, elements: &mut BTreeMap<u32, Box<dyn CircuitElement>>, node_connections: &mut HashMap<u32, Vec<u32>>, nodes_to_remove: &mut HashSet<u32>, debug_info: &mut String) {
-    nodes_map.entry(node.id).or_default().insert(other.id);
+        // joining two sets keeps the smaller root, whichever side it is on
+        sets.union(9, 7);
+        assert_eq!([sets.find(3), sets.find(5), sets.find(7), sets.find(8), sets.find(9)], [3; 5]);
+    }
 
-    if let Some(set) = nodes_map.remove(&other.id) {
-        *debug_info += format!("Set: {:?}\n", set).as_str();
-        nodes_map.entry(node.id).or_default().extend(set);
+    #[test]
+    fn union_find_merges_long_chains() {
+        let mut sets = UnionFind::default();
+        // joined from the far end, so every union lands on a longer chain
+        for node in (1..1000).rev() {
+            sets.union(node + 1, node);
+        }
+        assert!((1..=1000).all(|node| sets.find(node) == 1));
     }
 
-    for element in elements.values_mut() {
-        element.set_nodes(element.get_nodes().iter().map(|node_id| {
-            if *node_id == other.id {
-                node.id
-            } else {
-                *node_id
-            }
-        }).collect());
+    #[test]
+    fn chain_of_wires_becomes_one_net() {
+        // wires 4-3, 2-3 and 2-6 in a chain with a resistor from its end at 6 to node 5
+        let mut elements: BTreeMap<u32, Box<dyn Device>> = BTreeMap::new();
+        elements.insert(1, Box::new(Wire::new(vec![4, 3])));
+        elements.insert(2, Box::new(Wire::new(vec![2, 3])));
+        elements.insert(3, Box::new(Wire::new(vec![2, 6])));
+        elements.insert(4, Box::new(Resistor::new(vec![6, 5], 100.0)));
+        let mut nodes = nodes_of(&elements);
+        let table = simplify_graph(&mut nodes, &mut elements, &mut String::new());
+
+        // the net is named after the smallest node in it
+        for node in [2, 3, 4, 6] {
+            assert_eq!(table.net_of(node), Some(2));
+        }
+        assert_eq!(table.net_of(5), Some(5));
+        assert_eq!(table.net_of(1), None);
+        assert_eq!(table.iter().collect::<Vec<_>>(), [(2, 2), (3, 2), (4, 2), (5, 5), (6, 2)]);
+        assert_eq!(table.merged(), BTreeMap::from([(2, BTreeSet::from([3, 4, 6]))]));
+
+        // the elements moved onto the nets, the wires are shorted onto a single one
+        assert_eq!(elements[&1].get_nodes(), [2, 2]);
+        assert_eq!(elements[&4].get_nodes(), [2, 5]);
+        let nets: Vec<(u32, BTreeSet<u32>)> = nodes.into_iter().map(|node| (node.id, node.connections)).collect();
+        assert_eq!(nets, [(0, BTreeSet::new()), (2, BTreeSet::from([1, 2, 3, 4])), (5, BTreeSet::from([4]))]);
     }
 
-    node_connections.entry(node.id)
-        .or_default()
-        .extend(other.connections.iter().copied());
+    #[test]
+    fn ground_names_its_net() {
+        // ground at node 3, wired to node 1
+        let mut elements: BTreeMap<u32, Box<dyn Device>> = BTreeMap::new();
+        elements.insert(1, Box::new(Ground::new(vec![3])));
+        elements.insert(2, Box::new(Wire::new(vec![1, 3])));
+        elements.insert(3, Box::new(Resistor::new(vec![1, 2], 100.0)));
+        elements.insert(4, Box::new(Resistor::new(Vec::new(), 100.0)));
+        let mut nodes = nodes_of(&elements);
+        let mut debug_info = String::new();
+        let table = simplify_graph(&mut nodes, &mut elements, &mut debug_info);
 
-    nodes_to_remove.insert(other.id);
-}
\ No newline at end of file
+        assert_eq!(table.iter().collect::<Vec<_>>(), [(1, 0), (2, 2), (3, 0)]);
+        assert_eq!(table.merged(), BTreeMap::from([(0, BTreeSet::from([1, 3]))]));
+        assert_eq!(elements[&3].get_nodes(), [0, 2]);
+        // the element without nodes is gone
+        assert!(!elements.contains_key(&4));
+        assert!(debug_info.contains("Removed 1 elements"));
+        assert_eq!(nodes.iter().map(|node| node.id).collect::<Vec<_>>(), [0, 2]);
+    }
+}
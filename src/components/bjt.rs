@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use eframe::egui;
+use eframe::egui::{Frame, Pos2, Stroke, Vec2};
+use rusty_circuit::devices::{self, BjtPolarity};
+use rusty_circuit::{Device, ElementType};
+use rusty_circuit::circuit::Solution;
+use rusty_circuit::pins::Orientation;
+use crate::{CircuitElement, Node, WindowResponse};
+use crate::components::current_labels;
+
+#[derive(Clone, Debug)]
+pub struct Bjt {
+    pos: Pos2,
+    size: Vec2,
+    id: u32,
+    device: devices::Bjt,
+    orientation: Orientation,
+    window_hovered: bool,
+}
+
+impl CircuitElement for Bjt {
+    fn new_boxed(pos: Pos2, size: Vec2, id: u32, nodes: Vec<u32>) -> Box<dyn CircuitElement> {
+        Box::new(Bjt { pos, size, id, device: devices::Bjt::new(nodes, BjtPolarity::Npn), orientation: Orientation::default(), window_hovered: false })
+    }
+
+    // circle around the base bar, the collector and the emitter leave the bar at an angle, the arrow on
+    // the emitter points out of an npn transistor and into a pnp one
+    fn draw(&mut self, ui: &mut egui::Ui, stroke: Stroke, grid_step: f32, screen_pos: Pos2, _screen_size: Vec2, _nodes: &HashMap<(i32, i32), Node>) -> bool {
+        let to_screen = |(x, y): (i32, i32)| screen_pos + (Pos2::new(x as f32, y as f32) - self.pos) * grid_step;
+        let pins: Vec<Pos2> = self.pins().into_iter().map(to_screen).collect();
+        let (collector, base, emitter) = (pins[0], pins[1], pins[2]);
+
+        let middle = collector + (emitter - collector) / 2.0;
+        let axis = (middle - base).normalized();
+        let normal = (collector - emitter).normalized();
+
+        let bar = base + axis * grid_step;
+        ui.painter().circle_stroke(bar + axis * grid_step * 0.35, grid_step * 0.85, stroke);
+        ui.painter().line_segment([bar - normal * grid_step * 0.45, bar + normal * grid_step * 0.45], stroke);
+        ui.painter().line_segment([base, bar], stroke);
+        ui.painter().line_segment([bar + normal * grid_step * 0.2, collector], stroke);
+
+        let emitter_start = bar - normal * grid_step * 0.2;
+        ui.painter().line_segment([emitter_start, emitter], stroke);
+        let (from, to) = (emitter_start + (emitter - emitter_start) * 0.35, emitter_start + (emitter - emitter_start) * 0.7);
+        match self.device.polarity {
+            BjtPolarity::Npn => ui.painter().arrow(from, to - from, stroke),
+            BjtPolarity::Pnp => ui.painter().arrow(to, from - to, stroke),
+        }
+        false
+    }
+
+    fn pos(&self) -> Pos2 {
+        self.pos
+    }
+
+    fn size(&self) -> Vec2 {
+        self.size
+    }
+
+    fn set_placement(&mut self, pos: Pos2, size: Vec2) {
+        self.pos = pos;
+        self.size = size;
+    }
+
+    fn device(&self) -> &dyn Device {
+        &self.device
+    }
+
+    fn device_mut(&mut self) -> &mut dyn Device {
+        &mut self.device
+    }
+
+    fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    fn draw_window(&mut self, ctx: &egui::Context, solution: &Solution, _nodes: &HashMap<(i32, i32), Node>, _elements: &[(u32, ElementType)]) -> WindowResponse {
+        let mut window = egui::Window::new(format!("BJT (id {})", self.id));
+
+        if self.window_hovered {
+            window = window.frame(
+                Frame::window(&ctx.style()).stroke(
+                    Stroke::new(1.0, egui::Color32::GREEN),
+                ),
+            );
+        }
+
+        let window_response = window.show(ctx, |ui| {
+            let mut changed = false;
+            let device = &mut self.device;
+            egui::ComboBox::from_label("Polarity").selected_text(format!("{:?}", device.polarity)).show_ui(ui, |ui| {
+                changed |= ui.selectable_value(&mut device.polarity, BjtPolarity::Npn, "NPN").changed();
+                changed |= ui.selectable_value(&mut device.polarity, BjtPolarity::Pnp, "PNP").changed();
+            });
+            changed |= ui.add(egui::Slider::new(&mut device.beta, 10.0..=1000.0).logarithmic(true).text("Beta")).changed();
+            changed |= ui.add(egui::Slider::new(&mut device.reverse_beta, 0.1..=10.0).logarithmic(true).text("Reverse beta")).changed();
+            ui.label(format!("Saturation current: {:.2e} A", device.saturation_current));
+            changed |= ui.add(egui::Slider::new(&mut device.saturation_current, 1.0e-18..=1.0e-9).logarithmic(true).text("Is")).changed();
+            changed |= ui.add(egui::Slider::new(&mut device.thermal_voltage, 0.02..=0.05).text("Vt (V)")).changed();
+            // 0 is no early effect
+            changed |= ui.add(egui::Slider::new(&mut device.early_voltage, 0.0..=200.0).text("Early voltage (V)")).changed();
+            current_labels(ui, solution, self.id);
+            changed
+        });
+
+        let changed = window_response.as_ref().and_then(|window| window.inner).unwrap_or(false);
+        if let Some(window) = window_response {
+            if window.response.hovered() || window.response.has_focus() {
+                self.window_hovered = true;
+                return WindowResponse { hovered: Some(window.response.rect.center()), changed };
+            }
+        }
+        self.window_hovered = false;
+        WindowResponse { hovered: None, changed }
+    }
+}
@@ -1,8 +1,11 @@
-use std::collections::{BTreeMap, HashMap};
-use std::collections::hash_map::Values;
+use std::collections::HashMap;
 use eframe::egui;
-use eframe::egui::{Pos2, Rect, Stroke, Vec2};
-use crate::{CircuitElement, ElementType, Node};
+use eframe::egui::{Frame, Pos2, Stroke, Vec2};
+use rusty_circuit::devices;
+use rusty_circuit::{Device, ElementType};
+use rusty_circuit::circuit::Solution;
+use crate::{CircuitElement, Node, WindowResponse};
+use crate::components::current_labels;
 
 #[derive(Clone, Debug)]
 
@@ -10,14 +13,15 @@ pub struct Capacitor {
     pos: Pos2,
     size: Vec2,
     id: u32,
-    nodes: Vec<u32>
+    device: devices::Capacitor,
+    window_hovered: bool,
 }
 impl CircuitElement for Capacitor {
     fn new_boxed(pos: Pos2, size: Vec2, id: u32, nodes: Vec<u32>) -> Box<dyn CircuitElement> {
-        Box::new(Capacitor { pos, size, id, nodes })
+        Box::new(Capacitor { pos, size, id, device: devices::Capacitor::new(nodes, 1.0e-3), window_hovered: false })
     }
 
-    fn draw(&mut self, ui: &mut egui::Ui, stroke: Stroke, grid_step: f32, screen_pos: Pos2, screen_size: Vec2, nodes: &HashMap<(i32, i32), Node>) {
+    fn draw(&mut self, ui: &mut egui::Ui, stroke: Stroke, grid_step: f32, screen_pos: Pos2, screen_size: Vec2, _nodes: &HashMap<(i32, i32), Node>) -> bool {
         let center = screen_pos + screen_size / 2.0;
 
         let normalized = Vec2::new(screen_size.x, screen_size.y) / screen_size.length();
@@ -30,6 +34,7 @@ impl CircuitElement for Capacitor {
         ui.painter().line_segment([center - normalized * spacing + normal * length, center - normalized * spacing - normal * length], stroke);
         ui.painter().line_segment([center + normalized * spacing, screen_pos + screen_size], stroke);
         ui.painter().line_segment([center - normalized * spacing, screen_pos], stroke);
+        false
     }
 
     fn pos(&self) -> Pos2 {
@@ -40,19 +45,51 @@ impl CircuitElement for Capacitor {
         self.size
     }
 
-    fn get_id(&self) -> u32 {
-        self.id
+    fn set_placement(&mut self, pos: Pos2, size: Vec2) {
+        self.pos = pos;
+        self.size = size;
     }
 
-    fn get_type(&self) -> ElementType {
-        ElementType::Capacitor
+    fn device(&self) -> &dyn Device {
+        &self.device
     }
 
-    fn set_nodes(&mut self, nodes: Vec<u32>) {
-        self.nodes = nodes;
+    fn device_mut(&mut self) -> &mut dyn Device {
+        &mut self.device
     }
 
-    fn get_nodes(&self) -> Vec<u32> {
-        self.nodes.clone()
+    fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    fn draw_window(&mut self, ctx: &egui::Context, solution: &Solution, _nodes: &HashMap<(i32, i32), Node>, _elements: &[(u32, ElementType)]) -> WindowResponse {
+        let mut window = egui::Window::new(format!("Capacitor (id {})", self.id));
+
+        if self.window_hovered {
+            window = window.frame(
+                Frame::window(&ctx.style()).stroke(
+                    Stroke::new(1.0, egui::Color32::GREEN),
+                ),
+            );
+        }
+
+        let window_response = window.show(ctx, |ui| {
+            let mut changed = false;
+            ui.label(format!("Capacitance: {:.2e} F", self.device.capacitance));
+            changed |= ui.add(egui::Slider::new(&mut self.device.capacitance, 1.0e-6..=1.0e-1).logarithmic(true).text("Capacitance")).changed();
+            ui.label(format!("Voltage: {:.2} V", self.device.voltage()));
+            current_labels(ui, solution, self.id);
+            changed
+        });
+
+        let changed = window_response.as_ref().and_then(|window| window.inner).unwrap_or(false);
+        if let Some(window) = window_response {
+            if window.response.hovered() || window.response.has_focus() {
+                self.window_hovered = true;
+                return WindowResponse { hovered: Some(window.response.rect.center()), changed };
+            }
+        }
+        self.window_hovered = false;
+        WindowResponse { hovered: None, changed }
     }
 }
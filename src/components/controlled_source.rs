@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use eframe::egui;
+use eframe::egui::{Align2, Color32, FontId, Frame, Pos2, Shape, Stroke, Vec2};
+use eframe::epaint::PathShape;
+use rusty_circuit::{devices, Device, ElementType};
+use rusty_circuit::circuit::Solution;
+use crate::{CircuitElement, Node, WindowResponse};
+use crate::components::current_labels;
+
+// what the editor needs to know about the four kinds of controlled sources
+pub trait ControlledDevice: Device + Clone + 'static {
+    const NAME: &'static str;
+    // voltage outputs get + and - marks, current outputs an arrow
+    const VOLTAGE_OUTPUT: bool;
+    fn create(nodes: Vec<u32>) -> Self;
+    // the gain with its name and the range of its slider
+    fn gain(&mut self) -> (&mut f64, &'static str, RangeInclusive<f64>);
+    // the controlling element of current controlled sources, None for voltage controlled ones
+    fn control(&mut self) -> Option<&mut Option<u32>> {
+        None
+    }
+}
+
+impl ControlledDevice for devices::Vcvs {
+    const NAME: &'static str = "Voltage Controlled Voltage Source";
+    const VOLTAGE_OUTPUT: bool = true;
+
+    fn create(nodes: Vec<u32>) -> Self {
+        devices::Vcvs::new(nodes, 10.0)
+    }
+
+    fn gain(&mut self) -> (&mut f64, &'static str, RangeInclusive<f64>) {
+        (&mut self.gain, "Gain (V/V)", -100.0..=100.0)
+    }
+}
+
+impl ControlledDevice for devices::Vccs {
+    const NAME: &'static str = "Voltage Controlled Current Source";
+    const VOLTAGE_OUTPUT: bool = false;
+
+    fn create(nodes: Vec<u32>) -> Self {
+        devices::Vccs::new(nodes, 0.01)
+    }
+
+    fn gain(&mut self) -> (&mut f64, &'static str, RangeInclusive<f64>) {
+        (&mut self.transconductance, "Transconductance (S)", -1.0..=1.0)
+    }
+}
+
+impl ControlledDevice for devices::Ccvs {
+    const NAME: &'static str = "Current Controlled Voltage Source";
+    const VOLTAGE_OUTPUT: bool = true;
+
+    fn create(nodes: Vec<u32>) -> Self {
+        devices::Ccvs::new(nodes, 100.0, None)
+    }
+
+    fn gain(&mut self) -> (&mut f64, &'static str, RangeInclusive<f64>) {
+        (&mut self.transresistance, "Transresistance (Ohm)", -1000.0..=1000.0)
+    }
+
+    fn control(&mut self) -> Option<&mut Option<u32>> {
+        Some(&mut self.control)
+    }
+}
+
+impl ControlledDevice for devices::Cccs {
+    const NAME: &'static str = "Current Controlled Current Source";
+    const VOLTAGE_OUTPUT: bool = false;
+
+    fn create(nodes: Vec<u32>) -> Self {
+        devices::Cccs::new(nodes, 10.0, None)
+    }
+
+    fn gain(&mut self) -> (&mut f64, &'static str, RangeInclusive<f64>) {
+        (&mut self.gain, "Gain (A/A)", -100.0..=100.0)
+    }
+
+    fn control(&mut self) -> Option<&mut Option<u32>> {
+        Some(&mut self.control)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ControlledSource<D: ControlledDevice> {
+    pos: Pos2,
+    size: Vec2,
+    id: u32,
+    device: D,
+    // the sense nodes can be picked one after the other, they only count once both are there
+    sense: [Option<(i32, i32)>; 2],
+    window_hovered: bool,
+}
+
+impl<D: ControlledDevice + std::fmt::Debug> CircuitElement for ControlledSource<D> {
+    fn new_boxed(pos: Pos2, size: Vec2, id: u32, nodes: Vec<u32>) -> Box<dyn CircuitElement> {
+        Box::new(ControlledSource { pos, size, id, device: D::create(nodes), sense: [None, None], window_hovered: false })
+    }
+
+    // diamond between the output nodes, dashed lines lead to the sense nodes
+    fn draw(&mut self, ui: &mut egui::Ui, stroke: Stroke, grid_step: f32, screen_pos: Pos2, screen_size: Vec2, _nodes: &HashMap<(i32, i32), Node>) -> bool {
+        let center = screen_pos + screen_size / 2.0;
+
+        let normalized = Vec2::new(screen_size.x, screen_size.y) / screen_size.length();
+        let normal = Vec2::new(screen_size.y, -screen_size.x) / screen_size.length();
+
+        let radius = grid_step * 0.5;
+
+        let diamond = PathShape::closed_line(vec![
+            center - normalized * radius,
+            center + normal * radius,
+            center + normalized * radius,
+            center - normal * radius,
+        ], stroke);
+        ui.painter().add(Shape::Path(diamond));
+
+        ui.painter().line_segment([center + normalized * radius, screen_pos + screen_size], stroke);
+        ui.painter().line_segment([center - normalized * radius, screen_pos], stroke);
+
+        if D::VOLTAGE_OUTPUT {
+            let font = FontId::proportional(grid_step * 0.4);
+            ui.painter().text(center + normalized * radius * 0.45, Align2::CENTER_CENTER, "+", font.clone(), stroke.color);
+            ui.painter().text(center - normalized * radius * 0.45, Align2::CENTER_CENTER, "-", font, stroke.color);
+        } else {
+            ui.painter().arrow(center - normalized * radius * 0.6, normalized * radius * 1.2, stroke);
+        }
+
+        let sense_stroke = Stroke::new(1.0, Color32::GRAY);
+        for (position, sign) in self.sense.iter().zip(["+", "-"]) {
+            if let Some((x, y)) = position {
+                let point = screen_pos + (Pos2::new(*x as f32, *y as f32) - self.pos) * grid_step;
+                ui.painter().extend(Shape::dashed_line(&[center, point], sense_stroke, 4.0, 4.0));
+                ui.painter().text(point + Vec2::new(6.0, -6.0), Align2::LEFT_BOTTOM, sign, FontId::proportional(12.0), Color32::GRAY);
+            }
+        }
+        false
+    }
+
+    fn pos(&self) -> Pos2 {
+        self.pos
+    }
+
+    fn size(&self) -> Vec2 {
+        self.size
+    }
+
+    fn set_placement(&mut self, pos: Pos2, size: Vec2) {
+        self.pos = pos;
+        self.size = size;
+    }
+
+    fn device(&self) -> &dyn Device {
+        &self.device
+    }
+
+    fn device_mut(&mut self) -> &mut dyn Device {
+        &mut self.device
+    }
+
+    fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    fn sense(&self) -> Option<[(i32, i32); 2]> {
+        Some([self.sense[0]?, self.sense[1]?])
+    }
+
+    fn set_sense(&mut self, sense: Option<[(i32, i32); 2]>) {
+        self.sense = sense.map_or([None, None], |sense| sense.map(Some));
+    }
+
+    fn draw_window(&mut self, ctx: &egui::Context, solution: &Solution, nodes: &HashMap<(i32, i32), Node>, elements: &[(u32, ElementType)]) -> WindowResponse {
+        let mut window = egui::Window::new(format!("{} (id {})", D::NAME, self.id));
+
+        if self.window_hovered {
+            window = window.frame(
+                Frame::window(&ctx.style()).stroke(
+                    Stroke::new(1.0, egui::Color32::GREEN),
+                ),
+            );
+        }
+
+        let window_response = window.show(ctx, |ui| {
+            let mut changed = false;
+            let (gain, name, range) = self.device.gain();
+            ui.label(format!("{}: {:.3}", name, gain));
+            changed |= ui.add(egui::Slider::new(gain, range).text(name)).changed();
+
+            match self.device.control() {
+                // only elements with a branch current can control the source
+                Some(control) => {
+                    let label = |id: Option<u32>| id.map_or("None".to_string(), |id| format!("Element {}", id));
+                    egui::ComboBox::from_label("Controlled by").selected_text(label(*control)).show_ui(ui, |ui| {
+                        changed |= ui.selectable_value(control, None, "None").changed();
+                        for (id, element_type) in elements.iter().filter(|(id, element_type)| {
+                            *id != self.id && matches!(element_type, ElementType::DCVoltageSource | ElementType::Inductor | ElementType::Vcvs | ElementType::Ccvs)
+                        }) {
+                            changed |= ui.selectable_value(control, Some(*id), format!("{:?} {}", element_type, id)).changed();
+                        }
+                    });
+                }
+                None => {
+                    let mut positions: Vec<(&(i32, i32), &Node)> = nodes.iter().collect();
+                    positions.sort_by_key(|(_, node)| node.id);
+                    let label = |position: Option<(i32, i32)>| {
+                        position.and_then(|position| nodes.get(&position)).map_or("None".to_string(), |node| format!("Node {}", node.id))
+                    };
+                    for (sense, name) in self.sense.iter_mut().zip(["Sense +", "Sense -"]) {
+                        egui::ComboBox::from_label(name).selected_text(label(*sense)).show_ui(ui, |ui| {
+                            changed |= ui.selectable_value(sense, None, "None").changed();
+                            for (position, node) in positions.iter() {
+                                changed |= ui.selectable_value(sense, Some(**position), format!("Node {} at {:?}", node.id, position)).changed();
+                            }
+                        });
+                    }
+                }
+            }
+
+            current_labels(ui, solution, self.id);
+            changed
+        });
+
+        let changed = window_response.as_ref().and_then(|window| window.inner).unwrap_or(false);
+        if let Some(window) = window_response {
+            if window.response.hovered() || window.response.has_focus() {
+                self.window_hovered = true;
+                return WindowResponse { hovered: Some(window.response.rect.center()), changed };
+            }
+        }
+        self.window_hovered = false;
+        WindowResponse { hovered: None, changed }
+    }
+}
@@ -1,37 +1,36 @@
-use std::collections::{BTreeMap, HashMap};
-use std::collections::hash_map::Values;
+use std::collections::HashMap;
 use eframe::egui;
-use eframe::egui::{Color32, Pos2, Rect, Stroke, Vec2};
-use nalgebra::{DMatrix, DVector};
-use crate::{CircuitElement, ElementType, Node};
+use eframe::egui::{Color32, Frame, Pos2, Stroke, Vec2};
+use rusty_circuit::devices;
+use rusty_circuit::{Device, ElementType};
+use rusty_circuit::circuit::Solution;
+use crate::{CircuitElement, Node, WindowResponse};
+use crate::components::{ac_editor, current_labels, waveform_editor};
 
 #[derive(Clone, Debug)]
 pub struct CurrentSource {
     pos: Pos2,
     size: Vec2,
     id: u32,
-    nodes: Vec<u32>,
-    current: f64
+    device: devices::CurrentSource,
+    window_hovered: bool,
 }
 
-
 impl CircuitElement for CurrentSource {
     fn new_boxed(pos: Pos2, size: Vec2, id: u32, nodes: Vec<u32>) -> Box<dyn CircuitElement> {
-        Box::new(CurrentSource { pos, size, id, nodes, current: 1.0})
+        Box::new(CurrentSource { pos, size, id, device: devices::CurrentSource::new(nodes, 1.0), window_hovered: false })
     }
 
-    fn draw(&mut self, ui: &mut egui::Ui, stroke: Stroke, grid_step: f32, screen_pos: Pos2, screen_size: Vec2, nodes: &HashMap<(i32, i32), Node>) {
+    fn draw(&mut self, ui: &mut egui::Ui, stroke: Stroke, grid_step: f32, screen_pos: Pos2, screen_size: Vec2, _nodes: &HashMap<(i32, i32), Node>) -> bool {
         let center = screen_pos + screen_size / 2.0;
 
         let normalized = Vec2::new(screen_size.x, screen_size.y) / screen_size.length();
-        let normal = Vec2::new(screen_size.y, -screen_size.x) / screen_size.length();
 
         let radius = grid_step * 0.5;
         let line_length = grid_step * 0.6;
 
         ui.painter().circle(center, radius, Color32::TRANSPARENT, stroke);
 
-
         ui.painter().line_segment([center + normalized * radius, screen_pos + screen_size], stroke);
         ui.painter().line_segment([center - normalized * radius, screen_pos], stroke);
         ui.painter().arrow(
@@ -39,6 +38,7 @@ impl CircuitElement for CurrentSource {
             normalized * line_length,
             stroke
         );
+        false
     }
 
     fn pos(&self) -> Pos2 {
@@ -49,27 +49,54 @@ impl CircuitElement for CurrentSource {
         self.size
     }
 
-    fn get_id(&self) -> u32  {
-        self.id
+    fn set_placement(&mut self, pos: Pos2, size: Vec2) {
+        self.pos = pos;
+        self.size = size;
     }
 
-    fn get_type(&self) -> ElementType {
-        ElementType::CurrentSource
+    fn device(&self) -> &dyn Device {
+        &self.device
     }
 
-    fn set_nodes(&mut self, nodes: Vec<u32>) {
-        self.nodes = nodes;
+    fn device_mut(&mut self) -> &mut dyn Device {
+        &mut self.device
     }
 
-    fn get_nodes(&self) -> Vec<u32> {
-        self.nodes.clone()
+    fn get_id(&self) -> u32  {
+        self.id
     }
 
-    fn stamp_matrix(&self, matrix: &mut DMatrix<f64>, vector: &mut DVector<f64>, nodes: &Vec<Node>) {
-        let n1 = nodes.iter().position(|node| node.id == self.nodes[0]).unwrap();
-        let n2 = nodes.iter().position(|node| node.id == self.nodes[1]).unwrap();
-
-        vector[n1] -= self.current;
-        vector[n2] += self.current;
+    fn draw_window(&mut self, ctx: &egui::Context, solution: &Solution, _nodes: &HashMap<(i32, i32), Node>, _elements: &[(u32, ElementType)]) -> WindowResponse {
+        let mut window = egui::Window::new(format!("Current Source (id {})", self.id));
+
+        if self.window_hovered {
+            window = window.frame(
+                Frame::window(&ctx.style()).stroke(
+                    Stroke::new(1.0, egui::Color32::GREEN),
+                ),
+            );
+        }
+
+        let window_response = window.show(ctx, |ui| {
+            let mut changed = false;
+            if self.device.waveform.is_none() {
+                ui.label(format!("Current: {:.3} A", self.device.current));
+                changed |= ui.add(egui::Slider::new(&mut self.device.current, -10.0..=10.0).text("Current")).changed();
+            }
+            changed |= waveform_editor(ui, &mut self.device.waveform, self.device.current);
+            changed |= ac_editor(ui, &mut self.device.ac);
+            current_labels(ui, solution, self.id);
+            changed
+        });
+
+        let changed = window_response.as_ref().and_then(|window| window.inner).unwrap_or(false);
+        if let Some(window) = window_response {
+            if window.response.hovered() || window.response.has_focus() {
+                self.window_hovered = true;
+                return WindowResponse { hovered: Some(window.response.rect.center()), changed };
+            }
+        }
+        self.window_hovered = false;
+        WindowResponse { hovered: None, changed }
     }
 }
\ No newline at end of file
@@ -1,28 +1,27 @@
-use std::collections::{BTreeMap, HashMap};
-use std::collections::hash_map::Values;
+use std::collections::HashMap;
 use eframe::egui;
-use eframe::egui::{Frame, Pos2, Rect, Stroke, Vec2};
-use nalgebra::{DMatrix, DVector};
-use crate::{CircuitElement, ElementType, Node};
+use eframe::egui::{Frame, Pos2, Stroke, Vec2};
+use rusty_circuit::devices;
+use rusty_circuit::{Device, ElementType};
+use rusty_circuit::circuit::Solution;
+use crate::{CircuitElement, Node, WindowResponse};
+use crate::components::{ac_editor, current_labels, waveform_editor};
 
 #[derive(Clone, Debug)]
 pub struct DCVoltageSource {
     pos: Pos2,
     size: Vec2,
     id: u32,
-    nodes: Vec<u32>,
-    voltage: f64,
-    voltage_node: u32,
+    device: devices::DCVoltageSource,
     window_hovered: bool,
 }
 
-
 impl CircuitElement for DCVoltageSource {
     fn new_boxed(pos: Pos2, size: Vec2, id: u32, nodes: Vec<u32>) -> Box<dyn CircuitElement> {
-        Box::new(DCVoltageSource { pos, size, id, nodes, voltage: 5.0, voltage_node: 0, window_hovered: false})
+        Box::new(DCVoltageSource { pos, size, id, device: devices::DCVoltageSource::new(nodes, 5.0), window_hovered: false })
     }
 
-    fn draw(&mut self, ui: &mut egui::Ui, stroke: Stroke, grid_step: f32, screen_pos: Pos2, screen_size: Vec2, nodes: &HashMap<(i32, i32), Node>) {
+    fn draw(&mut self, ui: &mut egui::Ui, stroke: Stroke, grid_step: f32, screen_pos: Pos2, screen_size: Vec2, _nodes: &HashMap<(i32, i32), Node>) -> bool {
         let center = screen_pos + screen_size / 2.0;
 
         let normalized = Vec2::new(screen_size.x, screen_size.y) / screen_size.length();
@@ -37,6 +36,7 @@ impl CircuitElement for DCVoltageSource {
 
         ui.painter().line_segment([center + normalized * spacing, screen_pos + screen_size], stroke);
         ui.painter().line_segment([center - normalized * spacing, screen_pos], stroke);
+        false
     }
 
     fn pos(&self) -> Pos2 {
@@ -47,44 +47,24 @@ impl CircuitElement for DCVoltageSource {
         self.size
     }
 
-    fn get_id(&self) -> u32  {
-        self.id
-    }
-
-    fn get_type(&self) -> ElementType {
-        ElementType::DCVoltageSource
-    }
-
-    fn set_nodes(&mut self, nodes: Vec<u32>) {
-        self.nodes = nodes;
+    fn set_placement(&mut self, pos: Pos2, size: Vec2) {
+        self.pos = pos;
+        self.size = size;
     }
 
-    fn get_nodes(&self) -> Vec<u32> {
-        self.nodes.clone()
+    fn device(&self) -> &dyn Device {
+        &self.device
     }
 
-    fn get_voltage_source_count(&self) -> u32 {
-        1
+    fn device_mut(&mut self) -> &mut dyn Device {
+        &mut self.device
     }
 
-    fn set_voltage_node(&mut self, node: u32) {
-        self.voltage_node = node;
-    }
-
-    fn stamp_matrix(&self, matrix: &mut DMatrix<f64>, vector: &mut DVector<f64>, nodes: &Vec<Node>) {
-        let node1 = nodes.iter().position(|node| node.id == self.nodes[0]).unwrap();
-        let node2 = nodes.iter().position(|node| node.id == self.nodes[1]).unwrap();
-        let voltage_node = nodes.len() + self.voltage_node as usize;
-
-        matrix[(voltage_node, node1)] -= 1.0;
-        matrix[(voltage_node, node2)] += 1.0;
-        vector[voltage_node] = self.voltage;
-        matrix[(node1, voltage_node)] -= 1.0;
-        matrix[(node2, voltage_node)] += 1.0;
+    fn get_id(&self) -> u32  {
+        self.id
     }
 
-
-    fn draw_window(&mut self, ctx: &egui::Context) -> Option<Pos2> {
+    fn draw_window(&mut self, ctx: &egui::Context, solution: &Solution, _nodes: &HashMap<(i32, i32), Node>, _elements: &[(u32, ElementType)]) -> WindowResponse {
         let mut window = egui::Window::new(format!("DC Voltage Source (id {})", self.id));
 
         if self.window_hovered {
@@ -96,17 +76,25 @@ impl CircuitElement for DCVoltageSource {
         }
 
         let window_response = window.show(ctx, |ui| {
-            ui.label(format!("Voltage: {:.2} V", self.voltage));
-            ui.add(egui::Slider::new(&mut self.voltage, 0.0..=1000.0).text("Voltage"));
+            let mut changed = false;
+            if self.device.waveform.is_none() {
+                ui.label(format!("Voltage: {:.2} V", self.device.voltage));
+                changed |= ui.add(egui::Slider::new(&mut self.device.voltage, 0.0..=1000.0).text("Voltage")).changed();
+            }
+            changed |= waveform_editor(ui, &mut self.device.waveform, self.device.voltage);
+            changed |= ac_editor(ui, &mut self.device.ac);
+            current_labels(ui, solution, self.id);
+            changed
         });
 
+        let changed = window_response.as_ref().and_then(|window| window.inner).unwrap_or(false);
         if let Some(window) = window_response {
             if window.response.hovered() || window.response.has_focus() {
                 self.window_hovered = true;
-                return Some(window.response.rect.center());
+                return WindowResponse { hovered: Some(window.response.rect.center()), changed };
             }
         }
         self.window_hovered = false;
-        None
+        WindowResponse { hovered: None, changed }
     }
 }
\ No newline at end of file
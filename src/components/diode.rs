@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use eframe::egui;
+use eframe::egui::{Frame, Pos2, Shape, Stroke, Vec2};
+use eframe::epaint::PathShape;
+use rusty_circuit::devices;
+use rusty_circuit::{Device, ElementType};
+use rusty_circuit::circuit::Solution;
+use crate::{CircuitElement, Node, WindowResponse};
+use crate::components::current_labels;
+
+#[derive(Clone, Debug)]
+pub struct Diode {
+    pos: Pos2,
+    size: Vec2,
+    id: u32,
+    device: devices::Diode,
+    window_hovered: bool,
+}
+
+impl CircuitElement for Diode {
+    fn new_boxed(pos: Pos2, size: Vec2, id: u32, nodes: Vec<u32>) -> Box<dyn CircuitElement> {
+        Box::new(Diode { pos, size, id, device: devices::Diode::new(nodes, 1.0e-14, 1.0, 0.0), window_hovered: false })
+    }
+
+    // triangle pointing from the anode (first node) to the bar of the cathode
+    fn draw(&mut self, ui: &mut egui::Ui, stroke: Stroke, grid_step: f32, screen_pos: Pos2, screen_size: Vec2, _nodes: &HashMap<(i32, i32), Node>) -> bool {
+        let center = screen_pos + screen_size / 2.0;
+
+        let normalized = Vec2::new(screen_size.x, screen_size.y) / screen_size.length();
+        let normal = Vec2::new(screen_size.y, -screen_size.x) / screen_size.length();
+
+        let width = grid_step * 0.3;
+        let height = grid_step * 0.3;
+
+        let triangle = PathShape::closed_line(vec![
+            center - normalized * height + normal * width,
+            center - normalized * height - normal * width,
+            center + normalized * height,
+        ], stroke);
+        ui.painter().add(Shape::Path(triangle));
+
+        ui.painter().line_segment([center + normalized * height + normal * width, center + normalized * height - normal * width], stroke);
+
+        ui.painter().line_segment([center + normalized * height, screen_pos + screen_size], stroke);
+        ui.painter().line_segment([center - normalized * height, screen_pos], stroke);
+        false
+    }
+
+    fn pos(&self) -> Pos2 {
+        self.pos
+    }
+
+    fn size(&self) -> Vec2 {
+        self.size
+    }
+
+    fn set_placement(&mut self, pos: Pos2, size: Vec2) {
+        self.pos = pos;
+        self.size = size;
+    }
+
+    fn device(&self) -> &dyn Device {
+        &self.device
+    }
+
+    fn device_mut(&mut self) -> &mut dyn Device {
+        &mut self.device
+    }
+
+    fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    fn draw_window(&mut self, ctx: &egui::Context, solution: &Solution, _nodes: &HashMap<(i32, i32), Node>, _elements: &[(u32, ElementType)]) -> WindowResponse {
+        let mut window = egui::Window::new(format!("Diode (id {})", self.id));
+
+        if self.window_hovered {
+            window = window.frame(
+                Frame::window(&ctx.style()).stroke(
+                    Stroke::new(1.0, egui::Color32::GREEN),
+                ),
+            );
+        }
+
+        let window_response = window.show(ctx, |ui| {
+            let mut changed = false;
+            ui.label(format!("Saturation current: {:.2e} A", self.device.saturation_current));
+            changed |= ui.add(egui::Slider::new(&mut self.device.saturation_current, 1.0e-16..=1.0e-6).logarithmic(true).text("Is")).changed();
+            changed |= ui.add(egui::Slider::new(&mut self.device.emission_coefficient, 1.0..=2.0).text("n")).changed();
+            changed |= ui.add(egui::Slider::new(&mut self.device.series_resistance, 0.0..=100.0).text("Series resistance")).changed();
+            current_labels(ui, solution, self.id);
+            changed
+        });
+
+        let changed = window_response.as_ref().and_then(|window| window.inner).unwrap_or(false);
+        if let Some(window) = window_response {
+            if window.response.hovered() || window.response.has_focus() {
+                self.window_hovered = true;
+                return WindowResponse { hovered: Some(window.response.rect.center()), changed };
+            }
+        }
+        self.window_hovered = false;
+        WindowResponse { hovered: None, changed }
+    }
+}
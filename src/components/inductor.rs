@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use eframe::egui;
+use eframe::egui::{Frame, Pos2, Shape, Stroke, Vec2};
+use rusty_circuit::devices;
+use rusty_circuit::{Device, ElementType};
+use rusty_circuit::circuit::Solution;
+use crate::{CircuitElement, Node, WindowResponse};
+use crate::components::current_labels;
+
+#[derive(Clone, Debug)]
+pub struct Inductor {
+    pos: Pos2,
+    size: Vec2,
+    id: u32,
+    device: devices::Inductor,
+    window_hovered: bool,
+}
+
+impl CircuitElement for Inductor {
+    fn new_boxed(pos: Pos2, size: Vec2, id: u32, nodes: Vec<u32>) -> Box<dyn CircuitElement> {
+        Box::new(Inductor { pos, size, id, device: devices::Inductor::new(nodes, 1.0), window_hovered: false })
+    }
+
+    fn draw(&mut self, ui: &mut egui::Ui, stroke: Stroke, grid_step: f32, screen_pos: Pos2, screen_size: Vec2, _nodes: &HashMap<(i32, i32), Node>) -> bool {
+        let center = screen_pos + screen_size / 2.0;
+
+        let normalized = Vec2::new(screen_size.x, screen_size.y) / screen_size.length();
+        let normal = Vec2::new(screen_size.y, -screen_size.x) / screen_size.length();
+
+        let turns = 4;
+        let half_length = grid_step * 0.5;
+        let radius = half_length / turns as f32;
+        let start = center - normalized * half_length;
+
+        let mut points = Vec::new();
+        for turn in 0..turns {
+            let turn_center = start + normalized * radius * (2 * turn + 1) as f32;
+            for i in 0..=8 {
+                let angle = PI * i as f32 / 8.0;
+                points.push(turn_center - normalized * radius * angle.cos() + normal * radius * angle.sin());
+            }
+        }
+        ui.painter().add(Shape::line(points, stroke));
+
+        ui.painter().line_segment([center + normalized * half_length, screen_pos + screen_size], stroke);
+        ui.painter().line_segment([center - normalized * half_length, screen_pos], stroke);
+        false
+    }
+
+    fn pos(&self) -> Pos2 {
+        self.pos
+    }
+
+    fn size(&self) -> Vec2 {
+        self.size
+    }
+
+    fn set_placement(&mut self, pos: Pos2, size: Vec2) {
+        self.pos = pos;
+        self.size = size;
+    }
+
+    fn device(&self) -> &dyn Device {
+        &self.device
+    }
+
+    fn device_mut(&mut self) -> &mut dyn Device {
+        &mut self.device
+    }
+
+    fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    fn draw_window(&mut self, ctx: &egui::Context, solution: &Solution, _nodes: &HashMap<(i32, i32), Node>, _elements: &[(u32, ElementType)]) -> WindowResponse {
+        let mut window = egui::Window::new(format!("Inductor (id {})", self.id));
+
+        if self.window_hovered {
+            window = window.frame(
+                Frame::window(&ctx.style()).stroke(
+                    Stroke::new(1.0, egui::Color32::GREEN),
+                ),
+            );
+        }
+
+        let window_response = window.show(ctx, |ui| {
+            let mut changed = false;
+            ui.label(format!("Inductance: {:.2e} H", self.device.inductance));
+            changed |= ui.add(egui::Slider::new(&mut self.device.inductance, 1.0e-6..=10.0).logarithmic(true).text("Inductance")).changed();
+            current_labels(ui, solution, self.id);
+            changed
+        });
+
+        let changed = window_response.as_ref().and_then(|window| window.inner).unwrap_or(false);
+        if let Some(window) = window_response {
+            if window.response.hovered() || window.response.has_focus() {
+                self.window_hovered = true;
+                return WindowResponse { hovered: Some(window.response.rect.center()), changed };
+            }
+        }
+        self.window_hovered = false;
+        WindowResponse { hovered: None, changed }
+    }
+}
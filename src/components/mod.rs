@@ -5,3 +5,133 @@ pub mod wire;
 pub mod current_source;
 pub mod circuit_switch;
 pub mod ground;
+pub mod inductor;
+pub mod diode;
+pub mod controlled_source;
+pub mod op_amp;
+pub mod bjt;
+pub mod mosfet;
+
+use eframe::egui;
+use rusty_circuit::ac::AcSource;
+use rusty_circuit::circuit::Solution;
+use rusty_circuit::waveform::Waveform;
+
+// current and power of the last solved step, shown in the element windows
+pub fn current_labels(ui: &mut egui::Ui, solution: &Solution, id: u32) {
+    if let (Some(current), Some(power)) = (solution.current(id), solution.power(id)) {
+        ui.label(format!("Current: {:.3} A", current));
+        ui.label(format!("Power: {:.3} W", power));
+    }
+}
+
+// picks the waveform of a source and edits its parameters, None is the constant value of the source
+// a new waveform starts out swinging between 0 and the constant value
+// drag values move by 1 % of the value, so microseconds can be edited as well as seconds
+// returns whether the waveform was changed
+pub fn waveform_editor(ui: &mut egui::Ui, waveform: &mut Option<Waveform>, constant: f64) -> bool {
+    let name = |waveform: &Option<Waveform>| match waveform {
+        None => "DC",
+        Some(Waveform::Sine { .. }) => "Sine",
+        Some(Waveform::Pulse { .. }) => "Pulse",
+        Some(Waveform::PiecewiseLinear { .. }) => "Piecewise linear",
+        Some(Waveform::Exponential { .. }) => "Exponential",
+    };
+    let choices = [
+        None,
+        Some(Waveform::Sine { offset: 0.0, amplitude: constant, frequency: 1000.0, delay: 0.0, damping: 0.0, phase: 0.0 }),
+        Some(Waveform::Pulse { initial: 0.0, pulsed: constant, delay: 0.0, rise: 1.0e-6, fall: 1.0e-6, width: 5.0e-4, period: 1.0e-3 }),
+        Some(Waveform::PiecewiseLinear { points: vec![[0.0, 0.0], [1.0e-3, constant]] }),
+        Some(Waveform::Exponential { initial: 0.0, pulsed: constant, rise_delay: 0.0, rise_time_constant: 1.0e-4, fall_delay: 5.0e-4, fall_time_constant: 1.0e-4 }),
+    ];
+    let mut changed = false;
+    egui::ComboBox::from_label("Waveform").selected_text(name(waveform)).show_ui(ui, |ui| {
+        for choice in choices {
+            let selected = name(waveform) == name(&choice);
+            if ui.selectable_label(selected, name(&choice)).clicked() && !selected {
+                *waveform = choice;
+                changed = true;
+            }
+        }
+    });
+
+    let mut parameters: Vec<(&mut f64, &str)> = match waveform {
+        None => return changed,
+        Some(Waveform::Sine { offset, amplitude, frequency, delay, damping, phase }) => vec![
+            (offset, "Offset"), (amplitude, "Amplitude"), (frequency, "Frequency (Hz)"),
+            (delay, "Delay (s)"), (damping, "Damping (1/s)"), (phase, "Phase (deg)"),
+        ],
+        Some(Waveform::Pulse { initial, pulsed, delay, rise, fall, width, period }) => vec![
+            (initial, "Initial"), (pulsed, "Pulsed"), (delay, "Delay (s)"), (rise, "Rise (s)"),
+            (fall, "Fall (s)"), (width, "Width (s)"), (period, "Period (s)"),
+        ],
+        Some(Waveform::Exponential { initial, pulsed, rise_delay, rise_time_constant, fall_delay, fall_time_constant }) => vec![
+            (initial, "Initial"), (pulsed, "Pulsed"), (rise_delay, "Rise delay (s)"),
+            (rise_time_constant, "Rise time constant (s)"), (fall_delay, "Fall delay (s)"),
+            (fall_time_constant, "Fall time constant (s)"),
+        ],
+        Some(Waveform::PiecewiseLinear { points }) => {
+            return piecewise_linear_table(ui, points) || changed;
+        }
+    };
+    egui::Grid::new("waveform").show(ui, |ui| {
+        for (value, name) in parameters.iter_mut() {
+            ui.label(*name);
+            let speed = value.abs().max(1.0e-6) * 0.01;
+            changed |= ui.add(egui::DragValue::new(*value).speed(speed)).changed();
+            ui.end_row();
+        }
+    });
+    changed
+}
+
+// the ac value of a source for ac analysis, a source without one is switched off in the sweep
+pub fn ac_editor(ui: &mut egui::Ui, ac: &mut Option<AcSource>) -> bool {
+    let mut enabled = ac.is_some();
+    let mut changed = ui.checkbox(&mut enabled, "AC source").changed();
+    if changed {
+        *ac = enabled.then_some(AcSource { magnitude: 1.0, phase: 0.0 });
+    }
+    if let Some(AcSource { magnitude, phase }) = ac {
+        ui.horizontal(|ui| {
+            changed |= ui.add(egui::DragValue::new(magnitude).speed(0.01).prefix("Magnitude: ")).changed();
+            changed |= ui.add(egui::DragValue::new(phase).speed(1.0).range(-180.0..=180.0).prefix("Phase: ").suffix(" deg")).changed();
+        });
+    }
+    changed
+}
+
+// times are kept increasing, a point can not be dragged past its neighbours
+fn piecewise_linear_table(ui: &mut egui::Ui, points: &mut Vec<[f64; 2]>) -> bool {
+    let mut remove = None;
+    let mut changed = false;
+    egui::Grid::new("points").show(ui, |ui| {
+        ui.label("Time (s)");
+        ui.label("Value");
+        ui.end_row();
+        let count = points.len();
+        for index in 0..count {
+            let earliest = if index > 0 { points[index - 1][0] } else { 0.0 };
+            let latest = points.get(index + 1).map_or(f64::INFINITY, |point| point[0]);
+            let [time, value] = &mut points[index];
+            let speed = time.abs().max(1.0e-6) * 0.01;
+            changed |= ui.add(egui::DragValue::new(time).speed(speed).range(earliest..=latest)).changed();
+            changed |= ui.add(egui::DragValue::new(value).speed(0.01)).changed();
+            // one point is the least a table can have
+            if count > 1 && ui.small_button("x").clicked() {
+                remove = Some(index);
+            }
+            ui.end_row();
+        }
+    });
+    if let Some(index) = remove {
+        points.remove(index);
+        changed = true;
+    }
+    if ui.button("Add point").clicked() {
+        let [time, value] = points.last().copied().unwrap_or([0.0, 0.0]);
+        points.push([time + 1.0e-3, value]);
+        changed = true;
+    }
+    changed
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use eframe::egui;
+use eframe::egui::{Frame, Pos2, Stroke, Vec2};
+use rusty_circuit::devices::{self, MosfetChannel};
+use rusty_circuit::{Device, ElementType};
+use rusty_circuit::circuit::Solution;
+use rusty_circuit::pins::Orientation;
+use crate::{CircuitElement, Node, WindowResponse};
+use crate::components::current_labels;
+
+#[derive(Clone, Debug)]
+pub struct Mosfet {
+    pos: Pos2,
+    size: Vec2,
+    id: u32,
+    device: devices::Mosfet,
+    orientation: Orientation,
+    window_hovered: bool,
+}
+
+impl CircuitElement for Mosfet {
+    fn new_boxed(pos: Pos2, size: Vec2, id: u32, nodes: Vec<u32>) -> Box<dyn CircuitElement> {
+        Box::new(Mosfet { pos, size, id, device: devices::Mosfet::new(nodes, MosfetChannel::N), orientation: Orientation::default(), window_hovered: false })
+    }
+
+    // gate plate beside a broken channel (enhancement mode), the drain and the source leave its ends and
+    // the body in its middle is tied to the source, the arrow on the body points into an n-channel
+    // transistor and out of a p-channel one
+    fn draw(&mut self, ui: &mut egui::Ui, stroke: Stroke, grid_step: f32, screen_pos: Pos2, _screen_size: Vec2, _nodes: &HashMap<(i32, i32), Node>) -> bool {
+        let to_screen = |(x, y): (i32, i32)| screen_pos + (Pos2::new(x as f32, y as f32) - self.pos) * grid_step;
+        let pins: Vec<Pos2> = self.pins().into_iter().map(to_screen).collect();
+        let (drain, gate, source) = (pins[0], pins[1], pins[2]);
+
+        let middle = drain + (source - drain) / 2.0;
+        let axis = (middle - gate).normalized();
+        let normal = (drain - source).normalized();
+        let half_length = grid_step * 0.6;
+
+        let plate = gate + axis * grid_step * 0.9;
+        ui.painter().line_segment([gate, plate], stroke);
+        ui.painter().line_segment([plate - normal * half_length, plate + normal * half_length], stroke);
+
+        let channel = plate + axis * grid_step * 0.25;
+        for (from, to) in [(0.35, 0.6), (-0.12, 0.12), (-0.6, -0.35)] {
+            ui.painter().line_segment([channel + normal * grid_step * from, channel + normal * grid_step * to], stroke);
+        }
+
+        // drain and source come out of the ends of the channel and turn towards their pins
+        let end = grid_step * 0.475;
+        let drain_corner = drain - normal * (grid_step - end);
+        let source_corner = source + normal * (grid_step - end);
+        ui.painter().line_segment([channel + normal * end, drain_corner], stroke);
+        ui.painter().line_segment([drain_corner, drain], stroke);
+        ui.painter().line_segment([channel - normal * end, source_corner], stroke);
+        ui.painter().line_segment([source_corner, source], stroke);
+        ui.painter().line_segment([middle, source_corner], stroke);
+
+        match self.device.channel {
+            MosfetChannel::N => ui.painter().arrow(middle, channel - middle, stroke),
+            MosfetChannel::P => ui.painter().arrow(channel, middle - channel, stroke),
+        }
+        false
+    }
+
+    fn pos(&self) -> Pos2 {
+        self.pos
+    }
+
+    fn size(&self) -> Vec2 {
+        self.size
+    }
+
+    fn set_placement(&mut self, pos: Pos2, size: Vec2) {
+        self.pos = pos;
+        self.size = size;
+    }
+
+    fn device(&self) -> &dyn Device {
+        &self.device
+    }
+
+    fn device_mut(&mut self) -> &mut dyn Device {
+        &mut self.device
+    }
+
+    fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    fn draw_window(&mut self, ctx: &egui::Context, solution: &Solution, _nodes: &HashMap<(i32, i32), Node>, _elements: &[(u32, ElementType)]) -> WindowResponse {
+        let mut window = egui::Window::new(format!("MOSFET (id {})", self.id));
+
+        if self.window_hovered {
+            window = window.frame(
+                Frame::window(&ctx.style()).stroke(
+                    Stroke::new(1.0, egui::Color32::GREEN),
+                ),
+            );
+        }
+
+        let window_response = window.show(ctx, |ui| {
+            let mut changed = false;
+            let device = &mut self.device;
+            egui::ComboBox::from_label("Channel").selected_text(format!("{:?}", device.channel)).show_ui(ui, |ui| {
+                changed |= ui.selectable_value(&mut device.channel, MosfetChannel::N, "N").changed();
+                changed |= ui.selectable_value(&mut device.channel, MosfetChannel::P, "P").changed();
+            });
+            changed |= ui.add(egui::Slider::new(&mut device.threshold_voltage, 0.0..=5.0).text("Threshold (V)")).changed();
+            ui.label(format!("K: {:.2e} A/V²", device.transconductance));
+            changed |= ui.add(egui::Slider::new(&mut device.transconductance, 1.0e-5..=1.0e-1).logarithmic(true).text("K")).changed();
+            changed |= ui.add(egui::Slider::new(&mut device.channel_length_modulation, 0.0..=0.2).text("Lambda (1/V)")).changed();
+            current_labels(ui, solution, self.id);
+            changed
+        });
+
+        let changed = window_response.as_ref().and_then(|window| window.inner).unwrap_or(false);
+        if let Some(window) = window_response {
+            if window.response.hovered() || window.response.has_focus() {
+                self.window_hovered = true;
+                return WindowResponse { hovered: Some(window.response.rect.center()), changed };
+            }
+        }
+        self.window_hovered = false;
+        WindowResponse { hovered: None, changed }
+    }
+}
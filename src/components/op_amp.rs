@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use eframe::egui;
+use eframe::egui::{Align2, FontId, Frame, Pos2, Shape, Stroke, Vec2};
+use eframe::epaint::PathShape;
+use rusty_circuit::devices::{self, OpAmpModel};
+use rusty_circuit::{Device, ElementType};
+use rusty_circuit::circuit::Solution;
+use rusty_circuit::pins::{pin_positions, supply_positions, Orientation};
+use crate::{CircuitElement, Node, WindowResponse};
+use crate::components::current_labels;
+
+#[derive(Clone, Debug)]
+pub struct OpAmp {
+    pos: Pos2,
+    size: Vec2,
+    id: u32,
+    device: devices::OpAmp,
+    orientation: Orientation,
+    window_hovered: bool,
+}
+
+impl CircuitElement for OpAmp {
+    fn new_boxed(pos: Pos2, size: Vec2, id: u32, nodes: Vec<u32>) -> Box<dyn CircuitElement> {
+        Box::new(OpAmp { pos, size, id, device: devices::OpAmp::new(nodes, OpAmpModel::Ideal), orientation: Orientation::default(), window_hovered: false })
+    }
+
+    // triangle between the pins pointing at the output, the inputs lead into its back and are marked
+    // + and -, the supply pins lead into its sides
+    fn draw(&mut self, ui: &mut egui::Ui, stroke: Stroke, grid_step: f32, screen_pos: Pos2, _screen_size: Vec2, _nodes: &HashMap<(i32, i32), Node>) -> bool {
+        let to_screen = |(x, y): (i32, i32)| screen_pos + (Pos2::new(x as f32, y as f32) - self.pos) * grid_step;
+        let pins: Vec<Pos2> = self.pins().into_iter().map(to_screen).collect();
+        let (positive, negative, output) = (pins[0], pins[1], pins[2]);
+
+        let middle = positive + (negative - positive) / 2.0;
+        let axis = (output - middle).normalized();
+        let normal = (negative - positive).normalized();
+        let lead = axis * grid_step * 0.4;
+
+        let (back, tip) = (middle + lead, output - lead);
+        let half_width = grid_step * 1.4;
+        let triangle = PathShape::closed_line(vec![back + normal * half_width, back - normal * half_width, tip], stroke);
+        ui.painter().add(Shape::Path(triangle));
+
+        let font = FontId::proportional(grid_step * 0.5);
+        for (pin, sign) in [(positive, "+"), (negative, "−")] {
+            ui.painter().line_segment([pin, pin + lead], stroke);
+            ui.painter().text(pin + lead * 1.8, Align2::CENTER_CENTER, sign, font.clone(), stroke.color);
+        }
+        ui.painter().line_segment([tip, output], stroke);
+
+        // the sides are a grid step from the axis where the supply pins are
+        let side = middle + axis * grid_step;
+        for (pin, name) in pins[3..].iter().zip(["V+", "V−"]) {
+            let lead = (side - *pin).normalized() * grid_step * 0.95;
+            ui.painter().line_segment([*pin, *pin + lead], stroke);
+            ui.painter().text(*pin + axis * grid_step * 0.3, Align2::LEFT_CENTER, name, font.clone(), stroke.color);
+        }
+        false
+    }
+
+    // the supply pins come after the output
+    fn pins(&self) -> Vec<(i32, i32)> {
+        let anchor = (self.pos.x as i32, self.pos.y as i32);
+        let size = (self.size.x as i32, self.size.y as i32);
+        let mut pins = pin_positions(ElementType::OpAmp, anchor, size, self.orientation);
+        if self.device.supply {
+            pins.extend(supply_positions(ElementType::OpAmp, anchor, self.orientation));
+        }
+        pins
+    }
+
+    fn pos(&self) -> Pos2 {
+        self.pos
+    }
+
+    fn size(&self) -> Vec2 {
+        self.size
+    }
+
+    fn set_placement(&mut self, pos: Pos2, size: Vec2) {
+        self.pos = pos;
+        self.size = size;
+    }
+
+    fn device(&self) -> &dyn Device {
+        &self.device
+    }
+
+    fn device_mut(&mut self) -> &mut dyn Device {
+        &mut self.device
+    }
+
+    fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+    }
+
+    fn draw_window(&mut self, ctx: &egui::Context, solution: &Solution, _nodes: &HashMap<(i32, i32), Node>, _elements: &[(u32, ElementType)]) -> WindowResponse {
+        let mut window = egui::Window::new(format!("Op-amp (id {})", self.id));
+
+        if self.window_hovered {
+            window = window.frame(
+                Frame::window(&ctx.style()).stroke(
+                    Stroke::new(1.0, egui::Color32::GREEN),
+                ),
+            );
+        }
+
+        let window_response = window.show(ctx, |ui| {
+            let mut changed = false;
+            let device = &mut self.device;
+            egui::ComboBox::from_label("Model").selected_text(format!("{:?}", device.model)).show_ui(ui, |ui| {
+                changed |= ui.selectable_value(&mut device.model, OpAmpModel::Ideal, "Ideal").changed();
+                changed |= ui.selectable_value(&mut device.model, OpAmpModel::SinglePole, "SinglePole").changed();
+            });
+
+            // the ideal model has no parameters besides its rails
+            if device.model == OpAmpModel::SinglePole {
+                egui::Grid::new("op_amp").show(ui, |ui| {
+                    for (value, name) in [
+                        (&mut device.gain, "Open loop gain"),
+                        (&mut device.gain_bandwidth, "Gain bandwidth (Hz)"),
+                        (&mut device.input_resistance, "Input resistance (Ohm)"),
+                        (&mut device.output_resistance, "Output resistance (Ohm)"),
+                    ] {
+                        ui.label(name);
+                        let speed = value.abs().max(1.0e-3) * 0.01;
+                        changed |= ui.add(egui::DragValue::new(value).speed(speed).range(1.0e-3..=f64::INFINITY)).changed();
+                        ui.end_row();
+                    }
+                });
+            }
+
+            // the supply pins set the rails while the element has them
+            changed |= ui.checkbox(&mut device.supply, "Supply pins").changed();
+            if !device.supply {
+                let mut limited = device.rails.is_some();
+                if ui.checkbox(&mut limited, "Limit output").changed() {
+                    changed = true;
+                    device.rails = limited.then_some([-15.0, 15.0]);
+                }
+                if let Some([low, high]) = &mut device.rails {
+                    ui.horizontal(|ui| {
+                        changed |= ui.add(egui::DragValue::new(low).speed(0.1).prefix("Low: ").suffix(" V")).changed();
+                        changed |= ui.add(egui::DragValue::new(high).speed(0.1).prefix("High: ").suffix(" V")).changed();
+                    });
+                }
+            }
+
+            current_labels(ui, solution, self.id);
+            changed
+        });
+
+        let changed = window_response.as_ref().and_then(|window| window.inner).unwrap_or(false);
+        if let Some(window) = window_response {
+            if window.response.hovered() || window.response.has_focus() {
+                self.window_hovered = true;
+                return WindowResponse { hovered: Some(window.response.rect.center()), changed };
+            }
+        }
+        self.window_hovered = false;
+        WindowResponse { hovered: None, changed }
+    }
+}
@@ -1,29 +1,29 @@
-use std::collections::{BTreeMap, HashMap};
-use std::collections::hash_map::Values;
+use std::collections::HashMap;
 use eframe::egui;
-use eframe::egui::{Frame, Pos2, Rect, Shape, Stroke, Ui, Vec2};
-use eframe::egui::debug_text::print;
+use eframe::egui::{Frame, Pos2, Rect, Shape, Stroke, Vec2};
 use eframe::epaint::PathShape;
-use nalgebra::{DMatrix, DVector};
-use crate::{CircuitElement, ElementType, Node};
+use rusty_circuit::devices;
+use rusty_circuit::{Device, ElementType};
+use rusty_circuit::circuit::Solution;
+use crate::{CircuitElement, Node, WindowResponse};
+use crate::components::current_labels;
 
 #[derive(Clone, Debug)]
 
 pub struct Resistor {
     pos: Pos2,
     size: Vec2,
-    resistance: f64,
     id: u32,
-    nodes: Vec<u32>,
+    device: devices::Resistor,
     window_hovered: bool,
 }
 
 impl CircuitElement for Resistor {
     fn new_boxed(pos: Pos2, size: Vec2, id: u32, nodes: Vec<u32>) -> Box<dyn CircuitElement> {
-        Box::new(Resistor { pos, size, id, resistance: 10.0, nodes, window_hovered: false })
+        Box::new(Resistor { pos, size, id, device: devices::Resistor::new(nodes, 10.0), window_hovered: false })
     }
 
-    fn draw(&mut self, ui: &mut egui::Ui, stroke: Stroke, grid_step: f32, screen_pos: Pos2, screen_size: Vec2, nodes: &HashMap<(i32, i32), Node>) {
+    fn draw(&mut self, ui: &mut egui::Ui, stroke: Stroke, grid_step: f32, screen_pos: Pos2, screen_size: Vec2, nodes: &HashMap<(i32, i32), Node>) -> bool {
         let center = screen_pos + screen_size / 2.0;
 
         let normalized = Vec2::new(screen_size.x, screen_size.y) / screen_size.length();
@@ -44,14 +44,16 @@ impl CircuitElement for Resistor {
         ui.painter().line_segment([center + normalized * height, screen_pos + screen_size], stroke);
         ui.painter().line_segment([center - normalized * height, screen_pos], stroke);
 
-        if self.nodes.len() > 1 {
-            let node1 = nodes.values().find(|node| node.id == self.nodes[0]).unwrap();
-            let node2 = nodes.values().find(|node| node.id == self.nodes[1]).unwrap();
+        let device_nodes = self.device.get_nodes();
+        if device_nodes.len() > 1 {
+            let node1 = nodes.values().find(|node| node.id == device_nodes[0]).unwrap();
+            let node2 = nodes.values().find(|node| node.id == device_nodes[1]).unwrap();
             let voltage = node1.voltage - node2.voltage;
             ui.allocate_ui_at_rect(Rect::from_two_pos(center + Vec2::new(10.0, 0.0), center + Vec2::new(50.0, 50.0)), |ui| {
                 ui.label(format!("{:.2}V", voltage));
             });
         }
+        false
     }
 
     fn pos(&self) -> Pos2 {
@@ -62,36 +64,24 @@ impl CircuitElement for Resistor {
         self.size
     }
 
-    fn get_admittance(&self) -> f64 {
-        1.0 / self.resistance
+    fn set_placement(&mut self, pos: Pos2, size: Vec2) {
+        self.pos = pos;
+        self.size = size;
     }
 
-    fn get_id(&self) -> u32 {
-        self.id
-    }
-
-    fn get_type(&self) -> ElementType {
-        ElementType::Resistor
-    }
-    fn set_nodes(&mut self, nodes: Vec<u32>) {
-        self.nodes = nodes
+    fn device(&self) -> &dyn Device {
+        &self.device
     }
 
-    fn get_nodes(&self) -> Vec<u32> {
-        self.nodes.clone()
+    fn device_mut(&mut self) -> &mut dyn Device {
+        &mut self.device
     }
 
-    fn stamp_matrix(&self, matrix: &mut DMatrix<f64>, vector: &mut DVector<f64>, nodes: &Vec<Node>) {
-        let n1 = nodes.iter().position(|node| node.id == self.nodes[0]).unwrap();
-        let n2 = nodes.iter().position(|node| node.id == self.nodes[1]).unwrap();
-
-        matrix[(n1, n1)] += 1.0 / self.resistance;
-        matrix[(n2, n2)] += 1.0 / self.resistance;
-        matrix[(n1, n2)] -= 1.0 / self.resistance;
-        matrix[(n2, n1)] -= 1.0 / self.resistance;
+    fn get_id(&self) -> u32 {
+        self.id
     }
 
-    fn draw_window(&mut self, ctx: &egui::Context) -> Option<Pos2> {
+    fn draw_window(&mut self, ctx: &egui::Context, solution: &Solution, _nodes: &HashMap<(i32, i32), Node>, _elements: &[(u32, ElementType)]) -> WindowResponse {
         let mut window = egui::Window::new(format!("Resistor (id {})", self.id));
 
         if self.window_hovered {
@@ -102,20 +92,23 @@ impl CircuitElement for Resistor {
             );
         }
 
-
         let window_response = window.show(ctx, |ui| {
-            ui.label(format!("Resistance: {:.2} Ohms", self.resistance));
-            ui.add(egui::Slider::new(&mut self.resistance, 1.0..=1000.0).text("Resistance"));
+            let mut changed = false;
+            ui.label(format!("Resistance: {:.2} Ohms", self.device.resistance));
+            changed |= ui.add(egui::Slider::new(&mut self.device.resistance, 1.0..=1000.0).text("Resistance")).changed();
+            current_labels(ui, solution, self.id);
+            changed
         });
 
+        let changed = window_response.as_ref().and_then(|window| window.inner).unwrap_or(false);
         if let Some(window) = window_response {
             if window.response.hovered() || window.response.has_focus() {
                 self.window_hovered = true;
-                return Some(window.response.rect.center());
+                return WindowResponse { hovered: Some(window.response.rect.center()), changed };
             }
         }
         self.window_hovered = false;
-        None
+        WindowResponse { hovered: None, changed }
     }
 }
 
@@ -1,9 +1,9 @@
-use std::collections::{BTreeMap, HashMap};
-use std::collections::hash_map::Values;
+use std::collections::HashMap;
 use eframe::egui;
-use eframe::egui::{Pos2, Rect, Stroke, Vec2};
-use eframe::egui::accesskit::NodeClassSet;
-use crate::{CircuitElement, ElementType, Node};
+use eframe::egui::{Pos2, Stroke, Vec2};
+use rusty_circuit::devices;
+use rusty_circuit::Device;
+use crate::{CircuitElement, Node};
 
 #[derive(Clone, Debug)]
 
@@ -11,16 +11,17 @@ pub struct Wire {
     pos: Pos2,
     size: Vec2,
     id: u32,
-    nodes: Vec<u32>
+    device: devices::Wire,
 }
 
 impl CircuitElement for Wire {
     fn new_boxed(pos: Pos2, size: Vec2, id: u32, nodes: Vec<u32>) -> Box<dyn CircuitElement> {
-        Box::new(Wire { pos, size, id, nodes })
+        Box::new(Wire { pos, size, id, device: devices::Wire::new(nodes) })
     }
 
-    fn draw(&mut self, ui: &mut egui::Ui, stroke: Stroke, grid_step: f32, screen_pos: Pos2, screen_size: Vec2, nodes: &HashMap<(i32, i32), Node>) {
+    fn draw(&mut self, ui: &mut egui::Ui, stroke: Stroke, _grid_step: f32, screen_pos: Pos2, screen_size: Vec2, _nodes: &HashMap<(i32, i32), Node>) -> bool {
         ui.painter().line_segment([screen_pos, screen_pos + screen_size], stroke);
+        false
     }
 
     fn pos(&self) -> Pos2 {
@@ -31,27 +32,21 @@ impl CircuitElement for Wire {
         self.size
     }
 
-    fn get_admittance(&self) -> f64 {
-        f64::MAX
+    fn set_placement(&mut self, pos: Pos2, size: Vec2) {
+        self.pos = pos;
+        self.size = size;
     }
 
-    fn get_id(&self) -> u32 {
-        self.id
-    }
-
-    fn get_type(&self) -> ElementType {
-        ElementType::Wire
+    fn device(&self) -> &dyn Device {
+        &self.device
     }
 
-    fn shorted(&self) -> bool {
-        true
+    fn device_mut(&mut self) -> &mut dyn Device {
+        &mut self.device
     }
 
-    fn set_nodes(&mut self, nodes: Vec<u32>) {
-        self.nodes = nodes;
+    fn get_id(&self) -> u32 {
+        self.id
     }
 
-    fn get_nodes(&self) -> Vec<u32> {
-        self.nodes.clone()
-    }
 }
\ No newline at end of file
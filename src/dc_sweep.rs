@@ -0,0 +1,123 @@
+// dc sweep: one parameter of one element is stepped over a range and the operating point is solved
+// at every value, like the spice .dc analysis but for any numeric parameter (see Parameters::values_mut)
+//
+// a swept source loses its waveform, the sweep sets its dc value
+
+use std::fmt;
+use std::fmt::Write;
+use crate::circuit::{Circuit, Solution, Solver, SolverInfo, StepInfo};
+use crate::circuit_solver::SolverError;
+use crate::schematic::Parameters;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DcSweep {
+    pub element: u32,
+    // name of the parameter, as in the schematic file
+    pub parameter: String,
+    pub start: f64,
+    pub stop: f64,
+    // number of values, start and stop included
+    pub points: u32,
+}
+
+impl DcSweep {
+    pub fn values(&self) -> Vec<f64> {
+        if self.points <= 1 {
+            return vec![self.start];
+        }
+        let step = (self.stop - self.start) / (self.points - 1) as f64;
+        (0..self.points).map(|index| self.start + step * index as f64).collect()
+    }
+}
+
+#[derive(Debug)]
+pub enum DcSweepError {
+    UnknownElement(u32),
+    UnknownParameter { element: u32, parameter: String },
+    // the operating point could not be solved at the value
+    Solver { value: f64, error: SolverError },
+}
+
+impl fmt::Display for DcSweepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DcSweepError::UnknownElement(element) => write!(f, "There is no element {} to sweep", element),
+            DcSweepError::UnknownParameter { element, parameter } => write!(f, "Element {} has no parameter {}", element, parameter),
+            DcSweepError::Solver { value, error } => write!(f, "At {}: {}", value, error),
+        }
+    }
+}
+
+impl std::error::Error for DcSweepError {}
+
+// operating point at one value of the sweep
+#[derive(Debug, Clone)]
+pub struct DcSweepPoint {
+    pub value: f64,
+    pub solution: Solution,
+}
+
+// a column of the sweep table
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trace {
+    // voltage of a node against ground
+    Voltage(u32),
+    // current through an element from its first to its second node
+    Current(u32),
+}
+
+impl Trace {
+    pub fn value(&self, solution: &Solution) -> Option<f64> {
+        match self {
+            Trace::Voltage(node) => Some(solution.voltage(*node)),
+            Trace::Current(element) => solution.current(*element),
+        }
+    }
+}
+
+pub fn dc_sweep(circuit: &Circuit, sweep: &DcSweep) -> Result<Vec<DcSweepPoint>, DcSweepError> {
+    let mut circuit = circuit.clone();
+    let mut parameters = circuit.element(sweep.element).ok_or(DcSweepError::UnknownElement(sweep.element))?.parameters();
+    if let Parameters::DCVoltageSource { waveform, .. } | Parameters::CurrentSource { waveform, .. } = &mut parameters {
+        *waveform = None;
+    }
+    if !parameters.values_mut().iter().any(|(name, _)| *name == sweep.parameter) {
+        return Err(DcSweepError::UnknownParameter { element: sweep.element, parameter: sweep.parameter.clone() });
+    }
+
+    // one solver keeps the simplified circuit for all points, and every point starts newton from the
+    // operating point of the one before, which keeps it close to the solution through steep regions
+    let mut solver = Solver::new();
+    let mut points = Vec::new();
+    for value in sweep.values() {
+        for (name, parameter) in parameters.values_mut() {
+            if name == sweep.parameter {
+                *parameter = value;
+            }
+        }
+        if let Some(device) = circuit.element_mut(sweep.element) {
+            device.set_parameters(&parameters);
+        }
+        let solution = solver.solve(&circuit, &StepInfo::OPERATING_POINT, &mut SolverInfo::default())
+            .map_err(|error| DcSweepError::Solver { value, error })?;
+        circuit.linearize(&solution);
+        points.push(DcSweepPoint { value, solution });
+    }
+    Ok(points)
+}
+
+// comma separated table with the swept value in the first column and one column per trace,
+// values a trace does not have are left empty
+pub fn table(points: &[DcSweepPoint], swept: &str, traces: &[(String, Trace)]) -> String {
+    let mut table = String::new();
+    let header: Vec<&str> = [swept].into_iter().chain(traces.iter().map(|(name, _)| name.as_str())).collect();
+    writeln!(table, "{}", header.join(",")).unwrap();
+    for point in points {
+        let row: Vec<String> = [Some(point.value)].into_iter()
+            .chain(traces.iter().map(|(_, trace)| trace.value(&point.solution)))
+            .map(|value| value.map_or(String::new(), |value| value.to_string()))
+            .collect();
+        writeln!(table, "{}", row.join(",")).unwrap();
+    }
+    table
+}
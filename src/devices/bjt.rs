@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use nalgebra::DVector;
+use serde::{Deserialize, Serialize};
+use crate::{Device, ElementType};
+use crate::devices::diode::{limit_junction, MINIMUM_CONDUCTANCE, THERMAL_VOLTAGE};
+use crate::schematic::Parameters;
+use crate::circuit::{Solution, StepInfo};
+use crate::sparse_matrix::SparseMatrix;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BjtPolarity {
+    Npn,
+    // every voltage and current of an npn transistor turned around
+    Pnp,
+}
+
+// bipolar transistor in the ebers-moll transport model, with the base width modulation of gummel-poon
+// (the early effect) and without its high injection and charge terms, the nodes are the collector, the
+// base and the emitter
+#[derive(Clone, Debug)]
+pub struct Bjt {
+    nodes: Vec<u32>,
+    pub polarity: BjtPolarity,
+    // forward and reverse current gain
+    pub beta: f64,
+    pub reverse_beta: f64,
+    pub saturation_current: f64,
+    pub thermal_voltage: f64,
+    // forward early voltage, 0 turns the early effect off as in spice
+    pub early_voltage: f64,
+    // junction voltages the model is linearized around, forward biased is positive for both polarities
+    base_emitter: f64,
+    base_collector: f64,
+}
+
+// a current into a terminal with its derivatives by the base-emitter and the base-collector voltage
+type Linearized = (f64, f64, f64);
+
+impl Bjt {
+    pub fn new(nodes: Vec<u32>, polarity: BjtPolarity) -> Self {
+        Bjt {
+            nodes,
+            polarity,
+            beta: 100.0,
+            reverse_beta: 1.0,
+            saturation_current: 1.0e-14,
+            thermal_voltage: THERMAL_VOLTAGE,
+            early_voltage: 0.0,
+            base_emitter: 0.0,
+            base_collector: 0.0,
+        }
+    }
+
+    fn sign(&self) -> f64 {
+        match self.polarity {
+            BjtPolarity::Npn => 1.0,
+            BjtPolarity::Pnp => -1.0,
+        }
+    }
+
+    fn junction_voltages(&self, solution: &Solution) -> (f64, f64) {
+        let [collector, base, emitter] = [0, 1, 2].map(|index| solution.voltage(self.nodes[index]));
+        (self.sign() * (base - emitter), self.sign() * (base - collector))
+    }
+
+    // currents into the collector and the base of an npn transistor at the operating point
+    fn currents(&self) -> [Linearized; 2] {
+        let (vbe, vbc) = (self.base_emitter, self.base_collector);
+        let slope = self.thermal_voltage;
+        let forward_exponential = (vbe / slope).exp();
+        let reverse_exponential = (vbc / slope).exp();
+        let forward = self.saturation_current * (forward_exponential - 1.0);
+        let reverse = self.saturation_current * (reverse_exponential - 1.0);
+        let forward_conductance = self.saturation_current / slope * forward_exponential;
+        let reverse_conductance = self.saturation_current / slope * reverse_exponential;
+
+        // a reverse biased collector junction widens the base less, so more of the current gets across
+        let (early, early_slope) = if self.early_voltage > 0.0 {
+            (1.0 - vbc / self.early_voltage, -1.0 / self.early_voltage)
+        } else {
+            (1.0, 0.0)
+        };
+        let transport = (forward - reverse) * early;
+
+        let collector = (
+            transport - reverse / self.reverse_beta - MINIMUM_CONDUCTANCE * vbc,
+            forward_conductance * early,
+            -reverse_conductance * early + (forward - reverse) * early_slope - reverse_conductance / self.reverse_beta - MINIMUM_CONDUCTANCE,
+        );
+        let base = (
+            forward / self.beta + reverse / self.reverse_beta + MINIMUM_CONDUCTANCE * (vbe + vbc),
+            forward_conductance / self.beta + MINIMUM_CONDUCTANCE,
+            reverse_conductance / self.reverse_beta + MINIMUM_CONDUCTANCE,
+        );
+        [collector, base]
+    }
+
+    // currents into the collector and the base for the voltages of a solution
+    fn terminal(&self, solution: &Solution) -> [f64; 2] {
+        let (vbe, vbc) = self.junction_voltages(solution);
+        self.currents().map(|(current, by_base_emitter, by_base_collector)| {
+            self.sign() * (current + by_base_emitter * (vbe - self.base_emitter) + by_base_collector * (vbc - self.base_collector))
+        })
+    }
+}
+
+impl Device for Bjt {
+    fn get_type(&self) -> ElementType {
+        ElementType::Bjt
+    }
+
+    fn parameters(&self) -> Parameters {
+        Parameters::Bjt {
+            polarity: self.polarity,
+            beta: self.beta,
+            reverse_beta: self.reverse_beta,
+            saturation_current: self.saturation_current,
+            thermal_voltage: self.thermal_voltage,
+            early_voltage: self.early_voltage,
+        }
+    }
+
+    fn set_parameters(&mut self, parameters: &Parameters) {
+        if let Parameters::Bjt { polarity, beta, reverse_beta, saturation_current, thermal_voltage, early_voltage } = parameters {
+            self.polarity = *polarity;
+            self.beta = *beta;
+            self.reverse_beta = *reverse_beta;
+            self.saturation_current = *saturation_current;
+            self.thermal_voltage = *thermal_voltage;
+            self.early_voltage = *early_voltage;
+        }
+    }
+
+    fn is_conductive(&self) -> bool {
+        true
+    }
+
+    // the current of the element is the collector current, it comes out of the emitter
+    fn current_nodes(&self) -> (u32, u32) {
+        (self.nodes[0], self.nodes[2])
+    }
+
+    fn set_nodes(&mut self, nodes: Vec<u32>) {
+        self.nodes = nodes;
+    }
+
+    fn get_nodes(&self) -> Vec<u32> {
+        self.nodes.clone()
+    }
+
+    // companion model of both terminal currents, the emitter takes what goes into the other two
+    fn stamp_matrix(&self, matrix: &mut SparseMatrix, vector: &mut DVector<f64>, nodes: &HashMap<u32, usize>, _step: &StepInfo) {
+        let [collector, base, emitter] = [0, 1, 2].map(|index| nodes[&self.nodes[index]]);
+
+        for (row, (current, by_base_emitter, by_base_collector)) in [collector, base].into_iter().zip(self.currents()) {
+            let equivalent_current = self.sign() * (current - by_base_emitter * self.base_emitter - by_base_collector * self.base_collector);
+            for (target, direction) in [(row, 1.0), (emitter, -1.0)] {
+                matrix[(target, base)] += direction * (by_base_emitter + by_base_collector);
+                matrix[(target, emitter)] -= direction * by_base_emitter;
+                matrix[(target, collector)] -= direction * by_base_collector;
+                vector[target] -= direction * equivalent_current;
+            }
+        }
+    }
+
+    fn current(&self, solution: &Solution, _step: &StepInfo) -> f64 {
+        self.terminal(solution)[0]
+    }
+
+    fn terminal_currents(&self, solution: &Solution, _step: &StepInfo) -> Option<Vec<f64>> {
+        let [collector, base] = self.terminal(solution);
+        Some(vec![collector, base, -collector - base])
+    }
+
+    fn is_nonlinear(&self) -> bool {
+        true
+    }
+
+    // both junctions are limited like the junction of a diode
+    fn linearize(&mut self, solution: &Solution) -> bool {
+        let (base_emitter, base_collector) = self.junction_voltages(solution);
+        let limit = |new, old| limit_junction(new, old, self.thermal_voltage, self.saturation_current);
+        let limited = (limit(base_emitter, self.base_emitter), limit(base_collector, self.base_collector));
+        (self.base_emitter, self.base_collector) = limited;
+        limited != (base_emitter, base_collector)
+    }
+
+    fn update_state(&mut self, solution: &Solution, _step: &StepInfo) {
+        self.linearize(solution);
+    }
+
+    fn reset_state(&mut self) {
+        self.base_emitter = 0.0;
+        self.base_collector = 0.0;
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+use nalgebra::{Complex, DVector};
+use crate::{Device, ElementType};
+use crate::schematic::Parameters;
+use crate::circuit::{Solution, StepInfo};
+use crate::sparse_matrix::SparseMatrix;
+
+#[derive(Clone, Debug)]
+pub struct Capacitor {
+    nodes: Vec<u32>,
+    pub capacitance: f64,
+    // voltage across the capacitor at the end of the last time step
+    voltage: f64,
+}
+
+impl Capacitor {
+    pub fn new(nodes: Vec<u32>, capacitance: f64) -> Self {
+        Capacitor { nodes, capacitance, voltage: 0.0 }
+    }
+
+    pub fn voltage(&self) -> f64 {
+        self.voltage
+    }
+}
+
+impl Device for Capacitor {
+    fn get_type(&self) -> ElementType {
+        ElementType::Capacitor
+    }
+
+    fn parameters(&self) -> Parameters {
+        Parameters::Capacitor { capacitance: self.capacitance }
+    }
+
+    fn set_parameters(&mut self, parameters: &Parameters) {
+        if let Parameters::Capacitor { capacitance } = parameters {
+            self.capacitance = *capacitance;
+        }
+    }
+
+    // open at the operating point, where the companion conductance C/dt is 0
+    fn is_conductive_at(&self, step: &StepInfo) -> bool {
+        step.timestep.is_finite()
+    }
+
+    fn set_nodes(&mut self, nodes: Vec<u32>) {
+        self.nodes = nodes;
+    }
+
+    fn get_nodes(&self) -> Vec<u32> {
+        self.nodes.clone()
+    }
+
+    // backward euler companion model: a conductance C/dt in parallel with a current source
+    // that keeps the charge of the previous step
+    fn stamp_matrix(&self, matrix: &mut SparseMatrix, vector: &mut DVector<f64>, nodes: &HashMap<u32, usize>, step: &StepInfo) {
+        let n1 = nodes[&self.nodes[0]];
+        let n2 = nodes[&self.nodes[1]];
+
+        let conductance = self.capacitance / step.timestep;
+        let current = conductance * self.voltage;
+
+        matrix[(n1, n1)] += conductance;
+        matrix[(n2, n2)] += conductance;
+        matrix[(n1, n2)] -= conductance;
+        matrix[(n2, n1)] -= conductance;
+
+        vector[n1] += current;
+        vector[n2] -= current;
+    }
+
+    // admittance j omega C
+    fn stamp_ac(&self, matrix: &mut SparseMatrix<Complex<f64>>, _vector: &mut DVector<Complex<f64>>, nodes: &HashMap<u32, usize>, frequency: f64) {
+        let n1 = nodes[&self.nodes[0]];
+        let n2 = nodes[&self.nodes[1]];
+        let admittance = Complex::new(0.0, TAU * frequency * self.capacitance);
+
+        matrix[(n1, n1)] += admittance;
+        matrix[(n2, n2)] += admittance;
+        matrix[(n1, n2)] -= admittance;
+        matrix[(n2, n1)] -= admittance;
+    }
+
+    // the current of the companion model, zero at the operating point
+    fn current(&self, solution: &Solution, step: &StepInfo) -> f64 {
+        let voltage = solution.voltage(self.nodes[0]) - solution.voltage(self.nodes[1]);
+        self.capacitance / step.timestep * (voltage - self.voltage)
+    }
+
+    fn update_state(&mut self, solution: &Solution, _step: &StepInfo) {
+        self.voltage = solution.voltage(self.nodes[0]) - solution.voltage(self.nodes[1]);
+    }
+
+    fn reset_state(&mut self) {
+        self.voltage = 0.0;
+    }
+}
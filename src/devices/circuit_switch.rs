@@ -0,0 +1,46 @@
+use crate::{Device, ElementType};
+use crate::schematic::Parameters;
+
+#[derive(Clone, Debug)]
+pub struct Switch {
+    nodes: Vec<u32>,
+    pub closed: bool,
+}
+
+impl Switch {
+    pub fn new(nodes: Vec<u32>, closed: bool) -> Self {
+        Switch { nodes, closed }
+    }
+}
+
+impl Device for Switch {
+    fn get_type(&self) -> ElementType {
+        ElementType::Switch
+    }
+
+    fn parameters(&self) -> Parameters {
+        Parameters::Switch { closed: self.closed }
+    }
+
+    fn set_parameters(&mut self, parameters: &Parameters) {
+        if let Parameters::Switch { closed } = parameters {
+            self.closed = *closed;
+        }
+    }
+
+    fn get_admittance(&self) -> f64 {
+        0.0
+    }
+
+    fn shorted(&self) -> bool {
+        self.closed
+    }
+
+    fn set_nodes(&mut self, nodes: Vec<u32>) {
+        self.nodes = nodes;
+    }
+
+    fn get_nodes(&self) -> Vec<u32> {
+        self.nodes.clone()
+    }
+}
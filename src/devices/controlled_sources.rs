@@ -0,0 +1,295 @@
+// linear dependent sources
+//
+// the output is between the first two nodes, oriented like the independent sources: the voltage of a
+// voltage output is the second node against the first, the current of a current output flows from the
+// first node through the source to the second
+// voltage controlled sources sense the voltage of their third node against their fourth, without those
+// two nodes the control is 0 V
+// current controlled sources sense the branch current of another element (a voltage source, an
+// inductor or a voltage output), the solver tells them where that current is in the matrix
+
+use std::collections::HashMap;
+use nalgebra::DVector;
+use crate::{Device, ElementType};
+use crate::schematic::Parameters;
+use crate::circuit::{Solution, StepInfo};
+use crate::sparse_matrix::SparseMatrix;
+
+// row or column of the controlling voltage, None without sense nodes
+fn sense_rows(nodes: &[u32], indices: &HashMap<u32, usize>) -> Option<(usize, usize)> {
+    Some((indices[nodes.get(2)?], indices[nodes.get(3)?]))
+}
+
+fn sense_voltage(nodes: &[u32], solution: &Solution) -> f64 {
+    match (nodes.get(2), nodes.get(3)) {
+        (Some(positive), Some(negative)) => solution.voltage(*positive) - solution.voltage(*negative),
+        _ => 0.0,
+    }
+}
+
+// voltage output: v2 - v1 = control, with the control written as coefficients of the unknowns
+fn stamp_voltage_output(matrix: &mut SparseMatrix, nodes: &[u32], indices: &HashMap<u32, usize>, voltage_node: u32) -> usize {
+    let node1 = indices[&nodes[0]];
+    let node2 = indices[&nodes[1]];
+    let voltage_node = indices.len() + voltage_node as usize;
+
+    matrix[(voltage_node, node1)] += 1.0;
+    matrix[(voltage_node, node2)] -= 1.0;
+    matrix[(node1, voltage_node)] += 1.0;
+    matrix[(node2, voltage_node)] -= 1.0;
+    voltage_node
+}
+
+// voltage controlled voltage source
+#[derive(Clone, Debug)]
+pub struct Vcvs {
+    nodes: Vec<u32>,
+    pub gain: f64,
+    voltage_node: u32,
+}
+
+impl Vcvs {
+    pub fn new(nodes: Vec<u32>, gain: f64) -> Self {
+        Vcvs { nodes, gain, voltage_node: 0 }
+    }
+}
+
+impl Device for Vcvs {
+    fn get_type(&self) -> ElementType {
+        ElementType::Vcvs
+    }
+
+    fn parameters(&self) -> Parameters {
+        Parameters::Vcvs { gain: self.gain }
+    }
+
+    fn set_parameters(&mut self, parameters: &Parameters) {
+        if let Parameters::Vcvs { gain } = parameters {
+            self.gain = *gain;
+        }
+    }
+
+    fn is_conductive(&self) -> bool {
+        true
+    }
+
+    fn conductive_nodes(&self) -> Vec<u32> {
+        self.nodes[..2].to_vec()
+    }
+
+    fn set_nodes(&mut self, nodes: Vec<u32>) {
+        self.nodes = nodes;
+    }
+
+    fn get_nodes(&self) -> Vec<u32> {
+        self.nodes.clone()
+    }
+
+    fn get_voltage_source_count(&self) -> u32 {
+        1
+    }
+
+    fn set_voltage_node(&mut self, node: u32) {
+        self.voltage_node = node;
+    }
+
+    // v1 - v2 + gain * (v3 - v4) = 0
+    fn stamp_matrix(&self, matrix: &mut SparseMatrix, _vector: &mut DVector<f64>, nodes: &HashMap<u32, usize>, _step: &StepInfo) {
+        let voltage_node = stamp_voltage_output(matrix, &self.nodes, nodes, self.voltage_node);
+        if let Some((positive, negative)) = sense_rows(&self.nodes, nodes) {
+            matrix[(voltage_node, positive)] += self.gain;
+            matrix[(voltage_node, negative)] -= self.gain;
+        }
+    }
+}
+
+// voltage controlled current source
+#[derive(Clone, Debug)]
+pub struct Vccs {
+    nodes: Vec<u32>,
+    pub transconductance: f64,
+}
+
+impl Vccs {
+    pub fn new(nodes: Vec<u32>, transconductance: f64) -> Self {
+        Vccs { nodes, transconductance }
+    }
+}
+
+impl Device for Vccs {
+    fn get_type(&self) -> ElementType {
+        ElementType::Vccs
+    }
+
+    fn parameters(&self) -> Parameters {
+        Parameters::Vccs { transconductance: self.transconductance }
+    }
+
+    fn set_parameters(&mut self, parameters: &Parameters) {
+        if let Parameters::Vccs { transconductance } = parameters {
+            self.transconductance = *transconductance;
+        }
+    }
+
+    fn conductive_nodes(&self) -> Vec<u32> {
+        self.nodes[..2].to_vec()
+    }
+
+    fn set_nodes(&mut self, nodes: Vec<u32>) {
+        self.nodes = nodes;
+    }
+
+    fn get_nodes(&self) -> Vec<u32> {
+        self.nodes.clone()
+    }
+
+    fn current(&self, solution: &Solution, _step: &StepInfo) -> f64 {
+        self.transconductance * sense_voltage(&self.nodes, solution)
+    }
+
+    fn stamp_matrix(&self, matrix: &mut SparseMatrix, _vector: &mut DVector<f64>, nodes: &HashMap<u32, usize>, _step: &StepInfo) {
+        let Some((positive, negative)) = sense_rows(&self.nodes, nodes) else {
+            return;
+        };
+        let node1 = nodes[&self.nodes[0]];
+        let node2 = nodes[&self.nodes[1]];
+
+        matrix[(node1, positive)] += self.transconductance;
+        matrix[(node1, negative)] -= self.transconductance;
+        matrix[(node2, positive)] -= self.transconductance;
+        matrix[(node2, negative)] += self.transconductance;
+    }
+}
+
+// current controlled voltage source
+#[derive(Clone, Debug)]
+pub struct Ccvs {
+    nodes: Vec<u32>,
+    pub transresistance: f64,
+    pub control: Option<u32>,
+    voltage_node: u32,
+    control_branch: Option<u32>,
+}
+
+impl Ccvs {
+    pub fn new(nodes: Vec<u32>, transresistance: f64, control: Option<u32>) -> Self {
+        Ccvs { nodes, transresistance, control, voltage_node: 0, control_branch: None }
+    }
+}
+
+impl Device for Ccvs {
+    fn get_type(&self) -> ElementType {
+        ElementType::Ccvs
+    }
+
+    fn parameters(&self) -> Parameters {
+        Parameters::Ccvs { transresistance: self.transresistance, control: self.control }
+    }
+
+    fn set_parameters(&mut self, parameters: &Parameters) {
+        if let Parameters::Ccvs { transresistance, control } = parameters {
+            self.transresistance = *transresistance;
+            self.control = *control;
+        }
+    }
+
+    fn is_conductive(&self) -> bool {
+        true
+    }
+
+    fn set_nodes(&mut self, nodes: Vec<u32>) {
+        self.nodes = nodes;
+    }
+
+    fn get_nodes(&self) -> Vec<u32> {
+        self.nodes.clone()
+    }
+
+    fn get_voltage_source_count(&self) -> u32 {
+        1
+    }
+
+    fn set_voltage_node(&mut self, node: u32) {
+        self.voltage_node = node;
+    }
+
+    fn control_element(&self) -> Option<u32> {
+        self.control
+    }
+
+    fn set_control_branch(&mut self, branch: u32) {
+        self.control_branch = Some(branch);
+    }
+
+    // v1 - v2 + transresistance * i = 0
+    fn stamp_matrix(&self, matrix: &mut SparseMatrix, _vector: &mut DVector<f64>, nodes: &HashMap<u32, usize>, _step: &StepInfo) {
+        let voltage_node = stamp_voltage_output(matrix, &self.nodes, nodes, self.voltage_node);
+        if let Some(branch) = self.control_branch {
+            matrix[(voltage_node, nodes.len() + branch as usize)] += self.transresistance;
+        }
+    }
+}
+
+// current controlled current source
+#[derive(Clone, Debug)]
+pub struct Cccs {
+    nodes: Vec<u32>,
+    pub gain: f64,
+    pub control: Option<u32>,
+    control_branch: Option<u32>,
+}
+
+impl Cccs {
+    pub fn new(nodes: Vec<u32>, gain: f64, control: Option<u32>) -> Self {
+        Cccs { nodes, gain, control, control_branch: None }
+    }
+}
+
+impl Device for Cccs {
+    fn get_type(&self) -> ElementType {
+        ElementType::Cccs
+    }
+
+    fn parameters(&self) -> Parameters {
+        Parameters::Cccs { gain: self.gain, control: self.control }
+    }
+
+    fn set_parameters(&mut self, parameters: &Parameters) {
+        if let Parameters::Cccs { gain, control } = parameters {
+            self.gain = *gain;
+            self.control = *control;
+        }
+    }
+
+    fn set_nodes(&mut self, nodes: Vec<u32>) {
+        self.nodes = nodes;
+    }
+
+    fn get_nodes(&self) -> Vec<u32> {
+        self.nodes.clone()
+    }
+
+    fn control_element(&self) -> Option<u32> {
+        self.control
+    }
+
+    fn set_control_branch(&mut self, branch: u32) {
+        self.control_branch = Some(branch);
+    }
+
+    fn current(&self, solution: &Solution, _step: &StepInfo) -> f64 {
+        self.control.and_then(|control| solution.branch_current(control)).map_or(0.0, |current| self.gain * current)
+    }
+
+    fn stamp_matrix(&self, matrix: &mut SparseMatrix, _vector: &mut DVector<f64>, nodes: &HashMap<u32, usize>, _step: &StepInfo) {
+        let Some(branch) = self.control_branch else {
+            return;
+        };
+        let node1 = nodes[&self.nodes[0]];
+        let node2 = nodes[&self.nodes[1]];
+        let branch = nodes.len() + branch as usize;
+
+        matrix[(node1, branch)] += self.gain;
+        matrix[(node2, branch)] -= self.gain;
+    }
+}
@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use nalgebra::{Complex, DVector};
+use crate::{Device, ElementType};
+use crate::ac::AcSource;
+use crate::schematic::Parameters;
+use crate::circuit::{Solution, StepInfo};
+use crate::sparse_matrix::SparseMatrix;
+use crate::waveform::Waveform;
+
+// drives the current from the first node through the source to the second node
+// with a waveform the current follows it and the constant current is not used
+// without an ac value the source is open in ac analysis
+#[derive(Clone, Debug)]
+pub struct CurrentSource {
+    nodes: Vec<u32>,
+    pub current: f64,
+    pub waveform: Option<Waveform>,
+    pub ac: Option<AcSource>,
+}
+
+impl CurrentSource {
+    pub fn new(nodes: Vec<u32>, current: f64) -> Self {
+        CurrentSource { nodes, current, waveform: None, ac: None }
+    }
+
+    pub fn current_at(&self, time: f64) -> f64 {
+        self.waveform.as_ref().map_or(self.current, |waveform| waveform.value(time))
+    }
+}
+
+impl Device for CurrentSource {
+    fn get_type(&self) -> ElementType {
+        ElementType::CurrentSource
+    }
+
+    fn parameters(&self) -> Parameters {
+        Parameters::CurrentSource { current: self.current, waveform: self.waveform.clone(), ac: self.ac }
+    }
+
+    fn set_parameters(&mut self, parameters: &Parameters) {
+        if let Parameters::CurrentSource { current, waveform, ac } = parameters {
+            self.current = *current;
+            self.waveform = waveform.clone();
+            self.ac = *ac;
+        }
+    }
+
+    fn set_nodes(&mut self, nodes: Vec<u32>) {
+        self.nodes = nodes;
+    }
+
+    fn get_nodes(&self) -> Vec<u32> {
+        self.nodes.clone()
+    }
+
+    fn current(&self, _solution: &Solution, step: &StepInfo) -> f64 {
+        self.current_at(step.time)
+    }
+
+    fn next_breakpoint(&self, time: f64) -> Option<f64> {
+        self.waveform.as_ref()?.next_breakpoint(time)
+    }
+
+    fn stamp_matrix(&self, _matrix: &mut SparseMatrix, vector: &mut DVector<f64>, nodes: &HashMap<u32, usize>, step: &StepInfo) {
+        let n1 = nodes[&self.nodes[0]];
+        let n2 = nodes[&self.nodes[1]];
+        let current = self.current_at(step.time);
+
+        vector[n1] -= current;
+        vector[n2] += current;
+    }
+
+    fn stamp_ac(&self, _matrix: &mut SparseMatrix<Complex<f64>>, vector: &mut DVector<Complex<f64>>, nodes: &HashMap<u32, usize>, _frequency: f64) {
+        let Some(ac) = self.ac else {
+            return;
+        };
+        let n1 = nodes[&self.nodes[0]];
+        let n2 = nodes[&self.nodes[1]];
+
+        vector[n1] -= ac.phasor();
+        vector[n2] += ac.phasor();
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use nalgebra::{Complex, DVector};
+use crate::{Device, ElementType};
+use crate::ac::AcSource;
+use crate::schematic::Parameters;
+use crate::circuit::StepInfo;
+use crate::sparse_matrix::SparseMatrix;
+use crate::waveform::Waveform;
+
+// the second node is the positive terminal
+// with a waveform the voltage follows it and the constant voltage is not used
+// without an ac value the source is a short in ac analysis
+#[derive(Clone, Debug)]
+pub struct DCVoltageSource {
+    nodes: Vec<u32>,
+    pub voltage: f64,
+    pub waveform: Option<Waveform>,
+    pub ac: Option<AcSource>,
+    voltage_node: u32,
+}
+
+impl DCVoltageSource {
+    pub fn new(nodes: Vec<u32>, voltage: f64) -> Self {
+        DCVoltageSource { nodes, voltage, waveform: None, ac: None, voltage_node: 0 }
+    }
+
+    pub fn voltage_at(&self, time: f64) -> f64 {
+        self.waveform.as_ref().map_or(self.voltage, |waveform| waveform.value(time))
+    }
+}
+
+impl Device for DCVoltageSource {
+    fn get_type(&self) -> ElementType {
+        ElementType::DCVoltageSource
+    }
+
+    fn parameters(&self) -> Parameters {
+        Parameters::DCVoltageSource { voltage: self.voltage, waveform: self.waveform.clone(), ac: self.ac }
+    }
+
+    fn set_parameters(&mut self, parameters: &Parameters) {
+        if let Parameters::DCVoltageSource { voltage, waveform, ac } = parameters {
+            self.voltage = *voltage;
+            self.waveform = waveform.clone();
+            self.ac = *ac;
+        }
+    }
+
+    fn is_conductive(&self) -> bool {
+        true
+    }
+
+    fn set_nodes(&mut self, nodes: Vec<u32>) {
+        self.nodes = nodes;
+    }
+
+    fn get_nodes(&self) -> Vec<u32> {
+        self.nodes.clone()
+    }
+
+    fn get_voltage_source_count(&self) -> u32 {
+        1
+    }
+
+    fn set_voltage_node(&mut self, node: u32) {
+        self.voltage_node = node;
+    }
+
+    fn next_breakpoint(&self, time: f64) -> Option<f64> {
+        self.waveform.as_ref()?.next_breakpoint(time)
+    }
+
+    // v1 - v2 = -V, the current unknown flows from the first node through the source to the second node
+    fn stamp_matrix(&self, matrix: &mut SparseMatrix, vector: &mut DVector<f64>, nodes: &HashMap<u32, usize>, step: &StepInfo) {
+        let node1 = nodes[&self.nodes[0]];
+        let node2 = nodes[&self.nodes[1]];
+        let voltage_node = nodes.len() + self.voltage_node as usize;
+
+        matrix[(voltage_node, node1)] += 1.0;
+        matrix[(voltage_node, node2)] -= 1.0;
+        vector[voltage_node] = -self.voltage_at(step.time);
+        matrix[(node1, voltage_node)] += 1.0;
+        matrix[(node2, voltage_node)] -= 1.0;
+    }
+
+    fn stamp_ac(&self, _matrix: &mut SparseMatrix<Complex<f64>>, vector: &mut DVector<Complex<f64>>, nodes: &HashMap<u32, usize>, _frequency: f64) {
+        let voltage_node = nodes.len() + self.voltage_node as usize;
+        vector[voltage_node] = -self.ac.map_or(Complex::default(), |ac| ac.phasor());
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use nalgebra::DVector;
+use crate::{Device, ElementType};
+use crate::schematic::Parameters;
+use crate::circuit::{Solution, StepInfo};
+use crate::sparse_matrix::SparseMatrix;
+
+// kT/q at 300 K
+pub const THERMAL_VOLTAGE: f64 = 0.025852;
+// conductance across the junction that keeps a reverse biased diode from floating its node
+pub(crate) const MINIMUM_CONDUCTANCE: f64 = 1.0e-12;
+
+// junction limiting as in spice (pnjlim): large forward steps of a pn junction with the given slope
+// (emission coefficient times thermal voltage) only move its voltage logarithmically
+pub(crate) fn limit_junction(new: f64, old: f64, slope: f64, saturation_current: f64) -> f64 {
+    // above this voltage the exponential grows too fast for an unlimited newton step
+    let critical_voltage = slope * (slope / (std::f64::consts::SQRT_2 * saturation_current)).ln();
+    if new > critical_voltage && (new - old).abs() > 2.0 * slope {
+        if old > 0.0 {
+            let argument = 1.0 + (new - old) / slope;
+            if argument > 0.0 { old + slope * argument.ln() } else { critical_voltage }
+        } else {
+            slope * (new / slope).ln()
+        }
+    } else {
+        new
+    }
+}
+
+// shockley diode with a series resistance, the first node is the anode
+#[derive(Clone, Debug)]
+pub struct Diode {
+    nodes: Vec<u32>,
+    pub saturation_current: f64,
+    pub emission_coefficient: f64,
+    pub series_resistance: f64,
+    // voltage across the junction (without the series resistance) the model is linearized around
+    junction_voltage: f64,
+}
+
+impl Diode {
+    pub fn new(nodes: Vec<u32>, saturation_current: f64, emission_coefficient: f64, series_resistance: f64) -> Self {
+        Diode { nodes, saturation_current, emission_coefficient, series_resistance, junction_voltage: 0.0 }
+    }
+
+    fn slope(&self) -> f64 {
+        self.emission_coefficient * THERMAL_VOLTAGE
+    }
+
+    // current and conductance of the junction
+    fn junction(&self, voltage: f64) -> (f64, f64) {
+        let exponential = (voltage / self.slope()).exp();
+        let current = self.saturation_current * (exponential - 1.0) + MINIMUM_CONDUCTANCE * voltage;
+        let conductance = self.saturation_current / self.slope() * exponential + MINIMUM_CONDUCTANCE;
+        (current, conductance)
+    }
+
+    // the junction and the series resistance linearized together:
+    // (voltage across both at the operating point, current, conductance)
+    fn operating_point(&self) -> (f64, f64, f64) {
+        let (current, conductance) = self.junction(self.junction_voltage);
+        let voltage = self.junction_voltage + self.series_resistance * current;
+        (voltage, current, conductance / (1.0 + conductance * self.series_resistance))
+    }
+
+    fn terminal_voltage(&self, solution: &Solution) -> f64 {
+        solution.voltage(self.nodes[0]) - solution.voltage(self.nodes[1])
+    }
+}
+
+impl Device for Diode {
+    fn get_type(&self) -> ElementType {
+        ElementType::Diode
+    }
+
+    fn parameters(&self) -> Parameters {
+        Parameters::Diode {
+            saturation_current: self.saturation_current,
+            emission_coefficient: self.emission_coefficient,
+            series_resistance: self.series_resistance,
+        }
+    }
+
+    fn set_parameters(&mut self, parameters: &Parameters) {
+        if let Parameters::Diode { saturation_current, emission_coefficient, series_resistance } = parameters {
+            self.saturation_current = *saturation_current;
+            self.emission_coefficient = *emission_coefficient;
+            self.series_resistance = *series_resistance;
+        }
+    }
+
+    fn is_conductive(&self) -> bool {
+        true
+    }
+
+    fn set_nodes(&mut self, nodes: Vec<u32>) {
+        self.nodes = nodes;
+    }
+
+    fn get_nodes(&self) -> Vec<u32> {
+        self.nodes.clone()
+    }
+
+    // companion model: the conductance of the operating point in parallel with a current source that
+    // makes up the difference to the real current
+    fn stamp_matrix(&self, matrix: &mut SparseMatrix, vector: &mut DVector<f64>, nodes: &HashMap<u32, usize>, _step: &StepInfo) {
+        let n1 = nodes[&self.nodes[0]];
+        let n2 = nodes[&self.nodes[1]];
+
+        let (voltage, current, conductance) = self.operating_point();
+        let equivalent_current = current - conductance * voltage;
+
+        matrix[(n1, n1)] += conductance;
+        matrix[(n2, n2)] += conductance;
+        matrix[(n1, n2)] -= conductance;
+        matrix[(n2, n1)] -= conductance;
+
+        vector[n1] -= equivalent_current;
+        vector[n2] += equivalent_current;
+    }
+
+    fn current(&self, solution: &Solution, _step: &StepInfo) -> f64 {
+        let (voltage, current, conductance) = self.operating_point();
+        current + conductance * (self.terminal_voltage(solution) - voltage)
+    }
+
+    fn is_nonlinear(&self) -> bool {
+        true
+    }
+
+    fn linearize(&mut self, solution: &Solution) -> bool {
+        // the change of the terminal voltage is split between the junction and the series resistance
+        let (voltage, _, conductance) = self.operating_point();
+        let junction_conductance = self.junction(self.junction_voltage).1;
+        let step = (self.terminal_voltage(solution) - voltage) * conductance / junction_conductance;
+
+        let new = self.junction_voltage + step;
+        let limited = limit_junction(new, self.junction_voltage, self.slope(), self.saturation_current);
+        self.junction_voltage = limited;
+        limited != new
+    }
+
+    // the operating point of the last step is a good first guess for the next one
+    fn update_state(&mut self, solution: &Solution, _step: &StepInfo) {
+        self.linearize(solution);
+    }
+
+    fn reset_state(&mut self) {
+        self.junction_voltage = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SATURATION_CURRENT: f64 = 1.0e-14;
+
+    fn limit(new: f64, old: f64) -> f64 {
+        limit_junction(new, old, THERMAL_VOLTAGE, SATURATION_CURRENT)
+    }
+
+    #[test]
+    fn large_forward_step_is_limited() {
+        // from a forward biased junction the step only grows logarithmically
+        let limited = limit(5.0, 0.6);
+        assert!((limited - (0.6 + THERMAL_VOLTAGE * (1.0 + 4.4 / THERMAL_VOLTAGE).ln())).abs() < 1.0e-12);
+        assert!(limited > 0.6 && limited < 0.8, "limited to {} V", limited);
+        // the current at the limited voltage is still a number newton can work with
+        assert!((SATURATION_CURRENT * (limited / THERMAL_VOLTAGE).exp()).is_finite());
+
+        // from a junction that is off the new voltage is taken on a log scale of thermal voltages
+        let limited = limit(5.0, 0.0);
+        assert!((limited - THERMAL_VOLTAGE * (5.0 / THERMAL_VOLTAGE).ln()).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn small_and_reverse_steps_are_kept() {
+        // below the critical voltage of about 0.73 V the exponential is harmless
+        assert_eq!(limit(0.5, 0.0), 0.5);
+        // a step of less than two thermal voltages is never limited
+        assert_eq!(limit(0.8, 0.78), 0.8);
+        assert_eq!(limit(-5.0, 0.6), -5.0);
+    }
+}
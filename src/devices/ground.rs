@@ -0,0 +1,32 @@
+use crate::{Device, ElementType};
+use crate::schematic::Parameters;
+
+// connects its only node to the ground node
+#[derive(Clone, Debug)]
+pub struct Ground {
+    nodes: Vec<u32>,
+}
+
+impl Ground {
+    pub fn new(nodes: Vec<u32>) -> Self {
+        Ground { nodes }
+    }
+}
+
+impl Device for Ground {
+    fn get_type(&self) -> ElementType {
+        ElementType::Ground
+    }
+
+    fn parameters(&self) -> Parameters {
+        Parameters::Ground
+    }
+
+    fn set_nodes(&mut self, nodes: Vec<u32>) {
+        self.nodes = nodes;
+    }
+
+    fn get_nodes(&self) -> Vec<u32> {
+        self.nodes.clone()
+    }
+}
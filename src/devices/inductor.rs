@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+use nalgebra::{Complex, DVector};
+use crate::{Device, ElementType};
+use crate::schematic::Parameters;
+use crate::circuit::{Solution, StepInfo};
+use crate::sparse_matrix::SparseMatrix;
+
+#[derive(Clone, Debug)]
+pub struct Inductor {
+    nodes: Vec<u32>,
+    pub inductance: f64,
+    // current from the first to the second node at the end of the last time step
+    current: f64,
+    voltage_node: u32,
+}
+
+impl Inductor {
+    pub fn new(nodes: Vec<u32>, inductance: f64) -> Self {
+        Inductor { nodes, inductance, current: 0.0, voltage_node: 0 }
+    }
+
+    pub fn current(&self) -> f64 {
+        self.current
+    }
+}
+
+impl Device for Inductor {
+    fn get_type(&self) -> ElementType {
+        ElementType::Inductor
+    }
+
+    fn parameters(&self) -> Parameters {
+        Parameters::Inductor { inductance: self.inductance }
+    }
+
+    fn set_parameters(&mut self, parameters: &Parameters) {
+        if let Parameters::Inductor { inductance } = parameters {
+            self.inductance = *inductance;
+        }
+    }
+
+    fn is_conductive(&self) -> bool {
+        true
+    }
+
+    fn set_nodes(&mut self, nodes: Vec<u32>) {
+        self.nodes = nodes;
+    }
+
+    fn get_nodes(&self) -> Vec<u32> {
+        self.nodes.clone()
+    }
+
+    // the current through the inductor is an extra unknown, like the current of a voltage source
+    fn get_voltage_source_count(&self) -> u32 {
+        1
+    }
+
+    fn set_voltage_node(&mut self, node: u32) {
+        self.voltage_node = node;
+    }
+
+    // backward euler: v1 - v2 = L/dt * (i - i_previous)
+    fn stamp_matrix(&self, matrix: &mut SparseMatrix, vector: &mut DVector<f64>, nodes: &HashMap<u32, usize>, step: &StepInfo) {
+        let node1 = nodes[&self.nodes[0]];
+        let node2 = nodes[&self.nodes[1]];
+        let voltage_node = nodes.len() + self.voltage_node as usize;
+
+        let resistance = self.inductance / step.timestep;
+
+        matrix[(voltage_node, node1)] += 1.0;
+        matrix[(voltage_node, node2)] -= 1.0;
+        matrix[(voltage_node, voltage_node)] -= resistance;
+        vector[voltage_node] = -resistance * self.current;
+        matrix[(node1, voltage_node)] += 1.0;
+        matrix[(node2, voltage_node)] -= 1.0;
+    }
+
+    // the operating point stamp is a short, the impedance j omega L goes into the branch equation
+    fn stamp_ac(&self, matrix: &mut SparseMatrix<Complex<f64>>, _vector: &mut DVector<Complex<f64>>, nodes: &HashMap<u32, usize>, frequency: f64) {
+        let voltage_node = nodes.len() + self.voltage_node as usize;
+        matrix[(voltage_node, voltage_node)] -= Complex::new(0.0, TAU * frequency * self.inductance);
+    }
+
+    // the new current follows from the solved voltage, so the extra unknown does not have to be read back
+    fn update_state(&mut self, solution: &Solution, step: &StepInfo) {
+        let voltage = solution.voltage(self.nodes[0]) - solution.voltage(self.nodes[1]);
+        self.current += voltage * step.timestep / self.inductance;
+    }
+
+    // a shorted inductor has no voltage to integrate, its current is the one of the branch
+    fn set_operating_point(&mut self, _solution: &Solution, branch_current: Option<f64>) {
+        self.current = branch_current.unwrap_or(0.0);
+    }
+
+    fn reset_state(&mut self) {
+        self.current = 0.0;
+    }
+}
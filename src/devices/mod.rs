@@ -0,0 +1,27 @@
+pub mod capacitor;
+pub mod dc_voltage_source;
+pub mod resistor;
+pub mod wire;
+pub mod current_source;
+pub mod circuit_switch;
+pub mod ground;
+pub mod inductor;
+pub mod diode;
+pub mod controlled_sources;
+pub mod op_amp;
+pub mod bjt;
+pub mod mosfet;
+
+pub use capacitor::Capacitor;
+pub use dc_voltage_source::DCVoltageSource;
+pub use resistor::Resistor;
+pub use wire::Wire;
+pub use current_source::CurrentSource;
+pub use circuit_switch::Switch;
+pub use ground::Ground;
+pub use inductor::Inductor;
+pub use diode::Diode;
+pub use controlled_sources::{Vcvs, Vccs, Ccvs, Cccs};
+pub use op_amp::{OpAmp, OpAmpModel};
+pub use bjt::{Bjt, BjtPolarity};
+pub use mosfet::{Mosfet, MosfetChannel};
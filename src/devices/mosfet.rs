@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use nalgebra::DVector;
+use serde::{Deserialize, Serialize};
+use crate::{Device, ElementType};
+use crate::devices::diode::MINIMUM_CONDUCTANCE;
+use crate::schematic::Parameters;
+use crate::circuit::{Solution, StepInfo};
+use crate::sparse_matrix::SparseMatrix;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MosfetChannel {
+    N,
+    // every voltage and current of an n-channel transistor turned around
+    P,
+}
+
+// enhancement mosfet in the level 1 (shichman-hodges) model, the nodes are the drain, the gate and the
+// source, the body is tied to the source
+// the transistor is symmetric, drain and source swap roles while the drain is below the source
+#[derive(Clone, Debug)]
+pub struct Mosfet {
+    nodes: Vec<u32>,
+    pub channel: MosfetChannel,
+    // gate-source voltage where the channel starts to conduct, positive for both channels
+    pub threshold_voltage: f64,
+    // kp * w / l, the saturation current is transconductance / 2 * (vgs - vt)^2
+    pub transconductance: f64,
+    // lambda, the relative increase of the drain current per volt of drain-source voltage
+    pub channel_length_modulation: f64,
+    // voltages the model is linearized around, positive for a conducting transistor of both channels
+    gate_source: f64,
+    drain_source: f64,
+}
+
+impl Mosfet {
+    pub fn new(nodes: Vec<u32>, channel: MosfetChannel) -> Self {
+        Mosfet {
+            nodes,
+            channel,
+            threshold_voltage: 1.0,
+            transconductance: 2.0e-3,
+            channel_length_modulation: 0.0,
+            gate_source: 0.0,
+            drain_source: 0.0,
+        }
+    }
+
+    fn sign(&self) -> f64 {
+        match self.channel {
+            MosfetChannel::N => 1.0,
+            MosfetChannel::P => -1.0,
+        }
+    }
+
+    fn voltages(&self, solution: &Solution) -> (f64, f64) {
+        let [drain, gate, source] = [0, 1, 2].map(|index| solution.voltage(self.nodes[index]));
+        (self.sign() * (gate - source), self.sign() * (drain - source))
+    }
+
+    // drain current of an n-channel transistor with a drain above its source, with the derivatives by
+    // the gate-source and the drain-source voltage
+    fn drain_current(&self, gate_source: f64, drain_source: f64) -> (f64, f64, f64) {
+        let overdrive = gate_source - self.threshold_voltage;
+        let k = self.transconductance;
+        let lambda = self.channel_length_modulation;
+        let modulation = 1.0 + lambda * drain_source;
+        let (current, by_gate, by_drain) = if overdrive <= 0.0 {
+            (0.0, 0.0, 0.0)
+        } else if drain_source < overdrive {
+            // triode
+            let channel = overdrive * drain_source - drain_source * drain_source / 2.0;
+            (k * channel * modulation, k * drain_source * modulation, k * (overdrive - drain_source) * modulation + k * channel * lambda)
+        } else {
+            // saturation
+            let channel = overdrive * overdrive / 2.0;
+            (k * channel * modulation, k * overdrive * modulation, k * channel * lambda)
+        };
+        // the channel never quite lets go of the drain, so a cut off transistor does not float it
+        (current + MINIMUM_CONDUCTANCE * drain_source, by_gate, by_drain + MINIMUM_CONDUCTANCE)
+    }
+
+    // the node indices of the terminals that act as drain and source at the operating point, with the
+    // gate-source and drain-source voltage between them
+    fn roles(&self) -> (usize, usize, f64, f64) {
+        if self.drain_source >= 0.0 {
+            (0, 2, self.gate_source, self.drain_source)
+        } else {
+            (2, 0, self.gate_source - self.drain_source, -self.drain_source)
+        }
+    }
+}
+
+impl Device for Mosfet {
+    fn get_type(&self) -> ElementType {
+        ElementType::Mosfet
+    }
+
+    fn parameters(&self) -> Parameters {
+        Parameters::Mosfet {
+            channel: self.channel,
+            threshold_voltage: self.threshold_voltage,
+            transconductance: self.transconductance,
+            channel_length_modulation: self.channel_length_modulation,
+        }
+    }
+
+    fn set_parameters(&mut self, parameters: &Parameters) {
+        if let Parameters::Mosfet { channel, threshold_voltage, transconductance, channel_length_modulation } = parameters {
+            self.channel = *channel;
+            self.threshold_voltage = *threshold_voltage;
+            self.transconductance = *transconductance;
+            self.channel_length_modulation = *channel_length_modulation;
+        }
+    }
+
+    fn is_conductive(&self) -> bool {
+        true
+    }
+
+    // the gate is insulated
+    fn conductive_nodes(&self) -> Vec<u32> {
+        vec![self.nodes[0], self.nodes[2]]
+    }
+
+    fn current_nodes(&self) -> (u32, u32) {
+        (self.nodes[0], self.nodes[2])
+    }
+
+    fn set_nodes(&mut self, nodes: Vec<u32>) {
+        self.nodes = nodes;
+    }
+
+    fn get_nodes(&self) -> Vec<u32> {
+        self.nodes.clone()
+    }
+
+    // companion model of the channel current, it is controlled by the gate and the drain
+    fn stamp_matrix(&self, matrix: &mut SparseMatrix, vector: &mut DVector<f64>, nodes: &HashMap<u32, usize>, _step: &StepInfo) {
+        let (drain, source, gate_source, drain_source) = self.roles();
+        let (drain, gate, source) = (nodes[&self.nodes[drain]], nodes[&self.nodes[1]], nodes[&self.nodes[source]]);
+
+        let (current, by_gate, by_drain) = self.drain_current(gate_source, drain_source);
+        let equivalent_current = self.sign() * (current - by_gate * gate_source - by_drain * drain_source);
+        for (row, direction) in [(drain, 1.0), (source, -1.0)] {
+            matrix[(row, gate)] += direction * by_gate;
+            matrix[(row, drain)] += direction * by_drain;
+            matrix[(row, source)] -= direction * (by_gate + by_drain);
+            vector[row] -= direction * equivalent_current;
+        }
+    }
+
+    // current into the drain node
+    fn current(&self, solution: &Solution, _step: &StepInfo) -> f64 {
+        let (drain, _, gate_source, drain_source) = self.roles();
+        let (current, by_gate, by_drain) = self.drain_current(gate_source, drain_source);
+        let (new_gate_source, new_drain_source) = self.voltages(solution);
+        // the voltages of the solution seen from the terminal that acts as source
+        let (new_gate_source, new_drain_source) = if drain == 0 {
+            (new_gate_source, new_drain_source)
+        } else {
+            (new_gate_source - new_drain_source, -new_drain_source)
+        };
+        let channel = current + by_gate * (new_gate_source - gate_source) + by_drain * (new_drain_source - drain_source);
+        if drain == 0 { self.sign() * channel } else { -self.sign() * channel }
+    }
+
+    fn terminal_currents(&self, solution: &Solution, step: &StepInfo) -> Option<Vec<f64>> {
+        let drain = self.current(solution, step);
+        Some(vec![drain, 0.0, -drain])
+    }
+
+    fn is_nonlinear(&self) -> bool {
+        true
+    }
+
+    // the square law has no exponential to run away with, newton steps are taken as they are
+    fn linearize(&mut self, solution: &Solution) -> bool {
+        (self.gate_source, self.drain_source) = self.voltages(solution);
+        false
+    }
+
+    fn update_state(&mut self, solution: &Solution, _step: &StepInfo) {
+        self.linearize(solution);
+    }
+
+    fn reset_state(&mut self) {
+        self.gate_source = 0.0;
+        self.drain_source = 0.0;
+    }
+}
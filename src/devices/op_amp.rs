@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+use nalgebra::{Complex, DVector};
+use serde::{Deserialize, Serialize};
+use crate::{Device, ElementType};
+use crate::schematic::Parameters;
+use crate::circuit::{Solution, StepInfo};
+use crate::sparse_matrix::SparseMatrix;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum OpAmpModel {
+    // nullor: the output takes whatever voltage makes the inputs equal, the inputs draw no current
+    Ideal,
+    // the gain falls off above gain_bandwidth / gain, with a resistance between the inputs and one in
+    // series with the output
+    SinglePole,
+}
+
+// operational amplifier, the nodes are the non-inverting input, the inverting input, the output and
+// with a supply the positive and the negative supply
+// the output is a voltage source against ground, its branch current flows from ground into the output
+// the output stays between the rails, the supply nodes set them when they are there, they only sense
+// their voltage and draw no current
+#[derive(Clone, Debug)]
+pub struct OpAmp {
+    nodes: Vec<u32>,
+    pub model: OpAmpModel,
+    // open loop gain at dc
+    pub gain: f64,
+    // frequency where the open loop gain falls to 1, in Hz
+    pub gain_bandwidth: f64,
+    pub input_resistance: f64,
+    pub output_resistance: f64,
+    // lowest and highest output voltage without supply nodes, None for an output without limits
+    pub rails: Option<[f64; 2]>,
+    // whether the element has supply pins, they come after the output
+    pub supply: bool,
+    voltage_node: u32,
+    // output of the pole before the output resistance at the end of the last step
+    pole_voltage: f64,
+    // input voltage the single pole model is linearized around
+    input_voltage: f64,
+    // rail the output of the ideal model is held at
+    saturated: Option<f64>,
+    // rails of the last newton iterate
+    limits: Option<[f64; 2]>,
+}
+
+impl OpAmp {
+    pub fn new(nodes: Vec<u32>, model: OpAmpModel) -> Self {
+        OpAmp {
+            supply: nodes.len() >= 5,
+            nodes,
+            model,
+            gain: 2.0e5,
+            gain_bandwidth: 1.0e6,
+            input_resistance: 2.0e6,
+            output_resistance: 75.0,
+            rails: None,
+            voltage_node: 0,
+            pole_voltage: 0.0,
+            input_voltage: 0.0,
+            saturated: None,
+            limits: None,
+        }
+    }
+
+    fn has_supply(&self) -> bool {
+        self.supply && self.nodes.len() >= 5
+    }
+
+    fn input(&self, solution: &Solution) -> f64 {
+        solution.voltage(self.nodes[0]) - solution.voltage(self.nodes[1])
+    }
+
+    fn rails_in(&self, solution: &Solution) -> Option<[f64; 2]> {
+        if self.has_supply() {
+            Some([solution.voltage(self.nodes[4]), solution.voltage(self.nodes[3])])
+        } else {
+            self.rails
+        }
+    }
+
+    // time constant of the pole
+    fn time_constant(&self) -> f64 {
+        self.gain / (TAU * self.gain_bandwidth)
+    }
+
+    // output of the pole is gain * input + offset, backward euler over the step
+    fn pole(&self, step: &StepInfo) -> (f64, f64) {
+        let ratio = self.time_constant() / step.timestep;
+        (self.gain / (1.0 + ratio), self.pole_voltage * ratio / (1.0 + ratio))
+    }
+
+    // the pole output limited to the rails and linearized around the input voltage: (gain, offset)
+    fn limited_pole(&self, step: &StepInfo) -> (f64, f64) {
+        let (gain, offset) = self.pole(step);
+        match self.limits {
+            Some([low, _]) if gain * self.input_voltage + offset <= low => (0.0, low),
+            Some([_, high]) if gain * self.input_voltage + offset >= high => (0.0, high),
+            _ => (gain, offset),
+        }
+    }
+}
+
+impl Device for OpAmp {
+    fn get_type(&self) -> ElementType {
+        ElementType::OpAmp
+    }
+
+    fn parameters(&self) -> Parameters {
+        Parameters::OpAmp {
+            model: self.model,
+            gain: self.gain,
+            gain_bandwidth: self.gain_bandwidth,
+            input_resistance: self.input_resistance,
+            output_resistance: self.output_resistance,
+            rails: self.rails,
+            supply: self.supply,
+        }
+    }
+
+    fn set_parameters(&mut self, parameters: &Parameters) {
+        if let Parameters::OpAmp { model, gain, gain_bandwidth, input_resistance, output_resistance, rails, supply } = parameters {
+            self.model = *model;
+            self.gain = *gain;
+            self.gain_bandwidth = *gain_bandwidth;
+            self.input_resistance = *input_resistance;
+            self.output_resistance = *output_resistance;
+            self.rails = *rails;
+            self.supply = *supply;
+        }
+    }
+
+    // the output is driven against ground
+    fn is_conductive(&self) -> bool {
+        true
+    }
+
+    fn conductive_nodes(&self) -> Vec<u32> {
+        vec![0, self.nodes[2]]
+    }
+
+    fn current_nodes(&self) -> (u32, u32) {
+        (0, self.nodes[2])
+    }
+
+    fn set_nodes(&mut self, nodes: Vec<u32>) {
+        self.nodes = nodes;
+    }
+
+    fn get_nodes(&self) -> Vec<u32> {
+        self.nodes.clone()
+    }
+
+    fn get_voltage_source_count(&self) -> u32 {
+        1
+    }
+
+    fn set_voltage_node(&mut self, node: u32) {
+        self.voltage_node = node;
+    }
+
+    fn stamp_matrix(&self, matrix: &mut SparseMatrix, vector: &mut DVector<f64>, nodes: &HashMap<u32, usize>, step: &StepInfo) {
+        let positive = nodes[&self.nodes[0]];
+        let negative = nodes[&self.nodes[1]];
+        let output = nodes[&self.nodes[2]];
+        let ground = nodes[&0];
+        let voltage_node = nodes.len() + self.voltage_node as usize;
+
+        matrix[(ground, voltage_node)] += 1.0;
+        matrix[(output, voltage_node)] -= 1.0;
+
+        match (self.model, self.saturated) {
+            // v+ - v- = 0
+            (OpAmpModel::Ideal, None) => {
+                matrix[(voltage_node, positive)] += 1.0;
+                matrix[(voltage_node, negative)] -= 1.0;
+            }
+            (OpAmpModel::Ideal, Some(rail)) => {
+                matrix[(voltage_node, output)] += 1.0;
+                matrix[(voltage_node, ground)] -= 1.0;
+                vector[voltage_node] = rail;
+            }
+            // vout + rout * i = gain * (v+ - v-) + offset
+            (OpAmpModel::SinglePole, _) => {
+                let conductance = 1.0 / self.input_resistance;
+                matrix[(positive, positive)] += conductance;
+                matrix[(negative, negative)] += conductance;
+                matrix[(positive, negative)] -= conductance;
+                matrix[(negative, positive)] -= conductance;
+
+                let (gain, offset) = self.limited_pole(step);
+                matrix[(voltage_node, output)] += 1.0;
+                matrix[(voltage_node, ground)] -= 1.0;
+                matrix[(voltage_node, positive)] -= gain;
+                matrix[(voltage_node, negative)] += gain;
+                matrix[(voltage_node, voltage_node)] += self.output_resistance;
+                vector[voltage_node] = offset;
+            }
+        }
+    }
+
+    // the gain of the operating point is replaced by the gain of the pole at the frequency
+    fn stamp_ac(&self, matrix: &mut SparseMatrix<Complex<f64>>, _vector: &mut DVector<Complex<f64>>, nodes: &HashMap<u32, usize>, frequency: f64) {
+        if self.model != OpAmpModel::SinglePole {
+            return;
+        }
+        let (operating_gain, _) = self.limited_pole(&StepInfo::OPERATING_POINT);
+        if operating_gain == 0.0 {
+            return;
+        }
+        let gain = Complex::new(self.gain, 0.0) / Complex::new(1.0, TAU * frequency * self.time_constant());
+        let voltage_node = nodes.len() + self.voltage_node as usize;
+        let difference = gain - operating_gain;
+        matrix[(voltage_node, nodes[&self.nodes[0]])] -= difference;
+        matrix[(voltage_node, nodes[&self.nodes[1]])] += difference;
+    }
+
+    // the input resistance carries current between the inputs, the output current is the branch
+    // current and the supply pins draw none
+    fn terminal_currents(&self, solution: &Solution, _step: &StepInfo) -> Option<Vec<f64>> {
+        let input = match self.model {
+            OpAmpModel::Ideal => 0.0,
+            OpAmpModel::SinglePole => self.input(solution) / self.input_resistance,
+        };
+        let mut currents = vec![input, -input];
+        currents.resize(self.nodes.len(), 0.0);
+        Some(currents)
+    }
+
+    fn is_nonlinear(&self) -> bool {
+        self.rails.is_some() || self.has_supply()
+    }
+
+    fn linearize(&mut self, solution: &Solution) -> bool {
+        self.limits = self.rails_in(solution).map(|[low, high]| [low.min(high), low.max(high)]);
+        self.input_voltage = self.input(solution);
+        if self.model == OpAmpModel::SinglePole {
+            return false;
+        }
+
+        // the output is held at a rail while the inputs push it past it
+        let output = solution.voltage(self.nodes[2]);
+        let saturated = match (self.limits, self.saturated) {
+            (None, _) => None,
+            (Some([_, high]), None) if output > high => Some(high),
+            (Some([low, _]), None) if output < low => Some(low),
+            (Some(_), None) => None,
+            (Some([low, high]), Some(rail)) => {
+                let at_high = rail > (low + high) / 2.0;
+                match (at_high, self.input_voltage) {
+                    (true, input) if input < 0.0 => None,
+                    (false, input) if input > 0.0 => None,
+                    (true, _) => Some(high),
+                    (false, _) => Some(low),
+                }
+            }
+        };
+        let changed = saturated != self.saturated;
+        self.saturated = saturated;
+        changed
+    }
+
+    fn update_state(&mut self, solution: &Solution, step: &StepInfo) {
+        self.linearize(solution);
+        let (gain, offset) = self.limited_pole(step);
+        self.pole_voltage = gain * self.input_voltage + offset;
+    }
+
+    fn reset_state(&mut self) {
+        self.pole_voltage = 0.0;
+        self.input_voltage = 0.0;
+        self.saturated = None;
+        self.limits = None;
+    }
+}
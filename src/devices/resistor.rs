@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+use nalgebra::DVector;
+use crate::{Device, ElementType};
+use crate::schematic::Parameters;
+use crate::circuit::{Solution, StepInfo};
+use crate::sparse_matrix::SparseMatrix;
+
+#[derive(Clone, Debug)]
+pub struct Resistor {
+    nodes: Vec<u32>,
+    pub resistance: f64,
+}
+
+impl Resistor {
+    pub fn new(nodes: Vec<u32>, resistance: f64) -> Self {
+        Resistor { nodes, resistance }
+    }
+}
+
+impl Device for Resistor {
+    fn get_type(&self) -> ElementType {
+        ElementType::Resistor
+    }
+
+    fn parameters(&self) -> Parameters {
+        Parameters::Resistor { resistance: self.resistance }
+    }
+
+    fn set_parameters(&mut self, parameters: &Parameters) {
+        if let Parameters::Resistor { resistance } = parameters {
+            self.resistance = *resistance;
+        }
+    }
+
+    fn get_admittance(&self) -> f64 {
+        1.0 / self.resistance
+    }
+
+    fn is_conductive(&self) -> bool {
+        true
+    }
+
+    fn set_nodes(&mut self, nodes: Vec<u32>) {
+        self.nodes = nodes
+    }
+
+    fn get_nodes(&self) -> Vec<u32> {
+        self.nodes.clone()
+    }
+
+    fn current(&self, solution: &Solution, _step: &StepInfo) -> f64 {
+        (solution.voltage(self.nodes[0]) - solution.voltage(self.nodes[1])) / self.resistance
+    }
+
+    fn stamp_matrix(&self, matrix: &mut SparseMatrix, _vector: &mut DVector<f64>, nodes: &HashMap<u32, usize>, _step: &StepInfo) {
+        let n1 = nodes[&self.nodes[0]];
+        let n2 = nodes[&self.nodes[1]];
+
+        matrix[(n1, n1)] += 1.0 / self.resistance;
+        matrix[(n2, n2)] += 1.0 / self.resistance;
+        matrix[(n1, n2)] -= 1.0 / self.resistance;
+        matrix[(n2, n1)] -= 1.0 / self.resistance;
+    }
+}
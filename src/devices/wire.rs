@@ -0,0 +1,39 @@
+use crate::{Device, ElementType};
+use crate::schematic::Parameters;
+
+#[derive(Clone, Debug)]
+pub struct Wire {
+    nodes: Vec<u32>,
+}
+
+impl Wire {
+    pub fn new(nodes: Vec<u32>) -> Self {
+        Wire { nodes }
+    }
+}
+
+impl Device for Wire {
+    fn get_type(&self) -> ElementType {
+        ElementType::Wire
+    }
+
+    fn parameters(&self) -> Parameters {
+        Parameters::Wire
+    }
+
+    fn get_admittance(&self) -> f64 {
+        f64::MAX
+    }
+
+    fn shorted(&self) -> bool {
+        true
+    }
+
+    fn set_nodes(&mut self, nodes: Vec<u32>) {
+        self.nodes = nodes;
+    }
+
+    fn get_nodes(&self) -> Vec<u32> {
+        self.nodes.clone()
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use rusty_circuit::schematic::ElementRecord;
+
+// older commands are dropped
+const MAX_COMMANDS: usize = 200;
+
+// an element before and after an edit, None where it did not exist
+#[derive(Debug, Clone)]
+struct Change {
+    id: u32,
+    before: Option<ElementRecord>,
+    after: Option<ElementRecord>,
+}
+
+#[derive(Debug, Clone)]
+struct Command {
+    changes: Vec<Change>,
+}
+
+// undo and redo of every edit of the schematic
+// the elements are compared with the last time they were tracked, whatever changed becomes a command, so placing,
+// deleting, moving, parameter edits and switch toggles are all covered the same way
+pub struct History {
+    undo: VecDeque<Command>,
+    redo: Vec<Command>,
+    snapshot: BTreeMap<u32, ElementRecord>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self { undo: VecDeque::new(), redo: Vec::new(), snapshot: BTreeMap::new() }
+    }
+
+    // starts over from the given elements, used after a file is opened
+    pub fn reset(&mut self, records: BTreeMap<u32, ElementRecord>) {
+        self.undo.clear();
+        self.redo.clear();
+        self.snapshot = records;
+    }
+
+    // called once an edit is done, whatever changed since the last call becomes one command
+    pub fn track(&mut self, records: BTreeMap<u32, ElementRecord>) {
+        let ids: BTreeSet<u32> = self.snapshot.keys().chain(records.keys()).copied().collect();
+        let changes: Vec<Change> = ids.into_iter()
+            .filter(|id| self.snapshot.get(id) != records.get(id))
+            .map(|id| Change { id, before: self.snapshot.get(&id).cloned(), after: records.get(&id).cloned() })
+            .collect();
+        self.snapshot = records;
+        if changes.is_empty() {
+            return;
+        }
+
+        self.redo.clear();
+        self.undo.push_back(Command { changes });
+        if self.undo.len() > MAX_COMMANDS {
+            self.undo.pop_front();
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    // the elements to restore, None means the element has to be removed
+    pub fn undo(&mut self) -> Vec<(u32, Option<ElementRecord>)> {
+        let Some(command) = self.undo.pop_back() else {
+            return Vec::new();
+        };
+        let restore = self.restore(command.changes.iter().map(|change| (change.id, change.before.clone())));
+        self.redo.push(command);
+        restore
+    }
+
+    pub fn redo(&mut self) -> Vec<(u32, Option<ElementRecord>)> {
+        let Some(command) = self.redo.pop() else {
+            return Vec::new();
+        };
+        let restore = self.restore(command.changes.iter().map(|change| (change.id, change.after.clone())));
+        self.undo.push_back(command);
+        restore
+    }
+
+    // the snapshot already holds the restored state, so restoring is not recorded as a new edit
+    fn restore(&mut self, records: impl Iterator<Item = (u32, Option<ElementRecord>)>) -> Vec<(u32, Option<ElementRecord>)> {
+        let records: Vec<(u32, Option<ElementRecord>)> = records.collect();
+        for (id, record) in records.iter() {
+            match record {
+                Some(record) => self.snapshot.insert(*id, record.clone()),
+                None => self.snapshot.remove(id),
+            };
+        }
+        records
+    }
+}
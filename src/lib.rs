@@ -0,0 +1,145 @@
+// circuit model and solver, without any user interface
+// the editor in main.rs is one front-end for it
+
+pub mod ac;
+pub mod circuit;
+pub mod circuit_solver;
+pub mod dc_sweep;
+pub mod devices;
+pub mod pins;
+pub mod schematic;
+pub mod sparse_matrix;
+pub mod spice;
+pub mod waveform;
+
+use std::collections::HashMap;
+use nalgebra::{Complex, DVector};
+use crate::circuit::{Solution, StepInfo};
+use crate::schematic::Parameters;
+use crate::sparse_matrix::SparseMatrix;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ElementType {
+    Wire,
+    Resistor,
+    Capacitor,
+    Inductor,
+    DCVoltageSource,
+    CurrentSource,
+    Switch,
+    Ground,
+    Diode,
+    Vcvs,
+    Vccs,
+    Ccvs,
+    Cccs,
+    OpAmp,
+    Bjt,
+    Mosfet,
+}
+
+// the part of a circuit element the solver needs
+pub trait Device: DeviceClone + std::fmt::Debug {
+    fn get_type(&self) -> ElementType;
+    // the values the user can edit, this is what gets saved
+    fn parameters(&self) -> Parameters;
+    // parameters of another element type are ignored
+    fn set_parameters(&mut self, _parameters: &Parameters) {}
+    fn get_admittance(&self) -> f64 {
+        0.0
+    }
+    fn shorted(&self) -> bool {
+        false
+    }
+    // whether the element gives its nodes a path for direct current, used to find floating parts of the circuit
+    fn is_conductive(&self) -> bool {
+        self.shorted()
+    }
+    // the same in a time step of the given size, capacitors block direct current but conduct through
+    // their companion model
+    fn is_conductive_at(&self, _step: &StepInfo) -> bool {
+        self.is_conductive()
+    }
+    // the nodes the conductive path runs between, sense nodes of controlled sources are left out
+    fn conductive_nodes(&self) -> Vec<u32> {
+        self.get_nodes()
+    }
+
+    // the nodes the current of the element flows between, from the first to the second, ground elements
+    // connect their node to the ground node
+    fn current_nodes(&self) -> (u32, u32) {
+        let nodes = self.get_nodes();
+        (nodes[0], nodes.get(1).copied().unwrap_or(0))
+    }
+
+    fn set_nodes(&mut self, nodes: Vec<u32>);
+    fn get_nodes(&self) -> Vec<u32>;
+    // nodes maps the node ids to their row in the matrix
+    fn stamp_matrix(&self, _matrix: &mut SparseMatrix, _vector: &mut DVector<f64>, _nodes: &HashMap<u32, usize>, _step: &StepInfo) {}
+    // ac analysis starts from the matrix of the operating point, elements add their reactance on top of
+    // it and sources replace their value with their ac value, the frequency is in Hz
+    fn stamp_ac(&self, _matrix: &mut SparseMatrix<Complex<f64>>, _vector: &mut DVector<Complex<f64>>, _nodes: &HashMap<u32, usize>, _frequency: f64) {}
+    // extra unknowns are currents flowing through the element from its first to its second node
+    fn get_voltage_source_count(&self) -> u32 { 0 }
+    fn set_voltage_node(&mut self, _node: u32) {}
+    // element whose branch current controls this one, the solver hands back the index of that current
+    // among the extra unknowns
+    fn control_element(&self) -> Option<u32> {
+        None
+    }
+    fn set_control_branch(&mut self, _branch: u32) {}
+    // called after a time step was solved, elements with memory store what they need for the next step
+    fn update_state(&mut self, _solution: &Solution, _step: &StepInfo) {}
+    // takes the dc operating point as the state the first time step starts from, the branch current of
+    // an element that has one is passed along, the element can not look it up in the solution
+    fn set_operating_point(&mut self, solution: &Solution, _branch_current: Option<f64>) {
+        self.update_state(solution, &StepInfo::OPERATING_POINT);
+    }
+    // first time after the given one where the element changes abruptly, steps should not go past it
+    fn next_breakpoint(&self, _time: f64) -> Option<f64> {
+        None
+    }
+    // current from the first to the second node in a solved step, called before update_state
+    // elements with a branch current unknown report that instead, and the current of shorted elements
+    // follows from the currents around them
+    fn current(&self, _solution: &Solution, _step: &StepInfo) -> f64 {
+        0.0
+    }
+    // currents flowing into each node of an element with more than two terminals, in the order of its
+    // nodes, a branch current of the element flows between its current nodes on top of them
+    // None if its current only flows between its current nodes
+    fn terminal_currents(&self, _solution: &Solution, _step: &StepInfo) -> Option<Vec<f64>> {
+        None
+    }
+
+    // nonlinear elements stamp a model that is linearized around their operating point, the solver
+    // repeats the solve and moves the operating point with linearize until the solution settles
+    fn is_nonlinear(&self) -> bool {
+        false
+    }
+    // takes the result of a newton iteration as the next operating point, returns true if the step had
+    // to be limited, the solution can not be converged then
+    fn linearize(&mut self, _solution: &Solution) -> bool {
+        false
+    }
+    fn reset_state(&mut self) {}
+}
+
+pub trait DeviceClone {
+    fn clone_box(&self) -> Box<dyn Device>;
+}
+
+impl<T> DeviceClone for T
+where
+    T: 'static + Device + Clone,
+{
+    fn clone_box(&self) -> Box<dyn Device> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn Device> {
+    fn clone(&self) -> Box<dyn Device> {
+        self.clone_box()
+    }
+}
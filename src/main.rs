@@ -4,62 +4,89 @@
 )] // hide console window on Windows in release
 #![allow(rustdoc::missing_crate_level_docs)] // it's an example
 
-mod circuit_solver;
+mod bode;
 mod components;
+mod history;
 mod node;
+mod oscilloscope;
+mod simulation;
+mod sweep_plot;
 
 use std::collections::{BTreeMap, BTreeSet, HashMap};
-use std::collections::hash_map::Values;
-use std::num::FpCategory::Zero;
-use std::ops::Add;
-use eframe::{egui, WindowBuilder};
-use eframe::egui::{FontFamily, Key, Rgba, RichText, WidgetText};
+use std::path::Path;
+use eframe::egui;
+use eframe::egui::{Align2, FontFamily, FontId, Key, Rgba, RichText};
 use eframe::emath::Vec2;
-use eframe::epaint::{Color32, Pos2, Shape, Stroke};
+use eframe::epaint::{Color32, Pos2, Stroke};
 use egui::{Rect, Sense};
-use nalgebra::{DMatrix, DVector};
-use crate::circuit_solver::{simplify_graph};
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum ElementType {
-    Wire,
-    Resistor,
-    Capacitor,
-    DCVoltageSource,
-    CurrentSource,
-    Switch,
-    Ground,
-}
+use rusty_circuit::{devices, Device, ElementType};
+use rusty_circuit::circuit::{Circuit, Solution, Solver, SolverInfo, StepInfo, Warning};
+use rusty_circuit::circuit_solver::SolverError;
+use rusty_circuit::pins::{pin_positions, placement, Orientation};
+use rusty_circuit::schematic::{ElementRecord, Schematic, View};
+use rusty_circuit::spice;
+use rusty_circuit::spice::Analysis;
+use crate::bode::BodePlot;
+use crate::components::controlled_source::ControlledSource;
+use crate::history::History;
+use crate::oscilloscope::Oscilloscope;
+use crate::simulation::Simulation;
+use crate::sweep_plot::SweepPlot;
 
+// elements and nodes the circuit checker complains about
+const WARNING_COLOR: Color32 = Color32::from_rgb(255, 150, 0);
 
 trait CircuitElement: ElementClone + std::fmt::Debug {
     fn new_boxed(pos: Pos2, size: Vec2, id: u32, nodes: Vec<u32>) -> Box<dyn CircuitElement>
     where
         Self: Sized;
-    fn draw(&mut self, ui: &mut egui::Ui, stroke: Stroke, grid_step: f32, screen_pos: Pos2, screen_size: Vec2, values: &HashMap<(i32, i32), Node>);
+    // returns whether a click on the element changed it
+    fn draw(&mut self, ui: &mut egui::Ui, stroke: Stroke, grid_step: f32, screen_pos: Pos2, screen_size: Vec2, values: &HashMap<(i32, i32), Node>) -> bool;
     fn pos(&self) -> Pos2;
     fn size(&self) -> Vec2;
-    fn get_admittance(&self) -> f64 {
-        0.0
-    }
+    // pos and size are in grid cells, the nodes of the device are not touched
+    fn set_placement(&mut self, pos: Pos2, size: Vec2);
+    #[allow(dead_code)]
     fn get_id(&self) -> u32;
-    fn get_type(&self) -> ElementType;
-    fn shorted(&self) -> bool {
-        false
+    // the model of the element that is handed to the solver
+    fn device(&self) -> &dyn Device;
+    fn device_mut(&mut self) -> &mut dyn Device;
+    // elements with a pin layout keep how it is turned, the others point wherever their size does
+    fn orientation(&self) -> Orientation {
+        Orientation::default()
     }
-
-    fn set_nodes(&mut self, nodes: Vec<u32>);
-    fn get_nodes(&self) -> Vec<u32>;
-    fn stamp_matrix(&self, matrix: &mut DMatrix<f64>, vector: &mut DVector<f64>, nodes: &Vec<Node>) {}
-    fn get_voltage_source_count(&self) -> u32 { 0 }
-    fn set_voltage_node(&mut self, node: u32) {}
+    fn set_orientation(&mut self, _orientation: Orientation) {}
+    // grid points of the terminals, in the order of the device nodes
+    fn pins(&self) -> Vec<(i32, i32)> {
+        let anchor = (self.pos().x as i32, self.pos().y as i32);
+        let size = (self.size().x as i32, self.size().y as i32);
+        pin_positions(self.device().get_type(), anchor, size, self.orientation())
+    }
+    // the sense nodes come after the pins of the element
     fn get_node_positions(&self) -> Vec<(i32, i32)> {
-        let node1 = (self.pos().x as i32, self.pos().y as i32);
-        let node2 = (self.pos().x as i32 + self.size().x as i32, self.pos().y as i32 + self.size().y as i32);
-        vec![node1, node2]
+        let mut positions = self.pins();
+        positions.extend(self.sense().into_iter().flatten());
+        positions
+    }
+    // grid points whose voltage controls the element, positive first
+    fn sense(&self) -> Option<[(i32, i32); 2]> {
+        None
     }
+    fn set_sense(&mut self, _sense: Option<[(i32, i32); 2]>) {}
 
-    fn draw_window(&mut self, ctx: &egui::Context) -> Option<Pos2> { None }
+    // nodes and elements are what the window can offer to pick from
+    fn draw_window(&mut self, _ctx: &egui::Context, _solution: &Solution, _nodes: &HashMap<(i32, i32), Node>, _elements: &[(u32, ElementType)]) -> WindowResponse {
+        WindowResponse::default()
+    }
+}
+
+// what the window of an element did in a frame
+#[derive(Debug, Default)]
+struct WindowResponse {
+    // center of the window while it is hovered
+    hovered: Option<Pos2>,
+    // whether a widget of the window changed the element
+    changed: bool,
 }
 
 trait ElementClone {
@@ -90,7 +117,7 @@ fn main() -> eframe::Result {
     eframe::run_native(
         "Rusty Circuits",
         options,
-        Box::new(|cc| {
+        Box::new(|_cc| {
             Ok(Box::<RustyCircuits>::default())
         }),
     )
@@ -103,9 +130,26 @@ struct Node {
     connections: BTreeSet<u32>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Tool {
+    // dragging places an element of the selected type
+    Place,
+    // clicking and dragging selects and moves elements
+    Select,
+}
+
+enum SelectionDrag {
+    // the selection follows the pointer, last grid point it was moved to
+    Move(Pos2),
+    // rubber band from a screen position
+    Band(Pos2),
+}
+
+#[derive(Clone, PartialEq)]
 struct DebugOptions {
     show_node_numbers: bool,
     show_node_voltages: bool,
+    show_element_currents: bool,
 
     info_simplfication: bool,
     info_admittance_matrix: bool,
@@ -114,11 +158,21 @@ struct DebugOptions {
     info_node_map: bool,
 }
 
+// what the last solve was done for, the circuit is only solved again when some of it changed
+#[derive(PartialEq)]
+struct SolveKey {
+    revision: u64,
+    timestep: f64,
+    // the debug output is written while solving
+    debug_options: DebugOptions,
+}
+
 impl DebugOptions {
     fn new() -> Self {
         Self {
             show_node_numbers: false,
             show_node_voltages: false,
+            show_element_currents: false,
 
             info_simplfication: false,
             info_admittance_matrix: false,
@@ -132,11 +186,42 @@ impl DebugOptions {
 struct RustyCircuits {
     offset: Vec2,
     grid_step: f32,
+    tool: Tool,
     selected_element_type: ElementType,
+    selection: BTreeSet<u32>,
+    selection_drag: Option<SelectionDrag>,
     current_element: Option<Box<dyn CircuitElement>>,
     elements: BTreeMap<u32, Box<dyn CircuitElement>>,
     nodes: HashMap<(i32, i32), Node>,
+    // ids only ever grow, an id that was freed is not handed out again
+    next_element_id: u32,
+    next_node_id: u32,
     debug_options: DebugOptions,
+    simulation: Simulation,
+    oscilloscope: Oscilloscope,
+    bode: BodePlot,
+    sweep_plot: SweepPlot,
+    history: History,
+    // counts the edits of the schematic, whatever is derived from the elements is only rebuilt when it changes
+    revision: u64,
+    // revision the history last compared the elements at
+    tracked: u64,
+    // the elements as a circuit for the bode plot, the dc sweep and the checker, with the revision it
+    // was built at
+    circuit: Circuit,
+    circuit_revision: Option<u64>,
+    solver: Solver,
+    // None after a reset, the state of the elements changed without an edit
+    solved: Option<SolveKey>,
+    // result of the last solved step, for the current and power readouts
+    solution: Solution,
+    solver_error: Option<SolverError>,
+    // problems the circuit checker found after the last edit, shown on the canvas
+    warnings: Vec<Warning>,
+    debug_info: String,
+    file_path: String,
+    // errors of saving, opening, importing and exporting
+    file_error: Option<Box<dyn std::error::Error>>,
 }
 
 impl Default for RustyCircuits {
@@ -144,49 +229,121 @@ impl Default for RustyCircuits {
         Self {
             offset: Vec2::ZERO,
             grid_step: 35.0,
+            tool: Tool::Place,
             selected_element_type: ElementType::Wire,
+            selection: BTreeSet::new(),
+            selection_drag: None,
             current_element: None,
             elements: BTreeMap::new(),
             nodes: HashMap::new(),
+            next_element_id: 1,
+            next_node_id: 1,
             debug_options: DebugOptions::new(),
+            simulation: Simulation::new(),
+            oscilloscope: Oscilloscope::new(),
+            bode: BodePlot::new(),
+            sweep_plot: SweepPlot::new(),
+            history: History::new(),
+            revision: 0,
+            tracked: 0,
+            circuit: Circuit::new(),
+            circuit_revision: None,
+            solver: Solver::new(),
+            solved: None,
+            solution: Solution::default(),
+            solver_error: None,
+            warnings: Vec::new(),
+            debug_info: String::new(),
+            file_path: String::from("circuit.json"),
+            file_error: None,
         }
     }
 }
 
 impl eframe::App for RustyCircuits {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // for continuous updates
-        ctx.request_repaint();
+        // panels have to be added before the central panel
+        let element_types: Vec<(u32, ElementType)> = self.elements.iter().map(|(id, element)| (*id, element.device().get_type())).collect();
+        self.oscilloscope.draw(ctx, &self.nodes, &element_types);
+        if self.bode.open || self.sweep_plot.open {
+            self.update_circuit();
+        }
+        if self.bode.open {
+            self.bode.draw(ctx, &self.nodes, &self.circuit);
+        }
+        if self.sweep_plot.open {
+            self.sweep_plot.draw(ctx, &self.nodes, &element_types, &self.circuit, &self.file_path);
+        }
 
         egui::CentralPanel::default().show(ctx, |ui| {
             let input = ctx.input(|i| i.clone());
-            let mut debug_info = String::new();
-            ui.input(|input_state| {});
+            let mut debug_info = std::mem::take(&mut self.debug_info);
 
             for line in (0..(ui.available_width() + self.grid_step) as i32).step_by(self.grid_step as usize) {
                 let x = line as f32 + self.offset.x % self.grid_step;
-                ui.painter().line_segment([Pos2::new(x as f32, 0.0), Pos2::new(x as f32, ui.available_height() + self.grid_step)], Stroke::new(0.5, Color32::from_gray(64)));
+                ui.painter().line_segment([Pos2::new(x, 0.0), Pos2::new(x, ui.available_height() + self.grid_step)], Stroke::new(0.5, Color32::from_gray(64)));
             }
 
             for column in (0..(ui.available_height() + self.grid_step) as i32).step_by(self.grid_step as usize) {
                 let y = column as f32 + self.offset.y % self.grid_step;
-                ui.painter().line_segment([Pos2::new(0.0, y as f32), Pos2::new(ui.available_width() + self.grid_step, y as f32)], Stroke::new(0.5, Color32::from_gray(64)));
+                ui.painter().line_segment([Pos2::new(0.0, y), Pos2::new(ui.available_width() + self.grid_step, y)], Stroke::new(0.5, Color32::from_gray(64)));
             }
 
             ui.horizontal(|ui| {
-                ui.selectable_value(&mut self.selected_element_type, ElementType::Wire, "Wire");
-                ui.selectable_value(&mut self.selected_element_type, ElementType::Resistor, "Resistor");
-                ui.selectable_value(&mut self.selected_element_type, ElementType::Capacitor, "Capacitor");
-                ui.selectable_value(&mut self.selected_element_type, ElementType::DCVoltageSource, "DC Voltage Source");
-                ui.selectable_value(&mut self.selected_element_type, ElementType::CurrentSource, "Current Source");
-                ui.selectable_value(&mut self.selected_element_type, ElementType::Switch, "Switch");
-                ui.selectable_value(&mut self.selected_element_type, ElementType::Ground, "Ground");
+                self.draw_file_menu(ui);
+                ui.toggle_value(&mut self.oscilloscope.open, "Oscilloscope");
+                ui.toggle_value(&mut self.bode.open, "Bode plot");
+                ui.toggle_value(&mut self.sweep_plot.open, "DC sweep");
+                if ui.add_enabled(self.history.can_undo(), egui::Button::new("Undo")).clicked() {
+                    self.undo();
+                }
+                if ui.add_enabled(self.history.can_redo(), egui::Button::new("Redo")).clicked() {
+                    self.redo();
+                }
+                ui.selectable_value(&mut self.tool, Tool::Select, "Select");
+                for (element_type, name) in [
+                    (ElementType::Wire, "Wire"),
+                    (ElementType::Resistor, "Resistor"),
+                    (ElementType::Capacitor, "Capacitor"),
+                    (ElementType::Inductor, "Inductor"),
+                    (ElementType::DCVoltageSource, "DC Voltage Source"),
+                    (ElementType::CurrentSource, "Current Source"),
+                    (ElementType::Switch, "Switch"),
+                    (ElementType::Ground, "Ground"),
+                    (ElementType::Diode, "Diode"),
+                    (ElementType::Vcvs, "VCVS"),
+                    (ElementType::Vccs, "VCCS"),
+                    (ElementType::Ccvs, "CCVS"),
+                    (ElementType::Cccs, "CCCS"),
+                    (ElementType::OpAmp, "Op-amp"),
+                    (ElementType::Bjt, "BJT"),
+                    (ElementType::Mosfet, "MOSFET"),
+                ] {
+                    if ui.selectable_label(self.tool == Tool::Place && self.selected_element_type == element_type, name).clicked() {
+                        self.tool = Tool::Place;
+                        self.selected_element_type = element_type;
+                        self.selection.clear();
+                    }
+                }
+                if self.tool == Tool::Select {
+                    ui.separator();
+                    if ui.button("Rotate (R)").clicked() {
+                        self.transform_selection(|(x, y)| (-y, x));
+                    }
+                    if ui.button("Flip (F)").clicked() {
+                        self.transform_selection(|(x, y)| (-x, y));
+                    }
+                    if ui.button("Delete (Del)").clicked() {
+                        self.delete_selection();
+                    }
+                }
             });
 
             ui.allocate_ui_at_rect(Rect::from_min_size(Pos2::new(ui.available_width() - 180.0, 0.0), Vec2::new(180.0, 150.0)), |ui| {
                 ui.label("Debug options");
                 ui.checkbox(&mut self.debug_options.show_node_numbers, "Show node numbers");
                 ui.checkbox(&mut self.debug_options.show_node_voltages, "Show node voltages");
+                ui.checkbox(&mut self.debug_options.show_element_currents, "Show element currents");
 
                 ui.checkbox(&mut self.debug_options.info_simplfication, "Show simplification info");
                 ui.checkbox(&mut self.debug_options.info_admittance_matrix, "Show admittance matrix");
@@ -196,55 +353,54 @@ impl eframe::App for RustyCircuits {
             });
 
 
-            let (rect, response) =
+            let (_rect, response) =
                 ui.allocate_exact_size(ui.available_size(), Sense::click_and_drag());
 
 
-            if response.dragged() {
-                if input.key_down(Key::C) {
-                    self.offset += response.drag_delta();
-                } else if self.current_element.is_some() {
+            if response.dragged() && input.key_down(Key::C) {
+                self.offset += response.drag_delta();
+            } else if self.tool == Tool::Select {
+                self.handle_selection(ui, &response, &input);
+            } else if response.dragged() {
+                if self.current_element.is_some() {
                     let element = self.current_element.as_ref().unwrap();
-                    let new_size = response.interact_pointer_pos().unwrap().to_vec2() - self.grid_to_screen(element.pos()).to_vec2();
-                    self.current_element = Some(self.create_element(element.pos(), self.screen_to_grid_vec(new_size), 0, Vec::new()));
+                    let drag = response.interact_pointer_pos().unwrap().to_vec2() - self.grid_to_screen(element.pos()).to_vec2();
+                    self.current_element = Some(self.create_element(element.pos(), self.screen_to_grid_vec(drag)));
                 } else {
                     self.current_element = Some(self.create_element(
                         self.screen_to_grid(response.interact_pointer_pos().unwrap() - response.drag_delta()),
-                        self.screen_to_grid_vec(response.drag_delta()), 0, Vec::new(),
+                        self.screen_to_grid_vec(response.drag_delta()),
                     ));
                 }
             } else if self.current_element.is_some() {
                 if self.current_element.as_ref().unwrap().size() != Vec2::ZERO {
                     let element = self.current_element.as_ref().unwrap();
+                    let (pos, size, orientation) = (element.pos(), element.size(), element.orientation());
                     let element_id = self.get_next_element_id();
-
-                    let node_positions = element.get_node_positions();
-                    let mut node_ids = Vec::new();
-                    for position in node_positions {
-                        if self.nodes.contains_key(&position) {
-                            let node = self.nodes.get_mut(&position).unwrap();
-                            node_ids.push(node.id);
-                            node.connections.insert(element_id);
-                        } else {
-                            let id = self.get_next_node_id();
-                            node_ids.push(id);
-                            self.nodes.insert(position, Node { id: id, voltage: 0.0, connections: BTreeSet::from([element_id]) });
-                        }
-                    }
-                    println!("{:?}", node_ids);
-                    self.elements.insert(element_id, self.create_element(element.pos(), element.size(), element_id, node_ids));
+                    self.insert_element(self.selected_element_type, pos, size, orientation, element_id);
+                    self.split_wires(&[element_id]);
                 }
                 self.current_element = None;
             }
 
-            for element in self.elements.values_mut() {
+            self.simulation.draw_window(ctx);
+
+            // elements whose sense nodes were changed in their window, with their old node positions
+            let mut rewired = Vec::new();
+            let mut changed = false;
+            for (id, element) in self.elements.iter_mut() {
                 let screen_pos = element.pos() * self.grid_step + self.offset;
                 let screen_size = element.size() * self.grid_step;
-                let window_pos = element.draw_window(ctx);
+                let positions = element.get_node_positions();
+                let window = element.draw_window(ctx, &self.solution, &self.nodes, &element_types);
+                changed |= window.changed;
+                if element.get_node_positions() != positions {
+                    rewired.push((*id, positions));
+                }
 
                 let stroke;
 
-                if let Some(window_pos) = window_pos {
+                if let Some(window_pos) = window.hovered {
                     stroke = Stroke::new(2.0, Color32::GREEN);
                     let arrow_stroke = Stroke::new(1.0, Color32::GREEN);
 
@@ -252,25 +408,41 @@ impl eframe::App for RustyCircuits {
                     let normalized = (center - window_pos).normalized();
                     let end = center - normalized * 20.0;
 
-                    let normal = Vec2::new(normalized.y, -normalized.x);
-                    //ui.painter().line_segment([end, end - normalized * 6.0 + normal * 3.0], arrow_stroke);
-                    //ui.painter().line_segment([end, end - normalized * 6.0 - normal * 3.0], arrow_stroke);
                     ui.painter().line_segment([window_pos, end], arrow_stroke);
                 } else {
                     stroke = Stroke::new(2.0, Color32::WHITE);
                 }
 
-                element.draw(ui, stroke, self.grid_step, screen_pos, screen_size, &self.nodes);
+                let stroke = if self.selection.contains(id) {
+                    Stroke::new(2.0, Color32::LIGHT_BLUE)
+                } else if self.warnings.iter().any(|warning| warning.elements.contains(id)) {
+                    Stroke::new(2.0, WARNING_COLOR)
+                } else {
+                    stroke
+                };
+                changed |= element.draw(ui, stroke, self.grid_step, screen_pos, screen_size, &self.nodes);
 
-                let center = screen_pos + screen_size / 2.0;
+                if self.debug_options.show_element_currents {
+                    if let (Some(current), Some(power)) = (self.solution.current(*id), self.solution.power(*id)) {
+                        let center = screen_pos + screen_size / 2.0;
+                        ui.painter().text(center + Vec2::new(0.0, -15.0), Align2::CENTER_BOTTOM, format!("{:.3}A {:.3}W", current, power), FontId::proportional(12.0), Color32::YELLOW);
+                    }
+                }
+            }
 
-                /*ui.allocate_ui_at_rect(Rect::from_two_pos(center, center + Vec2::new(20.0, 20.0)), |ui| {
-                    ui.label(egui::RichText::new(format!("{}", element.get_id())).color(Rgba::GREEN));
-                });*/
+            if changed {
+                self.edited();
+            }
+            for (id, positions) in rewired {
+                self.reconnect_element(id, &positions);
             }
 
             for (pos, node) in self.nodes.iter() {
                 let screen_pos = self.grid_to_screen(Pos2::new(pos.0 as f32, pos.1 as f32));
+                // junction dot where more than two element ends meet
+                if node.connections.len() > 2 {
+                    ui.painter().circle_filled(screen_pos, 4.0, Color32::WHITE);
+                }
                 if self.debug_options.show_node_numbers {
                     ui.allocate_ui_at_rect(Rect::from_two_pos(screen_pos + Vec2::new(5.0, 5.0), screen_pos + Vec2::new(50.0, 50.0)), |ui| {
                         ui.label(RichText::new(format!("{}", node.id)).color(Rgba::RED));
@@ -284,8 +456,10 @@ impl eframe::App for RustyCircuits {
                 }
             }
 
+            self.draw_warnings(ui);
+
             if self.current_element.is_some() {
-                let mut element = self.current_element.as_deref_mut().unwrap();
+                let element = self.current_element.as_deref_mut().unwrap();
                 let screen_pos = element.pos() * self.grid_step + self.offset;
                 let screen_size = element.size() * self.grid_step;
 
@@ -297,95 +471,645 @@ impl eframe::App for RustyCircuits {
                 element.draw(ui, stroke, self.grid_step, screen_pos, screen_size, &self.nodes);
             }
 
-            let mut nodes: Vec<_> = self.nodes.values().collect::<Vec<&Node>>().into_iter().cloned().collect();
-            let mut elements = self.elements.clone();
-            let mut simplification_info = String::new();
-            // ground node
-            nodes.insert(0, Node { id: 0, voltage: 0.0, connections: BTreeSet::new() });
-            let nodes_map = simplify_graph(&mut nodes, &mut elements, &mut simplification_info);
-            if self.debug_options.info_simplfication {
-                debug_info += "------ Simplifying nodes ------\n";
-                debug_info += simplification_info.as_str();
-                debug_info += "-------------------------------\n";
+            if self.simulation.take_reset() {
+                self.oscilloscope.clear();
+                for element in self.elements.values_mut() {
+                    element.device_mut().reset_state();
+                }
+                self.solved = None;
             }
 
-            if self.debug_options.info_node_map {
-                debug_info += format!("Node map: {:?}\n", nodes_map).as_str();
+            // a paused circuit is only solved again after an edit, a running one every step
+            self.track_edits(ctx);
+            let key = SolveKey { revision: self.revision, timestep: self.simulation.timestep, debug_options: self.debug_options.clone() };
+            let changed = self.solved.as_ref() != Some(&key);
+            let steps = if self.simulation.running { self.simulation.steps_per_frame } else { changed as u32 };
+            if self.simulation.running || changed {
+                // the next frame shows what this one solved
+                ctx.request_repaint();
+            }
+            if changed {
+                self.update_circuit();
+                // the editor always steps in time, so capacitors conduct
+                self.warnings = self.circuit.check_at(&self.simulation.next_step(None));
             }
+            self.solved = Some(key);
+            for _ in 0..steps {
+                let breakpoint = self.elements.values().filter_map(|element| element.device().next_breakpoint(self.simulation.time)).min_by(f64::total_cmp);
+                let step = self.simulation.next_step(breakpoint);
+                // only the info of the last step is shown
+                debug_info.clear();
+                let solution = match self.solve(&step, &mut debug_info) {
+                    Ok(solution) => solution,
+                    Err(error) => {
+                        self.solver_error = Some(error);
+                        break;
+                    }
+                };
+                self.solver_error = None;
+                self.solution = solution.clone();
 
+                // a paused simulation is still solved so edits show up, but time does not move
+                if self.simulation.running {
+                    for element in self.elements.values_mut() {
+                        element.device_mut().update_state(&solution, &step);
+                    }
+                    self.simulation.advance(&step);
+                    self.oscilloscope.record(self.simulation.time, &self.nodes, &solution);
+                }
+            }
 
-            let mut voltage_nodes: u32 = 0;
-            for (id, element) in elements.iter_mut() {
-                if element.get_voltage_source_count() > 0 {
-                    element.set_voltage_node(voltage_nodes);
-                    voltage_nodes += element.get_voltage_source_count();
+            ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
+                ui.label(RichText::new(debug_info.as_str()).family(FontFamily::Monospace).small());
+                // the checker already explains the errors it found
+                if let Some(error) = self.solver_error.as_ref().filter(|error| !self.warnings.iter().any(|warning| warning.error == **error)) {
+                    ui.label(RichText::new(error.to_string()).color(Color32::RED));
                 }
+                for warning in self.warnings.iter() {
+                    ui.label(RichText::new(warning.error.to_string()).color(WARNING_COLOR));
+                }
+                if let Some(error) = &self.file_error {
+                    ui.label(RichText::new(error.to_string()).color(Color32::RED));
+                }
+            });
+            self.debug_info = debug_info;
+        });
+
+        self.update_history(ctx);
+    }
+}
+
+impl RustyCircuits {
+    // hands the elements to the solver and writes the result into the node voltages
+    fn solve(&mut self, step: &StepInfo, debug_info: &mut String) -> Result<Solution, SolverError> {
+        let circuit = self.to_circuit();
+        let mut info = SolverInfo::default();
+        let result = self.solver.solve(&circuit, step, &mut info);
+
+        if self.debug_options.info_simplfication {
+            *debug_info += "------ Simplifying nodes ------\n";
+            *debug_info += info.simplification.as_str();
+            *debug_info += "-------------------------------\n";
+        }
+
+        if self.debug_options.info_node_map {
+            *debug_info += format!("Node map: {:?}\n", info.nets.merged()).as_str();
+        }
+
+        if info.iterations > 0 {
+            *debug_info += format!("Newton iterations: {}\n", info.iterations).as_str();
+        }
+
+        if let (true, Some(matrix)) = (self.debug_options.info_admittance_matrix, &info.matrix) {
+            *debug_info += format!("Admittance Matrix:{}\n", matrix.to_dense()).as_str();
+        }
+
+        if let (true, Some(currents)) = (self.debug_options.info_injected_currents, &info.vector) {
+            *debug_info += format!("Injected currents:{}\n", currents).as_str();
+        }
+
+        let solution = result?;
+        for node in self.nodes.values_mut() {
+            node.voltage = solution.voltage(node.id);
+        }
+
+        if let (true, Some(voltages)) = (self.debug_options.info_node_voltages, &info.solution) {
+            *debug_info += format!("Node voltages:{}\n", voltages).as_str();
+        }
+
+        Ok(solution)
+    }
+
+    // rings around the nodes and a message next to the first element of every warning
+    fn draw_warnings(&self, ui: &egui::Ui) {
+        for warning in self.warnings.iter() {
+            let mut anchor = None;
+            for (position, _) in self.nodes.iter().filter(|(_, node)| warning.nodes.contains(&node.id)) {
+                let screen_pos = self.grid_to_screen(Pos2::new(position.0 as f32, position.1 as f32));
+                ui.painter().circle_stroke(screen_pos, 6.0, Stroke::new(1.5, WARNING_COLOR));
+                anchor = anchor.or(Some(screen_pos));
+            }
+            if let Some(element) = warning.elements.first().and_then(|id| self.elements.get(id)) {
+                let center = element.pos() + element.size() / 2.0;
+                anchor = Some(self.grid_to_screen(center));
+            }
+            if let Some(anchor) = anchor {
+                let font = FontId::proportional(13.0);
+                let galley = ui.painter().layout_no_wrap(warning.error.to_string(), font, WARNING_COLOR);
+                let rect = Align2::CENTER_TOP.anchor_size(anchor + Vec2::new(0.0, 18.0), galley.size()).expand(3.0);
+                ui.painter().rect_filled(rect, 3.0, Color32::from_black_alpha(200));
+                ui.painter().galley(rect.min + Vec2::splat(3.0), galley, WARNING_COLOR);
             }
+        }
+    }
 
-            // 1 for the ground node
-            let mut matrix_size = nodes.len() + voltage_nodes as usize;
+    // element of the selected type dragged out from pos, it is not part of the circuit yet
+    fn create_element(&self, pos: Pos2, drag: Vec2) -> Box<dyn CircuitElement> {
+        let ((x, y), orientation) = placement(self.selected_element_type, (drag.x as i32, drag.y as i32));
+        let mut element = Self::create_element_of_type(self.selected_element_type, pos, Vec2::new(x as f32, y as f32), 0, Vec::new());
+        element.set_orientation(orientation);
+        element
+    }
 
-            if matrix_size > 1 {
-                let mut admittance_matrix = DMatrix::from_element(matrix_size, matrix_size, 0.0);
-                let mut currents = DVector::<f64>::zeros(matrix_size);
+    fn create_element_of_type(element_type: ElementType, pos: Pos2, size: Vec2, id: u32, nodes: Vec<u32>) -> Box<dyn CircuitElement> {
+        match element_type {
+            ElementType::Wire => components::wire::Wire::new_boxed(pos, size, id, nodes),
+            ElementType::Resistor => components::resistor::Resistor::new_boxed(pos, size, id, nodes),
+            ElementType::Capacitor => components::capacitor::Capacitor::new_boxed(pos, size, id, nodes),
+            ElementType::Inductor => components::inductor::Inductor::new_boxed(pos, size, id, nodes),
+            ElementType::DCVoltageSource => components::dc_voltage_source::DCVoltageSource::new_boxed(pos, size, id, nodes),
+            ElementType::CurrentSource => components::current_source::CurrentSource::new_boxed(pos, size, id, nodes),
+            ElementType::Switch => components::circuit_switch::Switch::new_boxed(pos, size, id, nodes),
+            ElementType::Ground => components::ground::Ground::new_boxed(pos, size, id, nodes),
+            ElementType::Diode => components::diode::Diode::new_boxed(pos, size, id, nodes),
+            ElementType::Vcvs => ControlledSource::<devices::Vcvs>::new_boxed(pos, size, id, nodes),
+            ElementType::Vccs => ControlledSource::<devices::Vccs>::new_boxed(pos, size, id, nodes),
+            ElementType::Ccvs => ControlledSource::<devices::Ccvs>::new_boxed(pos, size, id, nodes),
+            ElementType::Cccs => ControlledSource::<devices::Cccs>::new_boxed(pos, size, id, nodes),
+            ElementType::OpAmp => components::op_amp::OpAmp::new_boxed(pos, size, id, nodes),
+            ElementType::Bjt => components::bjt::Bjt::new_boxed(pos, size, id, nodes),
+            ElementType::Mosfet => components::mosfet::Mosfet::new_boxed(pos, size, id, nodes),
+        }
+    }
 
-                for element in elements.values() {
-                    element.stamp_matrix(&mut admittance_matrix, &mut currents, &nodes);
-                }
+    // creates the element and connects it to the nodes at its node positions
+    fn insert_element(&mut self, element_type: ElementType, pos: Pos2, size: Vec2, orientation: Orientation, element_id: u32) -> &mut Box<dyn CircuitElement> {
+        let mut element = Self::create_element_of_type(element_type, pos, size, element_id, Vec::new());
+        element.set_orientation(orientation);
+        self.place_element(element_id, element)
+    }
 
-                if self.debug_options.info_admittance_matrix {
-                    debug_info += format!("Admittance Matrix:{}\n", admittance_matrix).as_str();
-                }
+    // connects the element to the nodes at its node positions, new nodes are created where there are none
+    fn place_element(&mut self, element_id: u32, mut element: Box<dyn CircuitElement>) -> &mut Box<dyn CircuitElement> {
+        let mut node_ids = Vec::new();
+        for position in element.get_node_positions() {
+            if self.nodes.contains_key(&position) {
+                let node = self.nodes.get_mut(&position).unwrap();
+                node_ids.push(node.id);
+                node.connections.insert(element_id);
+            } else {
+                let id = self.get_next_node_id();
+                node_ids.push(id);
+                self.nodes.insert(position, Node { id, voltage: 0.0, connections: BTreeSet::from([element_id]) });
+            }
+        }
+        element.device_mut().set_nodes(node_ids);
+        self.edited();
+        // elements restored from a file or the history keep their id
+        self.next_element_id = self.next_element_id.max(element_id + 1);
+        self.elements.insert(element_id, element);
+        self.elements.get_mut(&element_id).unwrap()
+    }
 
-                if self.debug_options.info_injected_currents {
-                    debug_info += format!("Injected currents:{}\n", currents).as_str();
+    // disconnects the element from its nodes, nodes without any connection left are removed
+    fn remove_element(&mut self, element_id: u32) -> Option<Box<dyn CircuitElement>> {
+        let element = self.elements.remove(&element_id)?;
+        self.edited();
+        self.disconnect(element_id, &element.get_node_positions());
+        Some(element)
+    }
+
+    fn disconnect(&mut self, element_id: u32, positions: &[(i32, i32)]) {
+        for position in positions {
+            if let Some(node) = self.nodes.get_mut(position) {
+                node.connections.remove(&element_id);
+                if node.connections.is_empty() {
+                    self.nodes.remove(position);
                 }
+            }
+        }
+    }
+
+    // connects an element whose node positions changed, positions are the ones it was connected at
+    fn reconnect_element(&mut self, element_id: u32, positions: &[(i32, i32)]) {
+        let Some(element) = self.elements.remove(&element_id) else {
+            return;
+        };
+        self.disconnect(element_id, positions);
+        self.place_element(element_id, element);
+        self.merge_wires(positions);
+    }
 
-                let pseudoinverse = admittance_matrix.clone().pseudo_inverse(1.0e-12).unwrap();
-                let voltages = pseudoinverse * currents;
+    // lines an element covers on the screen: from its anchor to its far end and from each pin to the
+    // middle of the two, for two terminal elements these are all the same line
+    fn element_lines(&self, element: &dyn CircuitElement) -> Vec<[Pos2; 2]> {
+        let start = self.grid_to_screen(element.pos());
+        let end = start + element.size() * self.grid_step;
+        let center = start + (end - start) / 2.0;
+        let pins = element.pins().into_iter().map(|(x, y)| self.grid_to_screen(Pos2::new(x as f32, y as f32)));
+        std::iter::once([start, end]).chain(pins.map(|pin| [pin, center])).collect()
+    }
+
+    // element whose lines pass closest to a screen position
+    fn element_at(&self, screen_pos: Pos2) -> Option<u32> {
+        const HIT_DISTANCE: f32 = 8.0;
+        self.elements.iter()
+            .map(|(id, element)| {
+                let distance = self.element_lines(element.as_ref()).into_iter().map(|[start, end]| {
+                    let along = ((screen_pos - start).dot(end - start) / (end - start).length_sq().max(1.0)).clamp(0.0, 1.0);
+                    screen_pos.distance(start + (end - start) * along)
+                }).fold(f32::INFINITY, f32::min);
+                (*id, distance)
+            })
+            .filter(|(_, distance)| *distance < HIT_DISTANCE)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(id, _)| id)
+    }
 
-                // map the nodes back to the original nodes
-                for (index, node) in nodes.into_iter().enumerate() {
-                    // skip the ground node
-                    if node.id == 0 {
-                        continue;
+    fn handle_selection(&mut self, ui: &egui::Ui, response: &egui::Response, input: &egui::InputState) {
+        let shift = input.modifiers.shift;
+        let pointer = response.interact_pointer_pos();
+
+        if response.clicked() {
+            match pointer.and_then(|pointer| self.element_at(pointer)) {
+                Some(id) if shift => {
+                    if !self.selection.remove(&id) {
+                        self.selection.insert(id);
                     }
+                }
+                Some(id) => self.selection = BTreeSet::from([id]),
+                None if shift => {}
+                None => self.selection.clear(),
+            }
+        }
 
-                    let mapped_node_ids = nodes_map.get(&node.id);
-                    // if the node is mapped to another node, also update the voltage of the mapped node
-                    if let Some(mapped_node_ids) = mapped_node_ids {
-                        for mapped_node_id in mapped_node_ids.iter() {
-                            let node = self.nodes.values_mut().find(|n| n.id == *mapped_node_id).unwrap();
-                            node.voltage = voltages[(index, 0)];
+        if response.drag_started() {
+            let start = input.pointer.press_origin();
+            self.selection_drag = start.map(|start| match self.element_at(start) {
+                Some(id) => {
+                    if !self.selection.contains(&id) {
+                        if !shift {
+                            self.selection.clear();
                         }
+                        self.selection.insert(id);
                     }
+                    SelectionDrag::Move(self.screen_to_grid(start))
+                }
+                None => SelectionDrag::Band(start),
+            });
+        }
+
+        if response.dragged() {
+            if let Some(pointer) = pointer {
+                match self.selection_drag {
+                    Some(SelectionDrag::Move(last)) => {
+                        let grid = self.screen_to_grid(pointer);
+                        if grid != last {
+                            self.rearrange_selection(|pos| pos + (grid - last));
+                            self.selection_drag = Some(SelectionDrag::Move(grid));
+                        }
+                    }
+                    Some(SelectionDrag::Band(start)) => {
+                        let band = Rect::from_two_pos(start, pointer);
+                        ui.painter().rect_stroke(band, 0.0, Stroke::new(1.0, Color32::LIGHT_BLUE));
+                    }
+                    None => {}
+                }
+            }
+        }
 
-                    let node = self.nodes.values_mut().find(|n| n.id == node.id).unwrap();
-                    node.voltage = voltages[(index, 0)];
+        if response.drag_stopped() {
+            if let (Some(SelectionDrag::Band(start)), Some(pointer)) = (self.selection_drag.take(), pointer) {
+                let band = Rect::from_two_pos(start, pointer);
+                if !shift {
+                    self.selection.clear();
                 }
-                if self.debug_options.info_node_voltages {
-                    debug_info += format!("Node voltages:{}\n", voltages).as_str();
+                for (id, element) in self.elements.iter() {
+                    if self.element_lines(element.as_ref()).iter().flatten().all(|point| band.contains(*point)) {
+                        self.selection.insert(*id);
+                    }
                 }
             }
+            self.selection_drag = None;
+        }
 
-            ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
-                ui.label(RichText::new(debug_info).family(FontFamily::Monospace).small());
-            });
+        // keys are left to text fields while they have the focus
+        if !ui.ctx().wants_keyboard_input() {
+            if input.key_pressed(Key::R) {
+                self.transform_selection(|(x, y)| (-y, x));
+            }
+            if input.key_pressed(Key::F) {
+                self.transform_selection(|(x, y)| (-x, y));
+            }
+            if input.key_pressed(Key::Delete) || input.key_pressed(Key::Backspace) {
+                self.delete_selection();
+            }
+        }
+    }
+
+    // rotates or flips the selection around the grid point closest to its center,
+    // transform maps grid offsets from that point
+    fn transform_selection(&mut self, transform: impl Fn((i32, i32)) -> (i32, i32)) {
+        let positions: Vec<Pos2> = self.selection.iter()
+            .filter_map(|id| self.elements.get(id))
+            .flat_map(|element| {
+                let pins = element.pins().into_iter().map(|(x, y)| Pos2::new(x as f32, y as f32));
+                pins.chain([element.pos(), element.pos() + element.size()])
+            })
+            .collect();
+        if positions.is_empty() {
+            return;
+        }
+        let center = (positions.iter().fold(Vec2::ZERO, |sum, pos| sum + pos.to_vec2()) / positions.len() as f32).round();
+
+        self.rearrange_selection(|pos| {
+            let (x, y) = transform(((pos.x - center.x) as i32, (pos.y - center.y) as i32));
+            Pos2::new(x as f32, y as f32) + center
         });
     }
-}
 
-impl RustyCircuits {
-    fn create_element(&self, pos: Pos2, size: Vec2, id: u32, nodes: Vec<u32>) -> Box<dyn CircuitElement> {
-        match self.selected_element_type {
-            ElementType::Wire => components::wire::Wire::new_boxed(pos, size, id, nodes),
-            ElementType::Resistor => components::resistor::Resistor::new_boxed(pos, size, id, nodes),
-            ElementType::Capacitor => components::capacitor::Capacitor::new_boxed(pos, size, id, nodes),
-            ElementType::DCVoltageSource => components::dc_voltage_source::DCVoltageSource::new_boxed(pos, size, id, nodes),
-            ElementType::CurrentSource => components::current_source::CurrentSource::new_boxed(pos, size, id, nodes),
-            ElementType::Switch => components::circuit_switch::Switch::new_boxed(pos, size, id, nodes),
-            ElementType::Ground => components::ground::Ground::new_boxed(pos, size, id, nodes),
+    // moves both ends of every selected element and turns its pin layout along, all of them are
+    // disconnected first so they can not connect to each other's old nodes
+    fn rearrange_selection(&mut self, apply: impl Fn(Pos2) -> Pos2) {
+        let ids: Vec<u32> = self.selection.iter().copied().collect();
+        let moved: Vec<(u32, Box<dyn CircuitElement>)> = ids.into_iter()
+            .filter_map(|id| self.remove_element(id).map(|element| (id, element)))
+            .collect();
+        let mut vacated = Vec::new();
+        let mut ids = Vec::new();
+        for (id, mut element) in moved {
+            vacated.extend(element.get_node_positions());
+            let start = apply(element.pos());
+            let end = apply(element.pos() + element.size());
+            let axis = |(x, y): (i32, i32)| {
+                let moved = apply(element.pos() + Vec2::new(x as f32, y as f32)) - start;
+                (moved.x as i32, moved.y as i32)
+            };
+            let orientation = element.orientation();
+            let orientation = Orientation::from_axes(axis(orientation.apply((1, 0))), axis(orientation.apply((0, 1))));
+            element.set_placement(start, end - start);
+            element.set_orientation(orientation);
+            self.place_element(id, element);
+            ids.push(id);
+        }
+        self.merge_wires(&vacated);
+        self.split_wires(&ids);
+    }
+
+    fn delete_selection(&mut self) {
+        let mut vacated = Vec::new();
+        for id in std::mem::take(&mut self.selection) {
+            if let Some(element) = self.remove_element(id) {
+                vacated.extend(element.get_node_positions());
+            }
+        }
+        self.merge_wires(&vacated);
+    }
+
+    fn wire_ends(&self, id: u32) -> Option<((i32, i32), (i32, i32))> {
+        let element = self.elements.get(&id).filter(|element| element.device().get_type() == ElementType::Wire)?;
+        let positions = element.get_node_positions();
+        Some((positions[0], positions[1]))
+    }
+
+    // whether a wire passes through a grid point between its ends
+    fn wire_contains(&self, id: u32, position: (i32, i32)) -> bool {
+        let Some((a, b)) = self.wire_ends(id) else {
+            return false;
+        };
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let (px, py) = (position.0 - a.0, position.1 - a.1);
+        let along = px * dx + py * dy;
+        px * dy - py * dx == 0 && along > 0 && along < dx * dx + dy * dy
+    }
+
+    fn wire_through(&self, position: (i32, i32)) -> Option<u32> {
+        self.elements.keys().copied().find(|id| self.wire_contains(*id, position))
+    }
+
+    // connects the given elements to the wires they touch: a wire is split in two where an endpoint
+    // lands on its span, and a placed wire is split at every node it passes over
+    fn split_wires(&mut self, ids: &[u32]) {
+        let mut positions: Vec<(i32, i32)> = Vec::new();
+        for id in ids {
+            let Some(element) = self.elements.get(id) else {
+                continue;
+            };
+            positions.extend(element.get_node_positions());
+            positions.extend(self.nodes.keys().copied().filter(|position| self.wire_contains(*id, *position)));
+        }
+
+        for position in positions {
+            while let Some(id) = self.wire_through(position) {
+                let (a, b) = self.wire_ends(id).unwrap();
+                let to_pos = |(x, y): (i32, i32)| Pos2::new(x as f32, y as f32);
+                self.remove_element(id);
+                self.insert_element(ElementType::Wire, to_pos(a), to_pos(position) - to_pos(a), Orientation::default(), id);
+                let second = self.get_next_element_id();
+                self.insert_element(ElementType::Wire, to_pos(position), to_pos(b) - to_pos(position), Orientation::default(), second);
+                if self.selection.contains(&id) {
+                    self.selection.insert(second);
+                }
+            }
+        }
+    }
+
+    // joins two collinear wires that meet at a point where nothing else is connected any more
+    fn merge_wires(&mut self, positions: &[(i32, i32)]) {
+        for position in positions {
+            let Some(node) = self.nodes.get(position) else {
+                continue;
+            };
+            let ids: Vec<u32> = node.connections.iter().copied().collect();
+            let [first, second] = ids[..] else {
+                continue;
+            };
+            let (Some(ends1), Some(ends2)) = (self.wire_ends(first), self.wire_ends(second)) else {
+                continue;
+            };
+            let far = |(a, b): ((i32, i32), (i32, i32))| if a == *position { b } else { a };
+            let (start, end) = (far(ends1), far(ends2));
+
+            // the junction has to lie between the two far ends on a straight line
+            let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+            let (px, py) = (position.0 - start.0, position.1 - start.1);
+            let along = px * dx + py * dy;
+            if px * dy - py * dx != 0 || along <= 0 || along >= dx * dx + dy * dy {
+                continue;
+            }
+
+            self.remove_element(first);
+            self.remove_element(second);
+            let start = Pos2::new(start.0 as f32, start.1 as f32);
+            let end = Pos2::new(end.0 as f32, end.1 as f32);
+            self.insert_element(ElementType::Wire, start, end - start, Orientation::default(), first);
+            if self.selection.remove(&second) {
+                self.selection.insert(first);
+            }
+        }
+    }
+
+    fn to_circuit(&self) -> Circuit {
+        let mut circuit = Circuit::new();
+        for (id, element) in self.elements.iter() {
+            circuit.insert(*id, element.device().clone_box());
+        }
+        circuit
+    }
+
+    fn update_circuit(&mut self) {
+        if self.circuit_revision != Some(self.revision) {
+            self.circuit = self.to_circuit();
+            self.circuit_revision = Some(self.revision);
+        }
+    }
+
+    fn records(&self) -> BTreeMap<u32, ElementRecord> {
+        self.to_schematic().elements.into_iter().map(|record| (record.id, record)).collect()
+    }
+
+    // every edit bumps the revision, the history only takes its snapshot once the edit is done, so a
+    // drag or a slider move becomes one command when the pointer is released
+    fn track_edits(&mut self, ctx: &egui::Context) {
+        if !ctx.input(|input| input.pointer.any_down()) {
+            self.record_edits();
+        }
+    }
+
+    fn record_edits(&mut self) {
+        if self.tracked != self.revision {
+            self.history.track(self.records());
+            self.tracked = self.revision;
+        }
+    }
+
+    fn edited(&mut self) {
+        self.revision += 1;
+    }
+
+    // Ctrl+Z undoes, Ctrl+Shift+Z redoes
+    fn update_history(&mut self, ctx: &egui::Context) {
+        let (z, shift) = ctx.input(|input| (input.modifiers.command && input.key_pressed(Key::Z), input.modifiers.shift));
+        if z && !ctx.wants_keyboard_input() {
+            if shift {
+                self.redo();
+            } else {
+                self.undo();
+            }
+            // the next frame solves what was restored
+            ctx.request_repaint();
+        }
+    }
+
+    // edits that were not recorded yet become a command of their own first, the history already holds
+    // what is restored
+    fn undo(&mut self) {
+        self.record_edits();
+        let records = self.history.undo();
+        self.restore_records(records);
+        self.tracked = self.revision;
+    }
+
+    fn redo(&mut self) {
+        self.record_edits();
+        let records = self.history.redo();
+        self.restore_records(records);
+        self.tracked = self.revision;
+    }
+
+    // all elements are removed before any is placed again, so none of them connects to a stale node
+    fn restore_records(&mut self, records: Vec<(u32, Option<ElementRecord>)>) {
+        for (id, _) in records.iter() {
+            self.remove_element(*id);
+            self.selection.remove(id);
         }
+        for record in records.into_iter().filter_map(|(_, record)| record) {
+            self.insert_record(&record);
+        }
+    }
+
+    fn insert_record(&mut self, record: &ElementRecord) {
+        let pos = Pos2::new(record.position[0] as f32, record.position[1] as f32);
+        let size = Vec2::new(record.size[0] as f32, record.size[1] as f32);
+        let mut element = Self::create_element_of_type(record.parameters.element_type(), pos, size, record.id, Vec::new());
+        element.device_mut().set_parameters(&record.parameters);
+        element.set_sense(record.sense.map(|sense| sense.map(|[x, y]| (x, y))));
+        element.set_orientation(record.orientation);
+        self.place_element(record.id, element);
+    }
+
+    fn to_schematic(&self) -> Schematic {
+        let mut schematic = Schematic::new(View { offset: [self.offset.x, self.offset.y], grid_step: self.grid_step });
+        for (id, element) in self.elements.iter() {
+            schematic.elements.push(ElementRecord {
+                id: *id,
+                position: [element.pos().x as i32, element.pos().y as i32],
+                size: [element.size().x as i32, element.size().y as i32],
+                parameters: element.device().parameters(),
+                sense: element.sense().map(|sense| sense.map(|(x, y)| [x, y])),
+                orientation: element.orientation(),
+            });
+        }
+        schematic
+    }
+
+    fn load_schematic(&mut self, schematic: Schematic) {
+        self.elements.clear();
+        self.edited();
+        self.nodes.clear();
+        self.selection.clear();
+        self.current_element = None;
+        self.simulation = Simulation::new();
+        self.oscilloscope.clear();
+        self.offset = Vec2::new(schematic.view.offset[0], schematic.view.offset[1]);
+        self.grid_step = schematic.view.grid_step;
+
+        for record in schematic.elements.iter() {
+            self.insert_record(record);
+        }
+        // opening a file can not be undone
+        self.history.reset(self.records());
+        self.tracked = self.revision;
+    }
+
+    fn draw_file_menu(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("File", |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Path");
+                ui.text_edit_singleline(&mut self.file_path);
+            });
+            if ui.button("Save").clicked() {
+                self.file_error = self.to_schematic().save(Path::new(&self.file_path)).err().map(Into::into);
+                ui.close_menu();
+            }
+            if ui.button("Open").clicked() {
+                match Schematic::load(Path::new(&self.file_path)) {
+                    Ok(schematic) => {
+                        self.load_schematic(schematic);
+                        self.file_error = None;
+                    }
+                    Err(error) => self.file_error = Some(error.into()),
+                }
+                ui.close_menu();
+            }
+            // the netlist goes next to the schematic, with the .cir extension
+            if ui.button("Export SPICE").clicked() {
+                let path = Path::new(&self.file_path).with_extension("cir");
+                let title = path.file_stem().map_or("circuit".into(), |stem| stem.to_string_lossy());
+                let netlist = spice::export(&self.to_circuit(), &title);
+                self.file_error = std::fs::write(&path, netlist).err().map(Into::into);
+                ui.close_menu();
+            }
+            if ui.button("Import SPICE").clicked() {
+                match spice::load(&Path::new(&self.file_path).with_extension("cir")) {
+                    Ok(netlist) => {
+                        let view = View { offset: [self.offset.x, self.offset.y], grid_step: self.grid_step };
+                        self.load_schematic(netlist.to_schematic(view));
+                        // a transient analysis in the deck sets the time step, an ac analysis the sweep
+                        for analysis in netlist.analyses.iter() {
+                            match analysis {
+                                Analysis::Transient { step, .. } => self.simulation.timestep = *step,
+                                Analysis::Ac(sweep) => self.bode.sweep = *sweep,
+                                Analysis::OperatingPoint => {}
+                            }
+                        }
+                        self.file_error = None;
+                    }
+                    Err(error) => self.file_error = Some(error.into()),
+                }
+                ui.close_menu();
+            }
+        });
     }
 
     fn grid_to_screen(&self, pos: Pos2) -> Pos2 {
@@ -400,25 +1124,15 @@ impl RustyCircuits {
         (vec / self.grid_step).round()
     }
 
-    fn get_next_element_id(&self) -> u32 {
-        let mut id = 1;
-        for i in 1.. {
-            if !self.elements.contains_key(&(i as u32)) {
-                id = i as u32;
-                break;
-            }
-        }
+    fn get_next_element_id(&mut self) -> u32 {
+        let id = self.next_element_id;
+        self.next_element_id += 1;
         id
     }
 
-    fn get_next_node_id(&self) -> u32 {
-        let mut id = 1;
-        for i in 1.. {
-            if !self.nodes.values().any(|node| node.id == i as u32) {
-                id = i as u32;
-                break;
-            }
-        }
+    fn get_next_node_id(&mut self) -> u32 {
+        let id = self.next_node_id;
+        self.next_node_id += 1;
         id
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,332 @@
+use std::collections::{HashMap, VecDeque};
+use eframe::egui;
+use eframe::egui::{Align2, Color32, FontId, Pos2, Rect, Sense, Shape, Stroke, Vec2};
+use rusty_circuit::circuit::Solution;
+use rusty_circuit::ElementType;
+use crate::Node;
+
+const HORIZONTAL_DIVISIONS: usize = 10;
+const VERTICAL_DIVISIONS: usize = 8;
+// samples kept per channel, older ones are dropped
+const MAX_SAMPLES: usize = 100_000;
+pub const COLORS: [Color32; 6] = [
+    Color32::YELLOW,
+    Color32::LIGHT_BLUE,
+    Color32::from_rgb(255, 80, 200),
+    Color32::LIGHT_GREEN,
+    Color32::from_rgb(255, 160, 60),
+    Color32::WHITE,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Probe {
+    // voltage of the node at a grid point, read from the node voltages the solver writes
+    Voltage((i32, i32)),
+    // current through an element from its first to its second node
+    Current(u32),
+}
+
+impl Probe {
+    pub fn label(&self) -> String {
+        match self {
+            Probe::Voltage((x, y)) => format!("V({}, {})", x, y),
+            Probe::Current(element) => format!("I({})", element),
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Probe::Voltage(_) => "V",
+            Probe::Current(_) => "A",
+        }
+    }
+}
+
+struct Channel {
+    probe: Probe,
+    color: Color32,
+    // volts (or amperes) per division
+    scale: f64,
+    // (time, value)
+    samples: VecDeque<(f64, f64)>,
+}
+
+impl Channel {
+    // value at a time, interpolated between the samples around it
+    fn value_at(&self, time: f64) -> Option<f64> {
+        let index = self.samples.partition_point(|(t, _)| *t < time);
+        let (t2, v2) = *self.samples.get(index)?;
+        if index == 0 {
+            return (t2 == time).then_some(v2);
+        }
+        let (t1, v1) = self.samples[index - 1];
+        Some(v1 + (v2 - v1) * (time - t1) / (t2 - t1))
+    }
+}
+
+pub struct Oscilloscope {
+    pub open: bool,
+    docked: bool,
+    channels: Vec<Channel>,
+    time_per_division: f64,
+    // a frozen scope keeps showing what it had and records nothing
+    frozen: bool,
+    trigger: bool,
+    trigger_channel: usize,
+    trigger_level: f64,
+    cursors: bool,
+    // cursor positions as fractions of the screen width
+    cursor_a: f32,
+    cursor_b: f32,
+    time: f64,
+}
+
+impl Oscilloscope {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            docked: true,
+            channels: Vec::new(),
+            time_per_division: 1.0e-3,
+            frozen: false,
+            trigger: false,
+            trigger_channel: 0,
+            trigger_level: 0.0,
+            cursors: false,
+            cursor_a: 0.25,
+            cursor_b: 0.75,
+            time: 0.0,
+        }
+    }
+
+    // called after every simulated step
+    pub fn record(&mut self, time: f64, nodes: &HashMap<(i32, i32), Node>, solution: &Solution) {
+        if self.frozen {
+            return;
+        }
+        self.time = time;
+        for channel in self.channels.iter_mut() {
+            let value = match channel.probe {
+                Probe::Voltage(position) => nodes.get(&position).map(|node| node.voltage),
+                Probe::Current(element) => solution.current(element),
+            };
+            if let Some(value) = value {
+                channel.samples.push_back((time, value));
+                if channel.samples.len() > MAX_SAMPLES {
+                    channel.samples.pop_front();
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.time = 0.0;
+        for channel in self.channels.iter_mut() {
+            channel.samples.clear();
+        }
+    }
+
+    // the scope is a panel at the bottom of the window or a free window
+    pub fn draw(&mut self, ctx: &egui::Context, nodes: &HashMap<(i32, i32), Node>, elements: &[(u32, ElementType)]) {
+        if !self.open {
+            return;
+        }
+        if self.docked {
+            egui::TopBottomPanel::bottom("oscilloscope").resizable(true).default_height(300.0).show(ctx, |ui| {
+                self.draw_contents(ui, nodes, elements);
+            });
+        } else {
+            let mut open = self.open;
+            egui::Window::new("Oscilloscope").open(&mut open).default_size([600.0, 350.0]).show(ctx, |ui| {
+                self.draw_contents(ui, nodes, elements);
+            });
+            self.open = open;
+        }
+    }
+
+    fn draw_contents(&mut self, ui: &mut egui::Ui, nodes: &HashMap<(i32, i32), Node>, elements: &[(u32, ElementType)]) {
+        ui.horizontal(|ui| {
+            if ui.button(if self.docked { "Undock" } else { "Dock" }).clicked() {
+                self.docked = !self.docked;
+            }
+            if ui.button(if self.frozen { "Run" } else { "Freeze" }).clicked() {
+                self.frozen = !self.frozen;
+            }
+            if ui.button("Clear").clicked() {
+                self.clear();
+            }
+            ui.add(egui::DragValue::new(&mut self.time_per_division).speed(1.0e-5).range(1.0e-7..=10.0).prefix("Time/div: ").suffix(" s"));
+            self.draw_probe_menu(ui, nodes, elements);
+        });
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.trigger, "Trigger on");
+            egui::ComboBox::from_id_source("trigger channel")
+                .selected_text(self.channels.get(self.trigger_channel).map_or("-".to_string(), |channel| channel.probe.label()))
+                .show_ui(ui, |ui| {
+                    for (index, channel) in self.channels.iter().enumerate() {
+                        ui.selectable_value(&mut self.trigger_channel, index, channel.probe.label());
+                    }
+                });
+            ui.add(egui::DragValue::new(&mut self.trigger_level).speed(0.01).prefix("Level: "));
+            ui.checkbox(&mut self.cursors, "Cursors");
+        });
+
+        let mut removed = None;
+        ui.horizontal_wrapped(|ui| {
+            for (index, channel) in self.channels.iter_mut().enumerate() {
+                ui.colored_label(channel.color, channel.probe.label());
+                ui.add(egui::DragValue::new(&mut channel.scale).speed(0.01).range(1.0e-9..=1.0e6).suffix(format!(" {}/div", channel.probe.unit())));
+                if ui.small_button("x").clicked() {
+                    removed = Some(index);
+                }
+                ui.separator();
+            }
+        });
+        if let Some(index) = removed {
+            self.channels.remove(index);
+            self.trigger_channel = self.trigger_channel.min(self.channels.len().saturating_sub(1));
+        }
+
+        let height = (ui.available_height() - if self.cursors { 20.0 * (self.channels.len() + 1) as f32 } else { 0.0 }).max(100.0);
+        let (rect, response) = ui.allocate_exact_size(Vec2::new(ui.available_width(), height), Sense::click_and_drag());
+        let start = self.screen_start();
+
+        // the cursor closest to the pointer follows it
+        if self.cursors && (response.dragged() || response.clicked()) {
+            if let Some(pointer) = response.interact_pointer_pos() {
+                let fraction = ((pointer.x - rect.left()) / rect.width()).clamp(0.0, 1.0);
+                if (fraction - self.cursor_a).abs() < (fraction - self.cursor_b).abs() {
+                    self.cursor_a = fraction;
+                } else {
+                    self.cursor_b = fraction;
+                }
+            }
+        }
+
+        self.draw_screen(ui, rect, start);
+
+        if self.cursors {
+            let width = self.time_per_division * HORIZONTAL_DIVISIONS as f64;
+            let time_a = start + self.cursor_a as f64 * width;
+            let time_b = start + self.cursor_b as f64 * width;
+            let delta = time_b - time_a;
+            ui.label(format!("A: {:.6} s   B: {:.6} s   Δt: {:.6} s   1/Δt: {:.3} Hz", time_a, time_b, delta, 1.0 / delta.abs()));
+            for channel in self.channels.iter() {
+                let a = channel.value_at(time_a);
+                let b = channel.value_at(time_b);
+                let format = |value: Option<f64>| value.map_or("-".to_string(), |value| format!("{:.4} {}", value, channel.probe.unit()));
+                let delta = a.zip(b).map(|(a, b)| b - a);
+                ui.colored_label(channel.color, format!("{}   A: {}   B: {}   Δ: {}", channel.probe.label(), format(a), format(b), format(delta)));
+            }
+        }
+    }
+
+    fn draw_probe_menu(&mut self, ui: &mut egui::Ui, nodes: &HashMap<(i32, i32), Node>, elements: &[(u32, ElementType)]) {
+        if let Some(probe) = probe_menu(ui, nodes, elements) {
+            let color = COLORS[self.channels.len() % COLORS.len()];
+            self.channels.push(Channel { probe, color, scale: 1.0, samples: VecDeque::new() });
+        }
+    }
+
+    // time at the left edge of the screen: the last rising edge through the trigger level that still
+    // fills the screen, or the newest samples if there is none
+    fn screen_start(&self) -> f64 {
+        let width = self.time_per_division * HORIZONTAL_DIVISIONS as f64;
+        let scrolling = (self.time - width).max(0.0);
+        if !self.trigger {
+            return scrolling;
+        }
+        let Some(channel) = self.channels.get(self.trigger_channel) else {
+            return scrolling;
+        };
+
+        let samples = &channel.samples;
+        (1..samples.len()).rev()
+            .map(|index| (samples[index - 1], samples[index]))
+            .filter(|((_, v1), (_, v2))| *v1 < self.trigger_level && *v2 >= self.trigger_level)
+            .map(|((t1, v1), (t2, v2))| t1 + (t2 - t1) * (self.trigger_level - v1) / (v2 - v1))
+            .find(|time| *time <= self.time - width)
+            .unwrap_or(scrolling)
+    }
+
+    fn draw_screen(&self, ui: &mut egui::Ui, rect: Rect, start: f64) {
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, Color32::BLACK);
+
+        let division = Vec2::new(rect.width() / HORIZONTAL_DIVISIONS as f32, rect.height() / VERTICAL_DIVISIONS as f32);
+        for i in 0..=HORIZONTAL_DIVISIONS {
+            let x = rect.left() + division.x * i as f32;
+            painter.line_segment([Pos2::new(x, rect.top()), Pos2::new(x, rect.bottom())], Stroke::new(0.5, Color32::from_gray(60)));
+        }
+        for i in 0..=VERTICAL_DIVISIONS {
+            let y = rect.top() + division.y * i as f32;
+            let gray = if i == VERTICAL_DIVISIONS / 2 { 110 } else { 60 };
+            painter.line_segment([Pos2::new(rect.left(), y), Pos2::new(rect.right(), y)], Stroke::new(0.5, Color32::from_gray(gray)));
+        }
+
+        let end = start + self.time_per_division * HORIZONTAL_DIVISIONS as f64;
+        let to_screen = |time: f64, value: f64, scale: f64| Pos2::new(
+            rect.left() + ((time - start) / self.time_per_division) as f32 * division.x,
+            (rect.center().y - (value / scale) as f32 * division.y).clamp(rect.top() - 1.0, rect.bottom() + 1.0),
+        );
+
+        for channel in self.channels.iter() {
+            // one sample on each side of the screen so the trace reaches the edges
+            let first = channel.samples.partition_point(|(time, _)| *time < start).saturating_sub(1);
+            let points: Vec<Pos2> = channel.samples.range(first..)
+                .take_while(|(time, _)| *time <= end + self.time_per_division)
+                .map(|(time, value)| to_screen(*time, *value, channel.scale))
+                .collect();
+            painter.add(Shape::line(points, Stroke::new(1.5, channel.color)));
+        }
+
+        if self.trigger {
+            if let Some(channel) = self.channels.get(self.trigger_channel) {
+                let y = to_screen(start, self.trigger_level, channel.scale).y;
+                painter.text(Pos2::new(rect.left() + 2.0, y), Align2::LEFT_CENTER, "T", FontId::monospace(12.0), channel.color);
+            }
+        }
+
+        if self.cursors {
+            for (fraction, name) in [(self.cursor_a, "A"), (self.cursor_b, "B")] {
+                let x = rect.left() + fraction * rect.width();
+                painter.line_segment([Pos2::new(x, rect.top()), Pos2::new(x, rect.bottom())], Stroke::new(1.0, Color32::GRAY));
+                painter.text(Pos2::new(x + 3.0, rect.top() + 2.0), Align2::LEFT_TOP, name, FontId::monospace(12.0), Color32::GRAY);
+            }
+        }
+
+        painter.text(rect.right_bottom() - Vec2::new(4.0, 4.0), Align2::RIGHT_BOTTOM, format!("{:.3e} s/div", self.time_per_division), FontId::monospace(11.0), Color32::GRAY);
+    }
+}
+
+// menu of the node voltages and element currents that can be probed, returns the one that was picked
+pub fn probe_menu(ui: &mut egui::Ui, nodes: &HashMap<(i32, i32), Node>, elements: &[(u32, ElementType)]) -> Option<Probe> {
+    let mut probe = None;
+    ui.menu_button("Add probe", |ui| {
+        ui.menu_button("Node voltage", |ui| {
+            let mut positions: Vec<_> = nodes.iter().collect();
+            positions.sort_by_key(|(_, node)| node.id);
+            egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                for (position, node) in positions {
+                    if ui.button(format!("Node {} at ({}, {})", node.id, position.0, position.1)).clicked() {
+                        probe = Some(Probe::Voltage(*position));
+                    }
+                }
+            });
+        });
+        ui.menu_button("Element current", |ui| {
+            egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                for (id, element_type) in elements {
+                    if ui.button(format!("{:?} {}", element_type, id)).clicked() {
+                        probe = Some(Probe::Current(*id));
+                    }
+                }
+            });
+        });
+        if probe.is_some() {
+            ui.close_menu();
+        }
+    });
+    probe
+}
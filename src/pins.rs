@@ -0,0 +1,105 @@
+// where the terminals of an element sit on the grid
+//
+// most elements stretch between their anchor and anchor + size, so their two pins follow the drag that
+// placed them. elements with more terminals have a fixed layout of pin offsets from their anchor, given
+// for an element that points right (+x, with +y down as on screen), that is turned and mirrored by the
+// orientation of the element. the pins are in the order of the nodes of the device, an op-amp set up
+// with a supply has its supply pins after the others
+
+use serde::{Deserialize, Serialize};
+use crate::ElementType;
+
+pub struct PinLayout {
+    pub pins: &'static [(i32, i32)],
+    // positive and negative supply, for elements that can have them
+    pub supply: &'static [(i32, i32)],
+    // length of the body along the direction the element points in, None if it stretches as far as it
+    // was dragged
+    pub length: Option<i32>,
+}
+
+pub fn pin_layout(element_type: ElementType) -> Option<PinLayout> {
+    match element_type {
+        ElementType::Ground => Some(PinLayout { pins: &[(0, 0)], supply: &[], length: None }),
+        // non-inverting input below the inverting input, output at the tip, positive supply above and
+        // negative supply below the body
+        ElementType::OpAmp => Some(PinLayout { pins: &[(0, 1), (0, -1), (3, 0)], supply: &[(1, -2), (1, 2)], length: Some(3) }),
+        // collector or drain above, base or gate at the anchor, emitter or source below
+        ElementType::Bjt | ElementType::Mosfet => Some(PinLayout { pins: &[(2, -1), (0, 0), (2, 1)], supply: &[], length: Some(2) }),
+        _ => None,
+    }
+}
+
+// quarter turns clockwise on screen from pointing right, after mirroring across the direction the
+// element points in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Orientation {
+    pub turns: u8,
+    pub mirrored: bool,
+}
+
+impl Orientation {
+    // the nearest of the four directions, ties go to the horizontal one
+    pub fn pointing((x, y): (i32, i32)) -> Self {
+        let turns = match (x.abs() >= y.abs(), x >= 0, y >= 0) {
+            (true, true, _) => 0,
+            (true, false, _) => 2,
+            (false, _, true) => 1,
+            (false, _, false) => 3,
+        };
+        Orientation { turns, mirrored: false }
+    }
+
+    // the orientation whose layout x and y axes end up along the given unit vectors
+    pub fn from_axes(x: (i32, i32), y: (i32, i32)) -> Self {
+        let Orientation { turns, .. } = Self::pointing(x);
+        Orientation { turns, mirrored: y != turn(x) }
+    }
+
+    pub fn apply(&self, (x, y): (i32, i32)) -> (i32, i32) {
+        let mut offset = if self.mirrored { (x, -y) } else { (x, y) };
+        for _ in 0..self.turns % 4 {
+            offset = turn(offset);
+        }
+        offset
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Orientation::default()
+    }
+}
+
+// a quarter turn clockwise on screen
+fn turn((x, y): (i32, i32)) -> (i32, i32) {
+    (-y, x)
+}
+
+// grid positions of the pins of an element, in the order of the nodes of its device
+pub fn pin_positions(element_type: ElementType, anchor: (i32, i32), size: (i32, i32), orientation: Orientation) -> Vec<(i32, i32)> {
+    match pin_layout(element_type) {
+        Some(layout) => layout.pins.iter()
+            .map(|pin| orientation.apply(*pin))
+            .map(|(x, y)| (anchor.0 + x, anchor.1 + y))
+            .collect(),
+        None => vec![anchor, (anchor.0 + size.0, anchor.1 + size.1)],
+    }
+}
+
+// grid positions of the supply pins of an element set up with a supply, positive first
+pub fn supply_positions(element_type: ElementType, anchor: (i32, i32), orientation: Orientation) -> Vec<(i32, i32)> {
+    pin_layout(element_type).map_or(Vec::new(), |layout| layout.supply.iter()
+        .map(|pin| orientation.apply(*pin))
+        .map(|(x, y)| (anchor.0 + x, anchor.1 + y))
+        .collect())
+}
+
+// size and orientation of an element dragged out from its anchor, elements with a pin layout point
+// along the axis nearest to the drag and keep the length of their layout if it has one
+pub fn placement(element_type: ElementType, drag: (i32, i32)) -> ((i32, i32), Orientation) {
+    let Some(layout) = pin_layout(element_type).filter(|_| drag != (0, 0)) else {
+        return (drag, Orientation::default());
+    };
+    let orientation = Orientation::pointing(drag);
+    let length = layout.length.unwrap_or(drag.0.abs().max(drag.1.abs()));
+    (orientation.apply((length, 0)), orientation)
+}
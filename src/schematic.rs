@@ -0,0 +1,332 @@
+// schematic files are json documents that look like this:
+//
+// {
+//   "version": 1,
+//   "view": { "offset": [0.0, 0.0], "grid_step": 35.0 },
+//   "elements": [
+//     { "id": 1, "position": [2, 3], "size": [0, 2], "type": "DCVoltageSource", "voltage": 5.0 },
+//     { "id": 2, "position": [2, 3], "size": [3, 0], "type": "Resistor", "resistance": 10.0 },
+//     { "id": 3, "position": [2, 5], "size": [0, 1], "type": "Ground" }
+//   ]
+// }
+//
+// - version: format version, files with a newer version than FORMAT_VERSION are rejected
+// - view: pan offset of the editor in pixels and the size of a grid cell in pixels
+// - elements: position and size are in grid cells, the nodes of an element are at position and
+//   position + size, elements whose nodes are at the same grid point are connected
+// - Ground, OpAmp, Bjt and Mosfet have their nodes at fixed offsets from position instead (see pins.rs),
+//   turned by an optional "orientation": { "turns": 1, "mirrored": false }, quarter turns clockwise from
+//   pointing right
+// - type is one of Wire, Resistor, Capacitor, Inductor, DCVoltageSource, CurrentSource, Switch, Ground,
+//   Diode, Vcvs, Vccs, Ccvs, Cccs, OpAmp, Bjt and Mosfet, followed by the parameters of that type:
+//   resistance (Ohm), capacitance (F), inductance (H), voltage (V), current (A), closed (bool),
+//   saturation_current (A), emission_coefficient and series_resistance (Ohm) of a diode (anode first),
+//   gain, transconductance (S) or transresistance (Ohm) of a controlled source,
+//   model (Ideal or SinglePole), gain, gain_bandwidth (Hz), input_resistance (Ohm), output_resistance (Ohm),
+//   optionally rails ([low, high] in V) and supply (true for an op-amp with supply pins that set its rails)
+//   of an op-amp,
+//   polarity (Npn or Pnp), beta, reverse_beta, saturation_current (A), thermal_voltage (V) and
+//   early_voltage (V, 0 for none) of a bjt (collector, base, emitter),
+//   channel (N or P), threshold_voltage (V), transconductance (A/V^2) and channel_length_modulation (1/V)
+//   of a mosfet (drain, gate, source)
+// - sources may have a "waveform" that replaces their constant value, an object with a "shape" of Sine,
+//   Pulse, PiecewiseLinear or Exponential and the parameters of that shape (see waveform.rs), for example
+//   { "shape": "PiecewiseLinear", "points": [[0.0, 0.0], [0.001, 5.0]] }
+// - sources may have an "ac" value for ac analysis, { "magnitude": 1.0, "phase": 0.0 } with the phase in degrees
+// - voltage controlled sources may have "sense": [[x, y], [x, y]], the grid points whose voltage
+//   (first against second) controls them, current controlled sources may have "control", the id of
+//   the voltage source or inductor whose current controls them
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::{Device, ElementType};
+use crate::ac::AcSource;
+use crate::circuit::Circuit;
+use crate::devices;
+use crate::devices::{BjtPolarity, MosfetChannel, OpAmpModel};
+use crate::pins::{pin_positions, supply_positions, Orientation};
+use crate::waveform::Waveform;
+
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Parameters {
+    Wire,
+    Resistor { resistance: f64 },
+    Capacitor { capacitance: f64 },
+    Inductor { inductance: f64 },
+    DCVoltageSource {
+        voltage: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")] waveform: Option<Waveform>,
+        #[serde(default, skip_serializing_if = "Option::is_none")] ac: Option<AcSource>,
+    },
+    CurrentSource {
+        current: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")] waveform: Option<Waveform>,
+        #[serde(default, skip_serializing_if = "Option::is_none")] ac: Option<AcSource>,
+    },
+    Switch { closed: bool },
+    Ground,
+    Diode { saturation_current: f64, emission_coefficient: f64, series_resistance: f64 },
+    Vcvs { gain: f64 },
+    Vccs { transconductance: f64 },
+    Ccvs { transresistance: f64, #[serde(default)] control: Option<u32> },
+    Cccs { gain: f64, #[serde(default)] control: Option<u32> },
+    OpAmp {
+        model: OpAmpModel,
+        gain: f64,
+        gain_bandwidth: f64,
+        input_resistance: f64,
+        output_resistance: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")] rails: Option<[f64; 2]>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")] supply: bool,
+    },
+    Bjt { polarity: BjtPolarity, beta: f64, reverse_beta: f64, saturation_current: f64, thermal_voltage: f64, early_voltage: f64 },
+    Mosfet { channel: MosfetChannel, threshold_voltage: f64, transconductance: f64, channel_length_modulation: f64 },
+}
+
+impl Parameters {
+    pub fn element_type(&self) -> ElementType {
+        match self {
+            Parameters::Wire => ElementType::Wire,
+            Parameters::Resistor { .. } => ElementType::Resistor,
+            Parameters::Capacitor { .. } => ElementType::Capacitor,
+            Parameters::Inductor { .. } => ElementType::Inductor,
+            Parameters::DCVoltageSource { .. } => ElementType::DCVoltageSource,
+            Parameters::CurrentSource { .. } => ElementType::CurrentSource,
+            Parameters::Switch { .. } => ElementType::Switch,
+            Parameters::Ground => ElementType::Ground,
+            Parameters::Diode { .. } => ElementType::Diode,
+            Parameters::Vcvs { .. } => ElementType::Vcvs,
+            Parameters::Vccs { .. } => ElementType::Vccs,
+            Parameters::Ccvs { .. } => ElementType::Ccvs,
+            Parameters::Cccs { .. } => ElementType::Cccs,
+            Parameters::OpAmp { .. } => ElementType::OpAmp,
+            Parameters::Bjt { .. } => ElementType::Bjt,
+            Parameters::Mosfet { .. } => ElementType::Mosfet,
+        }
+    }
+
+    // the numeric parameters by their name in the file, switches, wires and waveforms are left out
+    pub fn values_mut(&mut self) -> Vec<(&'static str, &mut f64)> {
+        match self {
+            Parameters::Wire | Parameters::Switch { .. } | Parameters::Ground => Vec::new(),
+            Parameters::Resistor { resistance } => vec![("resistance", resistance)],
+            Parameters::Capacitor { capacitance } => vec![("capacitance", capacitance)],
+            Parameters::Inductor { inductance } => vec![("inductance", inductance)],
+            Parameters::DCVoltageSource { voltage, .. } => vec![("voltage", voltage)],
+            Parameters::CurrentSource { current, .. } => vec![("current", current)],
+            Parameters::Diode { saturation_current, emission_coefficient, series_resistance } => vec![
+                ("saturation_current", saturation_current),
+                ("emission_coefficient", emission_coefficient),
+                ("series_resistance", series_resistance),
+            ],
+            Parameters::Vcvs { gain } | Parameters::Cccs { gain, .. } => vec![("gain", gain)],
+            Parameters::Vccs { transconductance } => vec![("transconductance", transconductance)],
+            Parameters::Ccvs { transresistance, .. } => vec![("transresistance", transresistance)],
+            Parameters::OpAmp { gain, gain_bandwidth, input_resistance, output_resistance, .. } => vec![
+                ("gain", gain),
+                ("gain_bandwidth", gain_bandwidth),
+                ("input_resistance", input_resistance),
+                ("output_resistance", output_resistance),
+            ],
+            Parameters::Bjt { beta, reverse_beta, saturation_current, thermal_voltage, early_voltage, .. } => vec![
+                ("beta", beta),
+                ("reverse_beta", reverse_beta),
+                ("saturation_current", saturation_current),
+                ("thermal_voltage", thermal_voltage),
+                ("early_voltage", early_voltage),
+            ],
+            Parameters::Mosfet { threshold_voltage, transconductance, channel_length_modulation, .. } => vec![
+                ("threshold_voltage", threshold_voltage),
+                ("transconductance", transconductance),
+                ("channel_length_modulation", channel_length_modulation),
+            ],
+        }
+    }
+
+    pub fn build(&self, nodes: Vec<u32>) -> Box<dyn Device> {
+        match self {
+            Parameters::Wire => Box::new(devices::Wire::new(nodes)),
+            Parameters::Resistor { resistance } => Box::new(devices::Resistor::new(nodes, *resistance)),
+            Parameters::Capacitor { capacitance } => Box::new(devices::Capacitor::new(nodes, *capacitance)),
+            Parameters::Inductor { inductance } => Box::new(devices::Inductor::new(nodes, *inductance)),
+            Parameters::DCVoltageSource { voltage, waveform, ac } => {
+                let mut source = devices::DCVoltageSource::new(nodes, *voltage);
+                source.waveform = waveform.clone();
+                source.ac = *ac;
+                Box::new(source)
+            }
+            Parameters::CurrentSource { current, waveform, ac } => {
+                let mut source = devices::CurrentSource::new(nodes, *current);
+                source.waveform = waveform.clone();
+                source.ac = *ac;
+                Box::new(source)
+            }
+            Parameters::Switch { closed } => Box::new(devices::Switch::new(nodes, *closed)),
+            Parameters::Ground => Box::new(devices::Ground::new(nodes)),
+            Parameters::Diode { saturation_current, emission_coefficient, series_resistance } => {
+                Box::new(devices::Diode::new(nodes, *saturation_current, *emission_coefficient, *series_resistance))
+            }
+            Parameters::Vcvs { gain } => Box::new(devices::Vcvs::new(nodes, *gain)),
+            Parameters::Vccs { transconductance } => Box::new(devices::Vccs::new(nodes, *transconductance)),
+            Parameters::Ccvs { transresistance, control } => Box::new(devices::Ccvs::new(nodes, *transresistance, *control)),
+            Parameters::Cccs { gain, control } => Box::new(devices::Cccs::new(nodes, *gain, *control)),
+            Parameters::OpAmp { .. } => {
+                let mut op_amp = devices::OpAmp::new(nodes, OpAmpModel::Ideal);
+                op_amp.set_parameters(self);
+                Box::new(op_amp)
+            }
+            Parameters::Bjt { polarity, .. } => {
+                let mut bjt = devices::Bjt::new(nodes, *polarity);
+                bjt.set_parameters(self);
+                Box::new(bjt)
+            }
+            Parameters::Mosfet { channel, .. } => {
+                let mut mosfet = devices::Mosfet::new(nodes, *channel);
+                mosfet.set_parameters(self);
+                Box::new(mosfet)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct View {
+    pub offset: [f32; 2],
+    pub grid_step: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElementRecord {
+    pub id: u32,
+    pub position: [i32; 2],
+    pub size: [i32; 2],
+    #[serde(flatten)]
+    pub parameters: Parameters,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sense: Option<[[i32; 2]; 2]>,
+    #[serde(default, skip_serializing_if = "Orientation::is_default")]
+    pub orientation: Orientation,
+}
+
+impl ElementRecord {
+    pub fn node_positions(&self) -> Vec<(i32, i32)> {
+        let anchor = (self.position[0], self.position[1]);
+        let size = (self.size[0], self.size[1]);
+        let mut positions = pin_positions(self.parameters.element_type(), anchor, size, self.orientation);
+        if let Parameters::OpAmp { supply: true, .. } = self.parameters {
+            positions.extend(supply_positions(ElementType::OpAmp, anchor, self.orientation));
+        }
+        positions.extend(self.sense.iter().flatten().map(|[x, y]| (*x, *y)));
+        positions
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Schematic {
+    pub version: u32,
+    pub view: View,
+    pub elements: Vec<ElementRecord>,
+}
+
+#[derive(Debug)]
+pub enum SchematicError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    MissingVersion,
+    NewerVersion { found: u32, supported: u32 },
+    DuplicateId(u32),
+    EmptyElement(u32),
+}
+
+impl fmt::Display for SchematicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchematicError::Io(error) => write!(f, "Could not access the file: {}", error),
+            SchematicError::Parse(error) => write!(f, "The file is not a valid schematic: {}", error),
+            SchematicError::MissingVersion => write!(f, "The file has no format version"),
+            SchematicError::NewerVersion { found, supported } => write!(f, "The file has format version {}, but only versions up to {} are supported", found, supported),
+            SchematicError::DuplicateId(id) => write!(f, "Element id {} is used more than once", id),
+            SchematicError::EmptyElement(id) => write!(f, "Element {} has no size", id),
+        }
+    }
+}
+
+impl std::error::Error for SchematicError {}
+
+impl From<std::io::Error> for SchematicError {
+    fn from(error: std::io::Error) -> Self {
+        SchematicError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for SchematicError {
+    fn from(error: serde_json::Error) -> Self {
+        SchematicError::Parse(error)
+    }
+}
+
+impl Schematic {
+    pub fn new(view: View) -> Self {
+        Schematic { version: FORMAT_VERSION, view, elements: Vec::new() }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, SchematicError> {
+        // the version is checked first, a newer file would most likely fail to parse with a less helpful error
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        let version = value.get("version").and_then(|version| version.as_u64()).ok_or(SchematicError::MissingVersion)?;
+        if version > FORMAT_VERSION as u64 {
+            return Err(SchematicError::NewerVersion { found: version as u32, supported: FORMAT_VERSION });
+        }
+
+        let schematic: Schematic = serde_json::from_value(value)?;
+        let mut ids = BTreeSet::new();
+        for element in schematic.elements.iter() {
+            if !ids.insert(element.id) {
+                return Err(SchematicError::DuplicateId(element.id));
+            }
+            if element.size == [0, 0] && element.parameters != Parameters::Ground {
+                return Err(SchematicError::EmptyElement(element.id));
+            }
+        }
+        Ok(schematic)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SchematicError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SchematicError> {
+        std::fs::write(path, self.to_json())?;
+        Ok(())
+    }
+
+    // node id of every grid point an element touches, numbered from 1 in the order of the elements
+    pub fn node_ids(&self) -> HashMap<(i32, i32), u32> {
+        let mut node_ids: HashMap<(i32, i32), u32> = HashMap::new();
+        for position in self.elements.iter().flat_map(|element| element.node_positions()) {
+            let next_id = node_ids.len() as u32 + 1;
+            node_ids.entry(position).or_insert(next_id);
+        }
+        node_ids
+    }
+
+    // connects the elements through the grid points they share, node ids are numbered as in node_ids
+    pub fn to_circuit(&self) -> Circuit {
+        let node_ids = self.node_ids();
+        let mut circuit = Circuit::new();
+        for element in self.elements.iter() {
+            let nodes = element.node_positions().iter().map(|position| node_ids[position]).collect();
+            circuit.insert(element.id, element.parameters.build(nodes));
+        }
+        circuit
+    }
+}
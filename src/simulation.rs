@@ -0,0 +1,91 @@
+use eframe::egui;
+use rusty_circuit::circuit::StepInfo;
+
+pub struct Simulation {
+    pub running: bool,
+    pub time: f64,
+    pub timestep: f64,
+    pub steps_per_frame: u32,
+    reset_requested: bool,
+}
+
+impl Simulation {
+    pub fn new() -> Self {
+        Self {
+            running: false,
+            time: 0.0,
+            timestep: 1.0e-4,
+            steps_per_frame: 1,
+            reset_requested: false,
+        }
+    }
+
+    // the step is shortened so it ends on the next breakpoint of the sources instead of jumping over it
+    pub fn next_step(&self, breakpoint: Option<f64>) -> StepInfo {
+        let timestep = breakpoint.map_or(self.timestep, |breakpoint| self.timestep.min(breakpoint - self.time));
+        StepInfo {
+            timestep,
+            time: self.time + timestep,
+        }
+    }
+
+    pub fn advance(&mut self, step: &StepInfo) {
+        self.time = step.time;
+    }
+
+    // returns true once after the reset button was pressed
+    pub fn take_reset(&mut self) -> bool {
+        let reset = self.reset_requested;
+        self.reset_requested = false;
+        if reset {
+            self.time = 0.0;
+        }
+        reset
+    }
+
+    pub fn draw_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Simulation").show(ctx, |ui| {
+            ui.label(format!("Time: {:.6} s", self.time));
+            ui.horizontal(|ui| {
+                if ui.button(if self.running { "Pause" } else { "Run" }).clicked() {
+                    self.running = !self.running;
+                }
+                if ui.button("Reset").clicked() {
+                    self.reset_requested = true;
+                }
+            });
+            ui.add(egui::Slider::new(&mut self.timestep, 1.0e-7..=1.0e-1).logarithmic(true).text("Timestep (s)"));
+            ui.add(egui::Slider::new(&mut self.steps_per_frame, 1..=1000).logarithmic(true).text("Steps per frame"));
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_ends_on_the_breakpoint() {
+        let mut simulation = Simulation::new();
+        simulation.timestep = 1.0e-4;
+        // the time is compared exactly, the breakpoint has to be hit and not just come close
+        for (time, breakpoint) in [(0.0, 2.5e-5), (1.0e-4, 1.3e-4), (1.0e-9, 5.0e-5), (0.1, 0.1 + 3.0e-5), (2.0e-4, 2.9e-4)] {
+            simulation.time = time;
+            let step = simulation.next_step(Some(breakpoint));
+            assert_eq!(step.time, breakpoint);
+            assert!((step.timestep - (breakpoint - time)).abs() < 1.0e-15);
+        }
+    }
+
+    #[test]
+    fn far_breakpoints_keep_the_timestep() {
+        let mut simulation = Simulation::new();
+        simulation.timestep = 1.0e-4;
+        simulation.time = 2.0e-4;
+        for breakpoint in [None, Some(3.5e-4), Some(1.0)] {
+            let step = simulation.next_step(breakpoint);
+            assert_eq!(step.timestep, 1.0e-4);
+            assert_eq!(step.time, 2.0e-4 + 1.0e-4);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::{Index, IndexMut};
+use nalgebra::{Complex, ComplexField, DMatrix, DVector};
+
+// pivots smaller than this (relative to the largest entry) are treated as zero
+const PIVOT_TOLERANCE: f64 = 1.0e-12;
+// a pivot may be up to this much smaller than the largest candidate if its row is sparser
+const PIVOT_THRESHOLD: f64 = 0.1;
+
+// square matrix that only stores the non-zero entries of every row
+// the entries are real for dc and transient solves and complex for ac analysis
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseMatrix<T = f64> {
+    size: usize,
+    rows: Vec<BTreeMap<usize, T>>,
+    // what indexing returns for an entry that is not stored
+    zero: T,
+}
+
+impl SparseMatrix<f64> {
+    pub fn to_complex(&self) -> SparseMatrix<Complex<f64>> {
+        let rows = self.rows.iter()
+            .map(|entries| entries.iter().map(|(column, value)| (*column, Complex::from(*value))).collect())
+            .collect();
+        SparseMatrix { size: self.size, rows, zero: Complex::default() }
+    }
+}
+
+impl<T: ComplexField<RealField = f64> + Copy> SparseMatrix<T> {
+    pub fn new(size: usize) -> Self {
+        Self { size, rows: vec![BTreeMap::new(); size], zero: T::zero() }
+    }
+
+    // matrix without the given row and column
+    pub fn minor(&self, index: usize) -> SparseMatrix<T> {
+        let shift = |i: usize| if i > index { i - 1 } else { i };
+        let rows = self.rows.iter().enumerate()
+            .filter(|(row, _)| *row != index)
+            .map(|(_, entries)| {
+                entries.iter()
+                    .filter(|(column, _)| **column != index)
+                    .map(|(column, value)| (shift(*column), *value))
+                    .collect()
+            })
+            .collect();
+        SparseMatrix { size: self.size - 1, rows, zero: self.zero }
+    }
+
+    pub fn to_dense(&self) -> DMatrix<T> {
+        let mut matrix = DMatrix::zeros(self.size, self.size);
+        for (row, entries) in self.rows.iter().enumerate() {
+            for (column, value) in entries {
+                matrix[(row, *column)] = *value;
+            }
+        }
+        matrix
+    }
+
+    // gaussian elimination with threshold partial pivoting, columns are eliminated in order
+    // returns the column that has no usable pivot if the matrix is singular
+    pub fn lu(&self) -> Result<LuDecomposition<T>, usize> {
+        let mut rows = self.rows.clone();
+        let mut columns = vec![BTreeSet::new(); self.size];
+        for (row, entries) in rows.iter().enumerate() {
+            for column in entries.keys() {
+                columns[*column].insert(row);
+            }
+        }
+
+        let scale = rows.iter()
+            .flat_map(|entries| entries.values())
+            .fold(0.0f64, |max, value| max.max(value.modulus()));
+
+        let mut pivoted = vec![false; self.size];
+        let mut pivots = Vec::with_capacity(self.size);
+        let mut eliminations = Vec::new();
+
+        for k in 0..self.size {
+            let candidates: Vec<usize> = columns[k].iter().copied().filter(|row| !pivoted[*row]).collect();
+            let largest = candidates.iter()
+                .map(|row| rows[*row].get(&k).map_or(0.0, |value| value.modulus()))
+                .fold(0.0, f64::max);
+
+            if largest <= PIVOT_TOLERANCE * scale || largest == 0.0 {
+                return Err(k);
+            }
+
+            let pivot = *candidates.iter()
+                .filter(|row| rows[**row].get(&k).map_or(0.0, |value| value.modulus()) >= PIVOT_THRESHOLD * largest)
+                .min_by_key(|row| rows[**row].len())
+                .unwrap();
+            pivoted[pivot] = true;
+            pivots.push(pivot);
+
+            let pivot_value = rows[pivot][&k];
+            let pivot_row: Vec<(usize, T)> = rows[pivot].range(k + 1..).map(|(column, value)| (*column, *value)).collect();
+
+            for row in candidates {
+                if row == pivot {
+                    continue;
+                }
+                let factor = rows[row].remove(&k).unwrap_or(self.zero) / pivot_value;
+                if factor.is_zero() {
+                    continue;
+                }
+                eliminations.push((row, pivot, factor));
+                for (column, value) in pivot_row.iter() {
+                    let entry = rows[row].entry(*column).or_insert_with(|| {
+                        columns[*column].insert(row);
+                        self.zero
+                    });
+                    *entry -= factor * *value;
+                }
+            }
+        }
+
+        Ok(LuDecomposition { size: self.size, pivots, eliminations, upper: rows })
+    }
+}
+
+impl<T> Index<(usize, usize)> for SparseMatrix<T> {
+    type Output = T;
+
+    fn index(&self, (row, column): (usize, usize)) -> &T {
+        self.rows[row].get(&column).unwrap_or(&self.zero)
+    }
+}
+
+impl<T: Copy> IndexMut<(usize, usize)> for SparseMatrix<T> {
+    fn index_mut(&mut self, (row, column): (usize, usize)) -> &mut T {
+        assert!(column < self.size, "column {} out of bounds", column);
+        self.rows[row].entry(column).or_insert(self.zero)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LuDecomposition<T = f64> {
+    size: usize,
+    // row that was used as the pivot of every column
+    pivots: Vec<usize>,
+    // (row, pivot row, factor) in the order they were applied
+    eliminations: Vec<(usize, usize, T)>,
+    upper: Vec<BTreeMap<usize, T>>,
+}
+
+impl<T: ComplexField<RealField = f64> + Copy> LuDecomposition<T> {
+    pub fn solve(&self, vector: &DVector<T>) -> DVector<T> {
+        let mut vector = vector.clone();
+        for (row, pivot, factor) in self.eliminations.iter() {
+            let eliminated = *factor * vector[*pivot];
+            vector[*row] -= eliminated;
+        }
+
+        let mut solution = DVector::zeros(self.size);
+        for k in (0..self.size).rev() {
+            let pivot = self.pivots[k];
+            let mut sum = vector[pivot];
+            for (column, value) in self.upper[pivot].range(k + 1..) {
+                sum -= *value * solution[*column];
+            }
+            solution[k] = sum / self.upper[pivot][&k];
+        }
+        solution
+    }
+}
@@ -0,0 +1,707 @@
+// conversion between circuits and spice netlists
+//
+// the importer reads a practical subset: R, C, L, V, I, D, Q and M cards, linear E, G, H and F sources,
+// SIN, PULSE, PWL and EXP waveforms and AC values of V and I sources, .model for diodes (IS, N and RS),
+// bjts (NPN and PNP with IS, BF, BR, NF and VAF) and level 1 mosfets (NMOS and PMOS with VTO, KP and
+// LAMBDA), .op, .tran and .ac, comments (* lines, ; and $ at the end of a line), + continuation lines
+// and values with si suffixes (4k7, 10u, 1meg)
+//
+// spice measures the current of a V, E or H element from its positive node through the source, ours is
+// the other way round, so the gains of H and F elements controlled by one of them change sign
+//
+// the exported netlist is written from the simplified circuit: wires and closed switches are merged into
+// their nodes, so every spice node is a net of the schematic, named after the id of the node that
+// was kept (N<id>), the ground node is 0
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fmt::Write;
+use std::path::Path;
+use crate::{Device, ElementType};
+use crate::devices::{self, BjtPolarity, MosfetChannel, OpAmpModel};
+use crate::devices::diode::THERMAL_VOLTAGE;
+use crate::pins::{pin_positions, placement, Orientation};
+use crate::ac::{AcSource, Sweep, SweepScale};
+use crate::circuit::Circuit;
+use crate::schematic::{ElementRecord, Parameters, Schematic, View};
+use crate::waveform::Waveform;
+
+// plain numbers for the usual range, exponent notation for everything else
+fn format_value(value: f64) -> String {
+    if value == 0.0 || (1.0e-3..1.0e6).contains(&value.abs()) {
+        format!("{}", value)
+    } else {
+        format!("{:e}", value)
+    }
+}
+
+// spice name of an element that can control a current controlled source, and the sign of its current
+// against ours
+fn control_name(circuit: &Circuit, control: Option<u32>) -> Option<(String, f64)> {
+    let control = control?;
+    match circuit.element(control)?.get_type() {
+        ElementType::DCVoltageSource => Some((format!("V{}", control), -1.0)),
+        ElementType::Vcvs => Some((format!("E{}", control), -1.0)),
+        ElementType::Ccvs => Some((format!("H{}", control), -1.0)),
+        ElementType::Inductor => Some((format!("L{}", control), 1.0)),
+        _ => None,
+    }
+}
+
+// the dc value is the value at time 0, so an operating point in spice matches ours
+fn format_source(value: f64, waveform: &Option<Waveform>, ac: &Option<AcSource>) -> String {
+    let ac = ac.map_or(String::new(), |ac| format!(" AC {} {}", format_value(ac.magnitude), format_value(ac.phase)));
+    let Some(waveform) = waveform else {
+        return format!("DC {}{}", format_value(value), ac);
+    };
+    let (name, parameters) = match waveform {
+        Waveform::Sine { offset, amplitude, frequency, delay, damping, phase } => {
+            ("SIN", vec![*offset, *amplitude, *frequency, *delay, *damping, *phase])
+        }
+        Waveform::Pulse { initial, pulsed, delay, rise, fall, width, period } => {
+            ("PULSE", vec![*initial, *pulsed, *delay, *rise, *fall, *width, *period])
+        }
+        Waveform::PiecewiseLinear { points } => ("PWL", points.iter().flatten().copied().collect()),
+        Waveform::Exponential { initial, pulsed, rise_delay, rise_time_constant, fall_delay, fall_time_constant } => {
+            ("EXP", vec![*initial, *pulsed, *rise_delay, *rise_time_constant, *fall_delay, *fall_time_constant])
+        }
+    };
+    let parameters: Vec<String> = parameters.into_iter().map(format_value).collect();
+    format!("DC {}{} {}({})", format_value(waveform.value(0.0)), ac, name, parameters.join(" "))
+}
+
+fn node_name(node: u32) -> String {
+    if node == 0 {
+        "0".to_string()
+    } else {
+        format!("N{}", node)
+    }
+}
+
+pub fn export(circuit: &Circuit, title: &str) -> String {
+    let simplified = circuit.simplify(&mut String::new());
+    let mut netlist = String::new();
+    // the first line of a deck is always the title
+    writeln!(netlist, "{}", title).unwrap();
+
+    for (net, merged) in simplified.nets.merged() {
+        let merged: Vec<String> = merged.iter().map(|node| node.to_string()).collect();
+        writeln!(netlist, "* {} contains nodes {}", node_name(net), merged.join(", ")).unwrap();
+    }
+
+    for (id, device) in simplified.elements.iter() {
+        let nodes: Vec<String> = device.get_nodes().into_iter().map(node_name).collect();
+        // controlled sources without sense nodes sense ground against itself
+        let sense = |index: usize| nodes.get(index).map_or("0", |node| node.as_str());
+        match device.parameters() {
+            Parameters::Resistor { resistance } => writeln!(netlist, "R{} {} {} {}", id, nodes[0], nodes[1], format_value(resistance)),
+            Parameters::Capacitor { capacitance } => writeln!(netlist, "C{} {} {} {}", id, nodes[0], nodes[1], format_value(capacitance)),
+            Parameters::Inductor { inductance } => writeln!(netlist, "L{} {} {} {}", id, nodes[0], nodes[1], format_value(inductance)),
+            // the second node is the positive terminal
+            Parameters::DCVoltageSource { voltage, waveform, ac } => writeln!(netlist, "V{} {} {} {}", id, nodes[1], nodes[0], format_source(voltage, &waveform, &ac)),
+            // spice current sources push their current from the first node through the source to the second
+            Parameters::CurrentSource { current, waveform, ac } => writeln!(netlist, "I{} {} {} {}", id, nodes[0], nodes[1], format_source(current, &waveform, &ac)),
+            // every diode gets its own model
+            Parameters::Diode { saturation_current, emission_coefficient, series_resistance } => writeln!(
+                netlist, "D{} {} {} D{}\n.model D{} D(IS={} N={} RS={})", id, nodes[0], nodes[1], id, id,
+                format_value(saturation_current), format_value(emission_coefficient), format_value(series_resistance),
+            ),
+            Parameters::Vcvs { gain } => writeln!(netlist, "E{} {} {} {} {} {}", id, nodes[1], nodes[0], sense(2), sense(3), format_value(gain)),
+            Parameters::Vccs { transconductance } => {
+                writeln!(netlist, "G{} {} {} {} {} {}", id, nodes[0], nodes[1], sense(2), sense(3), format_value(transconductance))
+            }
+            Parameters::Ccvs { transresistance, control } => match control_name(circuit, control) {
+                Some((name, sign)) => writeln!(netlist, "H{} {} {} {} {}", id, nodes[1], nodes[0], name, format_value(sign * transresistance)),
+                None => writeln!(netlist, "* source {} has no controlling voltage source and is left out", id),
+            },
+            Parameters::Cccs { gain, control } => match control_name(circuit, control) {
+                Some((name, sign)) => writeln!(netlist, "F{} {} {} {} {}", id, nodes[0], nodes[1], name, format_value(sign * gain)),
+                None => writeln!(netlist, "* source {} has no controlling voltage source and is left out", id),
+            },
+            // spice has no op-amp element, the single pole model is built from its parts with two nodes of
+            // its own, the pole and the output before the output resistance
+            Parameters::OpAmp { model: OpAmpModel::Ideal, gain, .. } => writeln!(
+                netlist, "* op-amp {} is ideal, the gain of its vcvs only has to be large\nE{} {} 0 {} {} {}",
+                id, id, nodes[2], nodes[0], nodes[1], format_value(gain.max(1.0e6)),
+            ),
+            // the pole is a resistor of the open loop gain in ohm driven by 1 S, so the capacitor sets the
+            // frequency where the gain falls to 1
+            Parameters::OpAmp { model: OpAmpModel::SinglePole, gain, gain_bandwidth, input_resistance, output_resistance, .. } => writeln!(
+                netlist, "* op-amp {} is not limited to its rails\nR{}I {} {} {}\nG{} 0 P{} {} {} 1\nR{}P P{} 0 {}\nC{}P P{} 0 {}\nE{} O{} 0 P{} 0 1\nR{}O O{} {} {}",
+                id, id, nodes[0], nodes[1], format_value(input_resistance),
+                id, id, nodes[0], nodes[1],
+                id, id, format_value(gain),
+                id, id, format_value(1.0 / (std::f64::consts::TAU * gain_bandwidth)),
+                id, id, id,
+                id, id, nodes[2], format_value(output_resistance),
+            ),
+            // every transistor gets its own model, a thermal voltage other than the one of 300 K becomes
+            // the emission coefficient of both junctions
+            Parameters::Bjt { polarity, beta, reverse_beta, saturation_current, thermal_voltage, early_voltage } => {
+                let emission_coefficient = format_value(thermal_voltage / THERMAL_VOLTAGE);
+                writeln!(
+                    netlist, "Q{} {} {} {} Q{}\n.model Q{} {}(IS={} BF={} BR={} NF={} NR={} VAF={})", id, nodes[0], nodes[1], nodes[2], id, id,
+                    if polarity == BjtPolarity::Npn { "NPN" } else { "PNP" }, format_value(saturation_current), format_value(beta),
+                    format_value(reverse_beta), emission_coefficient, emission_coefficient, format_value(early_voltage),
+                )
+            }
+            // the body is tied to the source, the threshold of a p-channel model is negative in spice
+            Parameters::Mosfet { channel, threshold_voltage, transconductance, channel_length_modulation } => {
+                let (name, threshold) = match channel {
+                    MosfetChannel::N => ("NMOS", threshold_voltage),
+                    MosfetChannel::P => ("PMOS", -threshold_voltage),
+                };
+                writeln!(
+                    netlist, "M{} {} {} {} {} M{}\n.model M{} {}(LEVEL=1 VTO={} KP={} LAMBDA={})", id, nodes[0], nodes[1], nodes[2], nodes[2], id, id,
+                    name, format_value(threshold), format_value(transconductance), format_value(channel_length_modulation),
+                )
+            }
+            Parameters::Switch { closed: false } => writeln!(netlist, "* switch {} is open and left out", id),
+            // closed switches and wires were merged into their nodes, ground is node 0
+            Parameters::Switch { closed: true } | Parameters::Wire | Parameters::Ground => Ok(()),
+        }.unwrap();
+    }
+
+    writeln!(netlist, ".op").unwrap();
+    writeln!(netlist, ".end").unwrap();
+    netlist
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Analysis {
+    OperatingPoint,
+    Transient { step: f64, stop: f64 },
+    Ac(Sweep),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetlistElement {
+    pub name: String,
+    // in the order of our devices: the positive terminal of a voltage source is the second node, a
+    // transistor has collector, base and emitter or drain, gate and source
+    pub nodes: Vec<String>,
+    pub parameters: Parameters,
+    // nodes whose voltage controls an E or G element, positive node first
+    pub sense: Option<[String; 2]>,
+    // name of the element whose current controls an H or F element
+    pub control: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Netlist {
+    pub title: String,
+    pub elements: Vec<NetlistElement>,
+    pub analyses: Vec<Analysis>,
+}
+
+#[derive(Debug)]
+pub enum SpiceError {
+    Io(std::io::Error),
+    UnsupportedElement { line: usize, name: String },
+    MissingField { line: usize, name: String },
+    InvalidValue { line: usize, value: String },
+    UnknownModel { line: usize, kind: &'static str, model: String },
+    UnknownControl { line: usize, name: String },
+    ShortedElement { line: usize, name: String },
+}
+
+impl fmt::Display for SpiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpiceError::Io(error) => write!(f, "Could not access the file: {}", error),
+            SpiceError::UnsupportedElement { line, name } => write!(f, "Line {}: element {} is not supported", line, name),
+            SpiceError::MissingField { line, name } => write!(f, "Line {}: {} is missing nodes or a value", line, name),
+            SpiceError::InvalidValue { line, value } => write!(f, "Line {}: {} is not a valid value", line, value),
+            SpiceError::UnknownModel { line, kind, model } => write!(f, "Line {}: there is no {} model {}", line, kind, model),
+            SpiceError::UnknownControl { line, name } => write!(f, "Line {}: there is no element {} to control the source", line, name),
+            SpiceError::ShortedElement { line, name } => write!(f, "Line {}: both nodes of {} are on the same net", line, name),
+        }
+    }
+}
+
+impl std::error::Error for SpiceError {}
+
+impl From<std::io::Error> for SpiceError {
+    fn from(error: std::io::Error) -> Self {
+        SpiceError::Io(error)
+    }
+}
+
+// numbers with an optional si suffix, 4k7 is 4.7k and trailing units like the F in 10uF are ignored
+pub fn parse_value(text: &str) -> Option<f64> {
+    let text = text.to_ascii_lowercase();
+    let bytes = text.as_bytes();
+    let is_digit = |index: usize| bytes.get(index).is_some_and(|byte| byte.is_ascii_digit());
+
+    let mut end = 0;
+    let mut plain = true;
+    while end < bytes.len() {
+        match bytes[end] {
+            b'0'..=b'9' => end += 1,
+            b'.' => {
+                plain = false;
+                end += 1;
+            }
+            b'+' | b'-' if end == 0 => end += 1,
+            // an e only starts an exponent if a number follows it
+            b'e' if is_digit(end + 1) || (matches!(bytes.get(end + 1), Some(b'+' | b'-')) && is_digit(end + 2)) => {
+                plain = false;
+                end += 2;
+            }
+            _ => break,
+        }
+    }
+    let mut value: f64 = text[..end].parse().ok()?;
+
+    let rest = &text[end..];
+    let (exponent, suffix_length): (i32, usize) = if rest.starts_with("meg") {
+        (6, 3)
+    } else if rest.starts_with("mil") {
+        return Some(value * 25.4e-6);
+    } else {
+        match rest.chars().next() {
+            Some('t') => (12, 1),
+            Some('g') => (9, 1),
+            Some('k') => (3, 1),
+            Some('m') => (-3, 1),
+            Some('u') => (-6, 1),
+            Some('n') => (-9, 1),
+            Some('p') => (-12, 1),
+            Some('f') => (-15, 1),
+            _ => (0, 0),
+        }
+    };
+
+    // digits after the suffix are the decimals: 4k7
+    let decimals: String = rest[suffix_length..].chars().take_while(|c| c.is_ascii_digit()).collect();
+    if suffix_length > 0 && plain && !decimals.is_empty() {
+        value += format!("0.{}", decimals).parse::<f64>().ok()?.copysign(value);
+    }
+    // dividing keeps 10u at exactly the same value as 1e-5
+    let scale = 10.0f64.powi(exponent.abs());
+    Some(if exponent < 0 { value / scale } else { value * scale })
+}
+
+// joins continuation lines and drops comments, returns the statements with the line they start on
+fn statements(text: &str) -> Vec<(usize, String)> {
+    let mut statements: Vec<(usize, String)> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.split([';', '$']).next().unwrap().trim();
+        if let Some(continuation) = line.strip_prefix('+') {
+            if let Some((_, statement)) = statements.last_mut() {
+                statement.push(' ');
+                statement.push_str(continuation.trim());
+            }
+        } else if !line.is_empty() && !line.starts_with('*') {
+            statements.push((index + 1, line.to_string()));
+        }
+    }
+    statements
+}
+
+pub fn parse(text: &str) -> Result<Netlist, SpiceError> {
+    let mut netlist = Netlist::default();
+    // the first line is the title, even if it looks like an element
+    let mut lines = text.splitn(2, '\n');
+    netlist.title = lines.next().unwrap_or("").trim().to_string();
+    let body = lines.next().unwrap_or("");
+    // models can be defined after the elements that use them
+    let mut models: HashMap<String, Parameters> = HashMap::new();
+    // elements that take their parameters from a model: index, line, the names the model can have and its type
+    let mut model_uses: Vec<(usize, usize, Vec<String>, ElementType)> = Vec::new();
+    let mut controlled: Vec<(usize, String)> = Vec::new();
+
+    for (line, statement) in statements(body) {
+        // the title line is not part of the body
+        let line = line + 1;
+        let fields: Vec<&str> = statement.split_whitespace().collect();
+        let name = fields[0].to_string();
+        let value_at = |index: usize| -> Result<f64, SpiceError> {
+            let field = fields.get(index).ok_or(SpiceError::MissingField { line, name: name.clone() })?;
+            parse_value(field).ok_or(SpiceError::InvalidValue { line, value: field.to_string() })
+        };
+
+        let lower = name.to_ascii_lowercase();
+        if lower.starts_with('.') {
+            match lower.as_str() {
+                ".op" => netlist.analyses.push(Analysis::OperatingPoint),
+                ".tran" => netlist.analyses.push(Analysis::Transient { step: value_at(1)?, stop: value_at(2)? }),
+                // .ac dec|oct|lin points start stop
+                ".ac" => {
+                    let scale = match fields.get(1).map(|field| field.to_ascii_lowercase()) {
+                        Some(scale) if scale == "dec" => SweepScale::Decade,
+                        Some(scale) if scale == "oct" => SweepScale::Octave,
+                        Some(scale) if scale == "lin" => SweepScale::Linear,
+                        Some(_) => return Err(SpiceError::InvalidValue { line, value: fields[1].to_string() }),
+                        None => return Err(SpiceError::MissingField { line, name }),
+                    };
+                    let points = value_at(2)?;
+                    if points < 1.0 {
+                        return Err(SpiceError::InvalidValue { line, value: fields[2].to_string() });
+                    }
+                    netlist.analyses.push(Analysis::Ac(Sweep { scale, points: points as u32, start: value_at(3)?, stop: value_at(4)? }));
+                }
+                ".model" => {
+                    let (name, parameters) = parse_model(&statement, line)?;
+                    if let Some(parameters) = parameters {
+                        models.insert(name, parameters);
+                    }
+                }
+                ".end" => break,
+                // other control lines (.options, .print, ...) do not change the circuit
+                _ => {}
+            }
+            continue;
+        }
+
+        if fields.len() < 3 {
+            return Err(SpiceError::MissingField { line, name });
+        }
+        let (first, second) = (fields[1].to_string(), fields[2].to_string());
+
+        let mut sense = None;
+        let mut control = None;
+        // h and f gains are given for the current in the spice direction
+        let control_sign = |name: &str| if name.to_ascii_lowercase().starts_with('l') { 1.0 } else { -1.0 };
+        let field_at = |index: usize| -> Result<String, SpiceError> {
+            fields.get(index).map(|field| field.to_string()).ok_or(SpiceError::MissingField { line, name: name.clone() })
+        };
+
+        let (nodes, parameters) = match lower.chars().next().unwrap() {
+            'r' => (vec![first, second], Parameters::Resistor { resistance: value_at(3)? }),
+            'c' => (vec![first, second], Parameters::Capacitor { capacitance: value_at(3)? }),
+            'l' => (vec![first, second], Parameters::Inductor { inductance: value_at(3)? }),
+            'v' => {
+                let (voltage, waveform, ac) = parse_source(&fields[3..], line, &name)?;
+                (vec![second, first], Parameters::DCVoltageSource { voltage, waveform, ac })
+            }
+            'i' => {
+                let (current, waveform, ac) = parse_source(&fields[3..], line, &name)?;
+                (vec![first, second], Parameters::CurrentSource { current, waveform, ac })
+            }
+            'd' => {
+                let model = field_at(3)?.to_ascii_lowercase();
+                model_uses.push((netlist.elements.len(), line, vec![model], ElementType::Diode));
+                (vec![first, second], Parameters::Ground)
+            }
+            // the substrate node is optional, so the model is the first of the two fields after the nodes
+            // that names one
+            'q' => {
+                let models = fields.iter().skip(4).take(2).map(|field| field.to_ascii_lowercase()).collect();
+                model_uses.push((netlist.elements.len(), line, models, ElementType::Bjt));
+                (vec![first, second, field_at(3)?], Parameters::Ground)
+            }
+            // the body node is left out, it is taken to be tied to the source
+            'm' => {
+                let model = field_at(5)?.to_ascii_lowercase();
+                model_uses.push((netlist.elements.len(), line, vec![model], ElementType::Mosfet));
+                (vec![first, second, field_at(3)?], Parameters::Ground)
+            }
+            'e' => {
+                sense = Some([field_at(3)?, field_at(4)?]);
+                (vec![second, first], Parameters::Vcvs { gain: value_at(5)? })
+            }
+            'g' => {
+                sense = Some([field_at(3)?, field_at(4)?]);
+                (vec![first, second], Parameters::Vccs { transconductance: value_at(5)? })
+            }
+            'h' => {
+                let name = field_at(3)?;
+                let transresistance = control_sign(&name) * value_at(4)?;
+                controlled.push((line, name.clone()));
+                control = Some(name);
+                (vec![second, first], Parameters::Ccvs { transresistance, control: None })
+            }
+            'f' => {
+                let name = field_at(3)?;
+                let gain = control_sign(&name) * value_at(4)?;
+                controlled.push((line, name.clone()));
+                control = Some(name);
+                (vec![first, second], Parameters::Cccs { gain, control: None })
+            }
+            _ => return Err(SpiceError::UnsupportedElement { line, name }),
+        };
+        // a two terminal element shorted by its own nodes would have no size in the schematic
+        if nodes.len() == 2 && net_name(&nodes[0]) == net_name(&nodes[1]) {
+            return Err(SpiceError::ShortedElement { line, name });
+        }
+        netlist.elements.push(NetlistElement { name, nodes, parameters, sense, control });
+    }
+
+    for (index, line, names, element_type) in model_uses {
+        let parameters = names.iter()
+            .find_map(|name| models.get(name).filter(|parameters| parameters.element_type() == element_type))
+            .ok_or_else(|| SpiceError::UnknownModel { line, kind: model_kind(element_type), model: names.first().cloned().unwrap_or_default() })?;
+        netlist.elements[index].parameters = parameters.clone();
+    }
+    for (line, name) in controlled {
+        if !netlist.elements.iter().any(|element| element.name.eq_ignore_ascii_case(&name)) {
+            return Err(SpiceError::UnknownControl { line, name });
+        }
+    }
+    Ok(netlist)
+}
+
+// the value of a source: [DC] value, a waveform or both, and an optional AC magnitude [phase]
+// parameters left out at the end of a waveform are 0, a source with only an ac value is 0 at dc
+fn parse_source(fields: &[&str], line: usize, name: &str) -> Result<(f64, Option<Waveform>, Option<AcSource>), SpiceError> {
+    let text = fields.join(" ").replace(['(', ')', ','], " ");
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    let missing = || SpiceError::MissingField { line, name: name.to_string() };
+
+    let mut value = None;
+    let mut waveform = None;
+    let mut ac = None;
+    // a value without a keyword is the dc value
+    let mut index = 0;
+    if let Some(number) = tokens.first().and_then(|token| parse_value(token)) {
+        value = Some(number);
+        index = 1;
+    }
+    while index < tokens.len() {
+        let keyword = tokens[index].to_ascii_lowercase();
+        // the numbers that follow a keyword
+        let numbers: Vec<f64> = tokens[index + 1..].iter().map_while(|token| parse_value(token)).collect();
+        let parameter = |index: usize| numbers.get(index).copied().unwrap_or(0.0);
+        let required = |count: usize| if numbers.len() < count { Err(missing()) } else { Ok(()) };
+        match keyword.as_str() {
+            "dc" => value = Some(*numbers.first().ok_or_else(missing)?),
+            // a magnitude left out is 1, as in spice
+            "ac" => ac = Some(AcSource { magnitude: numbers.first().copied().unwrap_or(1.0), phase: parameter(1) }),
+            "sin" => {
+                required(3)?;
+                waveform = Some(Waveform::Sine {
+                    offset: parameter(0), amplitude: parameter(1), frequency: parameter(2),
+                    delay: parameter(3), damping: parameter(4), phase: parameter(5),
+                });
+            }
+            "pulse" => {
+                required(2)?;
+                waveform = Some(Waveform::Pulse {
+                    initial: parameter(0), pulsed: parameter(1), delay: parameter(2), rise: parameter(3),
+                    fall: parameter(4), width: parameter(5), period: parameter(6),
+                });
+            }
+            "pwl" => {
+                if numbers.is_empty() || !numbers.len().is_multiple_of(2) {
+                    return Err(missing());
+                }
+                waveform = Some(Waveform::PiecewiseLinear { points: numbers.chunks(2).map(|pair| [pair[0], pair[1]]).collect() });
+            }
+            "exp" => {
+                required(6)?;
+                waveform = Some(Waveform::Exponential {
+                    initial: parameter(0), pulsed: parameter(1), rise_delay: parameter(2),
+                    rise_time_constant: parameter(3), fall_delay: parameter(4), fall_time_constant: parameter(5),
+                });
+            }
+            _ => return Err(SpiceError::InvalidValue { line, value: tokens[index].to_string() }),
+        }
+        index += 1 + numbers.len();
+    }
+
+    let value = value.or(waveform.as_ref().map(|waveform| waveform.value(0.0)));
+    let value = if ac.is_some() { value.unwrap_or(0.0) } else { value.ok_or_else(missing)? };
+    Ok((value, waveform, ac))
+}
+
+// .model name D(IS=1e-14 N=1 RS=0), models of other devices are skipped
+fn parse_model(statement: &str, line: usize) -> Result<(String, Option<Parameters>), SpiceError> {
+    let statement = statement.replace(['(', ')', '='], " ");
+    let fields: Vec<&str> = statement.split_whitespace().collect();
+    if fields.len() < 3 {
+        return Err(SpiceError::MissingField { line, name: ".model".to_string() });
+    }
+    let name = fields[1].to_ascii_lowercase();
+    // parameters left out keep the defaults of our devices
+    let mut parameters = match fields[2].to_ascii_lowercase().as_str() {
+        "d" => Parameters::Diode { saturation_current: 1.0e-14, emission_coefficient: 1.0, series_resistance: 0.0 },
+        "npn" => devices::Bjt::new(Vec::new(), BjtPolarity::Npn).parameters(),
+        "pnp" => devices::Bjt::new(Vec::new(), BjtPolarity::Pnp).parameters(),
+        "nmos" => devices::Mosfet::new(Vec::new(), MosfetChannel::N).parameters(),
+        "pmos" => devices::Mosfet::new(Vec::new(), MosfetChannel::P).parameters(),
+        _ => return Ok((name, None)),
+    };
+
+    for pair in fields[3..].chunks(2) {
+        let value = pair.get(1).ok_or(SpiceError::MissingField { line, name: pair[0].to_string() })?;
+        let value = parse_value(value).ok_or(SpiceError::InvalidValue { line, value: value.to_string() })?;
+        match (&mut parameters, pair[0].to_ascii_lowercase().as_str()) {
+            (Parameters::Diode { saturation_current, .. } | Parameters::Bjt { saturation_current, .. }, "is") => *saturation_current = value,
+            (Parameters::Diode { emission_coefficient, .. }, "n") => *emission_coefficient = value,
+            (Parameters::Diode { series_resistance, .. }, "rs") => *series_resistance = value,
+            (Parameters::Bjt { beta, .. }, "bf") => *beta = value,
+            (Parameters::Bjt { reverse_beta, .. }, "br") => *reverse_beta = value,
+            // both junctions share one thermal voltage, NR is taken to be the same as NF
+            (Parameters::Bjt { thermal_voltage, .. }, "nf") => *thermal_voltage = THERMAL_VOLTAGE * value,
+            (Parameters::Bjt { early_voltage, .. }, "vaf" | "va") => *early_voltage = value,
+            // the threshold of a p-channel model is negative in spice
+            (Parameters::Mosfet { channel, threshold_voltage, .. }, "vto") => {
+                *threshold_voltage = if *channel == MosfetChannel::P { -value } else { value };
+            }
+            (Parameters::Mosfet { transconductance, .. }, "kp") => *transconductance = value,
+            (Parameters::Mosfet { channel_length_modulation, .. }, "lambda") => *channel_length_modulation = value,
+            // junction capacitance, breakdown, the level and the like are not modeled
+            _ => {}
+        }
+    }
+    Ok((name, Some(parameters)))
+}
+
+// what the model of an element type is called in errors
+fn model_kind(element_type: ElementType) -> &'static str {
+    match element_type {
+        ElementType::Bjt => "bjt",
+        ElementType::Mosfet => "mosfet",
+        _ => "diode",
+    }
+}
+
+pub fn load(path: &Path) -> Result<Netlist, SpiceError> {
+    parse(&std::fs::read_to_string(path)?)
+}
+
+fn is_ground(node: &str) -> bool {
+    node == "0" || node.eq_ignore_ascii_case("gnd")
+}
+
+// every name of the ground node is 0
+fn net_name(node: &str) -> &str {
+    if is_ground(node) { "0" } else { node }
+}
+
+impl Netlist {
+    // every net is a vertical rail and every element gets its own row between the rails of its nodes,
+    // the ground rail is the leftmost one and ends in a ground symbol
+    // the sense nodes of a controlled source are the points where its row crosses their rails
+    // transistors sit to the right of the rails, with a wire from each pin to the rail of its node
+    pub fn to_schematic<'a>(&'a self, view: View) -> Schematic {
+        const RAIL_SPACING: i32 = 3;
+        const ROW_SPACING: i32 = 2;
+
+        let mut rails: Vec<&str> = vec!["0"];
+        let mut rail_rows: Vec<Vec<i32>> = vec![Vec::new()];
+        let mut rail_of = |node: &'a str| {
+            let node = net_name(node);
+            rails.iter().position(|rail| *rail == node).unwrap_or_else(|| {
+                rails.push(node);
+                rails.len() - 1
+            })
+        };
+
+        let mut schematic = Schematic::new(view);
+        let mut placed = Vec::new();
+        for element in self.elements.iter() {
+            let node_rails: Vec<usize> = element.nodes.iter().map(|node| rail_of(node)).collect();
+            let sense = element.sense.as_ref().map(|[positive, negative]| [rail_of(positive), rail_of(negative)]);
+            placed.push((element, node_rails, sense));
+        }
+        let body = rails.len() as i32 * RAIL_SPACING;
+
+        let mut id = 0;
+        let mut ids: HashMap<String, u32> = HashMap::new();
+        // wires from the pins of transistors to their rails: pin, rail
+        let mut leads = Vec::new();
+        let mut y = 0;
+        for (element, node_rails, sense) in placed.iter() {
+            let element_type = element.parameters.element_type();
+            // the rows where the element touches a rail
+            let mut touches: Vec<(usize, i32)> = Vec::new();
+            let (position, size) = match node_rails[..] {
+                [first, second] => {
+                    touches.extend([first, second].into_iter().chain(sense.iter().flatten().copied()).map(|rail| (rail, y)));
+                    ([first as i32 * RAIL_SPACING, y], [(second as i32 - first as i32) * RAIL_SPACING, 0])
+                }
+                _ => {
+                    // the base or gate is in the middle, a row below the collector or drain
+                    let (size, orientation) = placement(element_type, (1, 0));
+                    for ((x, pin_y), rail) in pin_positions(element_type, (body, y + 1), size, orientation).into_iter().zip(node_rails.iter()) {
+                        touches.push((*rail, pin_y));
+                        leads.push(([x, pin_y], *rail as i32 * RAIL_SPACING));
+                    }
+                    ([body, y + 1], [size.0, size.1])
+                }
+            };
+            for (rail, row) in touches {
+                rail_rows.resize(rail_rows.len().max(rail + 1), Vec::new());
+                // a sense node can be on the rail of an output node
+                if rail_rows[rail].last() != Some(&row) {
+                    rail_rows[rail].push(row);
+                }
+            }
+            id += 1;
+            ids.insert(element.name.to_ascii_lowercase(), id);
+            schematic.elements.push(ElementRecord {
+                id,
+                position,
+                size,
+                parameters: element.parameters.clone(),
+                sense: sense.map(|rails| rails.map(|rail| [rail as i32 * RAIL_SPACING, y])),
+                orientation: Orientation::default(),
+            });
+            y = rail_rows.iter().flatten().copied().max().unwrap_or(0) + ROW_SPACING;
+        }
+
+        // controlling elements can come after the sources they control
+        for (record, (element, ..)) in schematic.elements.iter_mut().zip(placed.iter()) {
+            let control = element.control.as_ref().and_then(|name| ids.get(&name.to_ascii_lowercase())).copied();
+            match &mut record.parameters {
+                Parameters::Ccvs { control: parameter, .. } | Parameters::Cccs { control: parameter, .. } => *parameter = control,
+                _ => {}
+            }
+        }
+
+        for ([x, y], rail_x) in leads {
+            id += 1;
+            schematic.elements.push(ElementRecord {
+                id,
+                position: [rail_x, y],
+                size: [x - rail_x, 0],
+                parameters: Parameters::Wire,
+                sense: None,
+                orientation: Orientation::default(),
+            });
+        }
+
+        // wires only connect at their ends, so every rail is split at the rows that touch it
+        for (rail, rows) in rail_rows.iter().enumerate() {
+            let x = rail as i32 * RAIL_SPACING;
+            for pair in rows.windows(2) {
+                id += 1;
+                schematic.elements.push(ElementRecord {
+                    id,
+                    position: [x, pair[0]],
+                    size: [0, pair[1] - pair[0]],
+                    parameters: Parameters::Wire,
+                    sense: None,
+                    orientation: Orientation::default(),
+                });
+            }
+        }
+        if let Some(bottom) = rail_rows[0].last() {
+            id += 1;
+            schematic.elements.push(ElementRecord { id, position: [0, *bottom], size: [0, 1], parameters: Parameters::Ground, sense: None, orientation: Orientation::default() });
+        }
+        schematic
+    }
+
+    // spice names of the nodes and elements in the circuit of a schematic made by to_schematic,
+    // a net is named at the node with the smallest id on its rail, ground is left out
+    pub fn names(&self, schematic: &Schematic) -> (BTreeMap<u32, String>, BTreeMap<u32, String>) {
+        let node_ids = schematic.node_ids();
+        let mut nodes: BTreeMap<&str, u32> = BTreeMap::new();
+        let mut elements = BTreeMap::new();
+        // the elements come first in the schematic and in the order of the netlist
+        for (record, element) in schematic.elements.iter().zip(self.elements.iter()) {
+            elements.insert(record.id, element.name.clone());
+            let names = element.nodes.iter().chain(element.sense.iter().flatten());
+            for (position, name) in record.node_positions().iter().zip(names) {
+                let id = node_ids[position];
+                let smallest = nodes.entry(net_name(name)).or_insert(id);
+                *smallest = id.min(*smallest);
+            }
+        }
+        nodes.remove("0");
+        (nodes.into_iter().map(|(name, id)| (id, name.to_string())).collect(), elements)
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use std::path::Path;
+use eframe::egui;
+use eframe::egui::{Align2, Color32, FontId, Pos2, Rect, Sense, Shape, Stroke, Vec2};
+use rusty_circuit::circuit::Circuit;
+use rusty_circuit::dc_sweep::{dc_sweep, table, DcSweep, DcSweepPoint, Trace};
+use rusty_circuit::ElementType;
+use crate::Node;
+use crate::oscilloscope::{probe_menu, Probe, COLORS};
+
+const GRID_LINES: usize = 8;
+
+// node voltages and element currents against a swept parameter, every trace is scaled to fill the plot
+// on its own, its range is shown next to its name
+pub struct SweepPlot {
+    pub open: bool,
+    pub sweep: DcSweep,
+    probes: Vec<Probe>,
+    // the traces of the last sweep, probes of nodes that were gone are left out
+    traces: Vec<(Probe, Trace)>,
+    points: Vec<DcSweepPoint>,
+    error: Option<String>,
+}
+
+impl SweepPlot {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            sweep: DcSweep { element: 0, parameter: String::new(), start: 0.0, stop: 5.0, points: 51 },
+            probes: Vec::new(),
+            traces: Vec::new(),
+            points: Vec::new(),
+            error: None,
+        }
+    }
+
+    // the table is written next to the schematic, with the .csv extension
+    pub fn draw(&mut self, ctx: &egui::Context, nodes: &HashMap<(i32, i32), Node>, elements: &[(u32, ElementType)], circuit: &Circuit, file_path: &str) {
+        let mut open = self.open;
+        egui::Window::new("DC sweep").open(&mut open).default_size([600.0, 400.0]).show(ctx, |ui| {
+            self.draw_settings(ui, elements, circuit);
+
+            ui.horizontal(|ui| {
+                if let Some(probe) = probe_menu(ui, nodes, elements) {
+                    self.probes.push(probe);
+                }
+                if ui.button("Sweep").clicked() {
+                    self.run(nodes, circuit);
+                }
+                if ui.add_enabled(!self.points.is_empty(), egui::Button::new("Export table")).clicked() {
+                    self.export(Path::new(file_path).with_extension("csv").as_path());
+                }
+            });
+
+            let mut removed = None;
+            ui.horizontal_wrapped(|ui| {
+                for (index, probe) in self.probes.iter().enumerate() {
+                    let color = COLORS[index % COLORS.len()];
+                    let range = self.traces.iter()
+                        .find(|(traced, _)| traced == probe)
+                        .and_then(|(_, trace)| self.range(trace))
+                        .map_or(String::new(), |(low, high)| format!(" {:.4} .. {:.4} {}", low, high, probe.unit()));
+                    ui.colored_label(color, format!("{}{}", probe.label(), range));
+                    if ui.small_button("x").clicked() {
+                        removed = Some(index);
+                    }
+                    ui.separator();
+                }
+            });
+            if let Some(index) = removed {
+                self.probes.remove(index);
+            }
+
+            if let Some(error) = &self.error {
+                ui.colored_label(Color32::RED, error);
+            }
+            if !self.points.is_empty() {
+                let (rect, _) = ui.allocate_exact_size(Vec2::new(ui.available_width(), ui.available_height().max(150.0)), Sense::hover());
+                self.draw_plot(ui, rect);
+            }
+        });
+        self.open = open;
+    }
+
+    fn draw_settings(&mut self, ui: &mut egui::Ui, elements: &[(u32, ElementType)], circuit: &Circuit) {
+        ui.horizontal(|ui| {
+            // only elements with a numeric parameter can be swept
+            let sweepable: Vec<(u32, ElementType, Vec<&'static str>)> = elements.iter()
+                .filter_map(|(id, element_type)| {
+                    let names: Vec<&'static str> = circuit.element(*id)?.parameters().values_mut().into_iter().map(|(name, _)| name).collect();
+                    (!names.is_empty()).then_some((*id, *element_type, names))
+                })
+                .collect();
+
+            let selected = elements.iter().find(|(id, _)| *id == self.sweep.element);
+            egui::ComboBox::from_label("Element")
+                .selected_text(selected.map_or("-".to_string(), |(id, element_type)| format!("{:?} {}", element_type, id)))
+                .show_ui(ui, |ui| {
+                    for (id, element_type, names) in sweepable.iter() {
+                        if ui.selectable_label(self.sweep.element == *id, format!("{:?} {}", element_type, id)).clicked() {
+                            self.sweep.element = *id;
+                            self.sweep.parameter = names[0].to_string();
+                        }
+                    }
+                });
+
+            let names = sweepable.iter().find(|(id, ..)| *id == self.sweep.element).map_or(Vec::new(), |(.., names)| names.clone());
+            egui::ComboBox::from_label("Parameter")
+                .selected_text(self.sweep.parameter.clone())
+                .show_ui(ui, |ui| {
+                    for name in names {
+                        ui.selectable_value(&mut self.sweep.parameter, name.to_string(), name);
+                    }
+                });
+        });
+
+        ui.horizontal(|ui| {
+            let speed = self.sweep.start.abs().max(self.sweep.stop.abs()).max(1.0e-3) * 0.01;
+            ui.add(egui::DragValue::new(&mut self.sweep.start).speed(speed).prefix("Start: "));
+            ui.add(egui::DragValue::new(&mut self.sweep.stop).speed(speed).prefix("Stop: "));
+            ui.add(egui::DragValue::new(&mut self.sweep.points).range(2..=10_000).prefix("Points: "));
+        });
+    }
+
+    fn run(&mut self, nodes: &HashMap<(i32, i32), Node>, circuit: &Circuit) {
+        self.traces = self.probes.iter()
+            .filter_map(|probe| match probe {
+                Probe::Voltage(position) => nodes.get(position).map(|node| (*probe, Trace::Voltage(node.id))),
+                Probe::Current(element) => Some((*probe, Trace::Current(*element))),
+            })
+            .collect();
+        match dc_sweep(circuit, &self.sweep) {
+            Ok(points) => {
+                self.points = points;
+                self.error = None;
+            }
+            Err(error) => {
+                self.points.clear();
+                self.error = Some(error.to_string());
+            }
+        }
+    }
+
+    fn export(&mut self, path: &Path) {
+        let columns: Vec<(String, Trace)> = self.traces.iter().map(|(probe, trace)| (probe.label(), *trace)).collect();
+        let swept = format!("{} {}", self.sweep.parameter, self.sweep.element);
+        self.error = std::fs::write(path, table(&self.points, &swept, &columns)).err().map(|error| error.to_string());
+    }
+
+    fn range(&self, trace: &Trace) -> Option<(f64, f64)> {
+        self.points.iter()
+            .filter_map(|point| trace.value(&point.solution))
+            .fold(None, |range, value| match range {
+                None => Some((value, value)),
+                Some((low, high)) => Some((f64::min(low, value), f64::max(high, value))),
+            })
+    }
+
+    fn draw_plot(&self, ui: &mut egui::Ui, rect: Rect) {
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, Color32::BLACK);
+        for i in 0..=GRID_LINES {
+            let fraction = i as f32 / GRID_LINES as f32;
+            let x = rect.left() + fraction * rect.width();
+            let y = rect.top() + fraction * rect.height();
+            painter.line_segment([Pos2::new(x, rect.top()), Pos2::new(x, rect.bottom())], Stroke::new(0.5, Color32::from_gray(60)));
+            painter.line_segment([Pos2::new(rect.left(), y), Pos2::new(rect.right(), y)], Stroke::new(0.5, Color32::from_gray(60)));
+        }
+
+        let first = self.points[0].value;
+        let last = self.points[self.points.len() - 1].value;
+        let width = if last != first { last - first } else { 1.0 };
+        for (probe, trace) in self.traces.iter() {
+            let Some((low, high)) = self.range(trace) else {
+                continue;
+            };
+            // a flat trace is drawn through the middle
+            let (low, high) = if high - low < 1.0e-12 { (low - 1.0, high + 1.0) } else { (low, high) };
+            let index = self.probes.iter().position(|traced| traced == probe).unwrap_or(0);
+            let points: Vec<Pos2> = self.points.iter()
+                .filter_map(|point| Some((point.value, trace.value(&point.solution)?)))
+                .map(|(x, y)| Pos2::new(
+                    rect.left() + ((x - first) / width) as f32 * rect.width(),
+                    rect.bottom() - ((y - low) / (high - low)) as f32 * rect.height(),
+                ))
+                .collect();
+            painter.add(Shape::line(points, Stroke::new(1.5, COLORS[index % COLORS.len()])));
+        }
+
+        let font = FontId::monospace(11.0);
+        painter.text(rect.left_bottom() + Vec2::new(4.0, -2.0), Align2::LEFT_BOTTOM, format!("{}", first), font.clone(), Color32::GRAY);
+        painter.text(rect.right_bottom() - Vec2::new(4.0, 2.0), Align2::RIGHT_BOTTOM, format!("{} {}", last, self.sweep.parameter), font, Color32::GRAY);
+    }
+}
@@ -0,0 +1,106 @@
+// time dependent values of the independent sources, the shapes and their parameters are the ones of
+// the spice SIN, PULSE, PWL and EXP sources
+//
+// the sources are evaluated at the time of the end of the step that is being solved, edges of pulses
+// and corners of piecewise linear tables are reported as breakpoints so the stepper can land on them
+// instead of cutting them off
+
+use std::f64::consts::PI;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "shape")]
+pub enum Waveform {
+    // offset + amplitude * e^(-damping * t) * sin(2 pi frequency t + phase) from the delay on,
+    // the phase is in degrees
+    Sine { offset: f64, amplitude: f64, frequency: f64, delay: f64, damping: f64, phase: f64 },
+    // starts at initial, after the delay it rises to pulsed, stays there for the width and falls
+    // back, a period of 0 is a single pulse
+    Pulse { initial: f64, pulsed: f64, delay: f64, rise: f64, fall: f64, width: f64, period: f64 },
+    // [time, value] pairs with increasing times, the first and last value hold before and after the table
+    PiecewiseLinear { points: Vec<[f64; 2]> },
+    // approaches pulsed from the rise delay on and goes back to initial from the fall delay on
+    Exponential { initial: f64, pulsed: f64, rise_delay: f64, rise_time_constant: f64, fall_delay: f64, fall_time_constant: f64 },
+}
+
+// 1 - e^(-t / tau) for t >= 0, a time constant of 0 is a step
+fn approach(time: f64, time_constant: f64) -> f64 {
+    if time < 0.0 {
+        0.0
+    } else if time_constant <= 0.0 {
+        1.0
+    } else {
+        1.0 - (-time / time_constant).exp()
+    }
+}
+
+impl Waveform {
+    pub fn value(&self, time: f64) -> f64 {
+        match self {
+            Waveform::Sine { offset, amplitude, frequency, delay, damping, phase } => {
+                let time = (time - delay).max(0.0);
+                let angle = 2.0 * PI * frequency * time + phase.to_radians();
+                offset + amplitude * (-damping * time).exp() * angle.sin()
+            }
+            Waveform::Pulse { initial, pulsed, delay, rise, fall, width, period } => {
+                if time < *delay {
+                    return *initial;
+                }
+                let mut time = time - delay;
+                if *period > 0.0 {
+                    time %= period;
+                }
+                let step = pulsed - initial;
+                if time < *rise {
+                    initial + step * time / rise
+                } else if time < rise + width {
+                    *pulsed
+                } else if time < rise + width + fall {
+                    pulsed - step * (time - rise - width) / fall
+                } else {
+                    *initial
+                }
+            }
+            Waveform::PiecewiseLinear { points } => {
+                let Some(last) = points.last() else {
+                    return 0.0;
+                };
+                match points.iter().position(|[point_time, _]| *point_time > time) {
+                    Some(0) => points[0][1],
+                    Some(index) => {
+                        let ([time1, value1], [time2, value2]) = (points[index - 1], points[index]);
+                        value1 + (value2 - value1) * (time - time1) / (time2 - time1)
+                    }
+                    None => last[1],
+                }
+            }
+            Waveform::Exponential { initial, pulsed, rise_delay, rise_time_constant, fall_delay, fall_time_constant } => {
+                initial + (pulsed - initial) * approach(time - rise_delay, *rise_time_constant)
+                    + (initial - pulsed) * approach(time - fall_delay, *fall_time_constant)
+            }
+        }
+    }
+
+    // first time after the given one where the waveform has a corner
+    pub fn next_breakpoint(&self, time: f64) -> Option<f64> {
+        // a breakpoint that was just reached is not reported again
+        let after = |breakpoint: &f64| *breakpoint > time + 1.0e-9 * time.abs().max(1.0e-12);
+        match self {
+            Waveform::Sine { delay, .. } => Some(*delay).filter(after),
+            Waveform::Pulse { delay, rise, fall, width, period, .. } => {
+                let corners = [0.0, *rise, rise + width, rise + width + fall];
+                // the corners of the cycle the time is in and of the next one
+                let cycle = if *period > 0.0 { ((time - delay) / period).floor().max(0.0) } else { 0.0 };
+                let cycles: &[f64] = if *period > 0.0 { &[cycle, cycle + 1.0] } else { &[0.0] };
+                cycles.iter()
+                    .flat_map(|cycle| corners.map(|corner| delay + cycle * period + corner))
+                    .filter(after)
+                    .min_by(f64::total_cmp)
+            }
+            Waveform::PiecewiseLinear { points } => points.iter().map(|[point_time, _]| *point_time).find(after),
+            Waveform::Exponential { rise_delay, fall_delay, .. } => {
+                [*rise_delay, *fall_delay].into_iter().filter(after).min_by(f64::total_cmp)
+            }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+// small-signal sweeps of an rc low-pass against its corner frequency, and sweeps that can not be run
+//
+// the input is a source with an ac value of 1 V at node 1, the output is node 2
+
+mod common;
+
+use std::f64::consts::{FRAC_1_SQRT_2, TAU};
+use rusty_circuit::ac::{bode, AcSource, Sweep, SweepScale};
+use rusty_circuit::circuit::Circuit;
+use rusty_circuit::circuit_solver::SolverError;
+use rusty_circuit::devices::{Capacitor, DCVoltageSource, Resistor};
+use common::assert_close;
+
+const RESISTANCE: f64 = 1000.0;
+const CAPACITANCE: f64 = 1.0e-6;
+
+// 1 kOhm into 1 uF, returns the circuit and the id of the input
+fn low_pass() -> (Circuit, u32) {
+    let mut circuit = Circuit::new();
+    let mut source = DCVoltageSource::new(vec![0, 1], 0.0);
+    source.ac = Some(AcSource { magnitude: 1.0, phase: 0.0 });
+    let input = circuit.add(source);
+    circuit.add(Resistor::new(vec![1, 2], RESISTANCE));
+    circuit.add(Capacitor::new(vec![2, 0], CAPACITANCE));
+    (circuit, input)
+}
+
+fn corner() -> f64 {
+    1.0 / (TAU * RESISTANCE * CAPACITANCE)
+}
+
+#[test]
+fn low_pass_at_its_corner() {
+    let (circuit, input) = low_pass();
+    let sweep = Sweep { scale: SweepScale::Linear, points: 1, start: corner(), stop: corner() };
+    let solutions = circuit.ac_sweep(&sweep).unwrap();
+    assert_eq!(solutions.len(), 1);
+    assert_close(solutions[0].voltage(2).norm(), FRAC_1_SQRT_2, 1.0e-9);
+
+    let points = bode(&circuit, &solutions, 2, input).unwrap();
+    assert_close(points[0].decibels(), -10.0 * 2.0f64.log10(), 1.0e-9);
+    assert_close(points[0].phase, -45.0, 1.0e-9);
+}
+
+#[test]
+fn low_pass_over_four_decades() {
+    let (circuit, input) = low_pass();
+    // the corner is the 21st of the 41 frequencies
+    let sweep = Sweep { scale: SweepScale::Decade, points: 10, start: corner() / 100.0, stop: corner() * 100.0 };
+    let points = bode(&circuit, &circuit.ac_sweep(&sweep).unwrap(), 2, input).unwrap();
+    assert_eq!(points.len(), 41);
+    assert_close(points[20].frequency, corner(), 1.0e-9);
+    assert_close(points[20].decibels(), -3.0103, 1.0e-4);
+    assert_close(points[20].phase, -45.0, 1.0e-6);
+
+    // flat well below the corner and falling by 20 dB per decade well above it
+    assert!(points[0].decibels().abs() < 1.0e-3);
+    assert!(points[0].phase.abs() < 1.0);
+    assert_close(points[40].decibels(), -40.0, 1.0e-3);
+    assert_close(points[40].phase, -90.0, 1.0e-2);
+    for pair in points.windows(2) {
+        assert!(pair[1].decibels() < pair[0].decibels() && pair[1].phase < pair[0].phase);
+    }
+}
+
+#[test]
+fn sweeps_without_frequencies_are_refused() {
+    let (circuit, _) = low_pass();
+    for sweep in [
+        // logarithmic sweeps have to start above 0 and can not run backwards
+        Sweep { scale: SweepScale::Decade, points: 10, start: 0.0, stop: 1000.0 },
+        Sweep { scale: SweepScale::Octave, points: 10, start: 1000.0, stop: 10.0 },
+        Sweep { scale: SweepScale::Decade, points: 0, start: 1.0, stop: 1000.0 },
+        Sweep { scale: SweepScale::Linear, points: 3, start: -10.0, stop: 10.0 },
+        Sweep { scale: SweepScale::Linear, points: 3, start: 1.0, stop: f64::INFINITY },
+        Sweep { scale: SweepScale::Linear, points: 3, start: f64::NAN, stop: 10.0 },
+    ] {
+        // compared with matches, a sweep holding NaN is not equal to itself
+        assert!(matches!(circuit.ac_sweep(&sweep), Err(SolverError::InvalidSweep { .. })), "{:?}", sweep);
+    }
+
+    let sweep = Sweep { scale: SweepScale::Decade, points: 10, start: 0.0, stop: 1000.0 };
+    assert_eq!(circuit.ac_sweep(&sweep).unwrap_err().to_string(), "The sweep of 10 points from 0 Hz to 1000 Hz has no valid frequencies");
+}
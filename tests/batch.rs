@@ -0,0 +1,326 @@
+// the command-line batch solver: exit codes, output formats and argument parsing
+//
+// the schematics are written to the temp directory and the binary is run on them like a script would
+
+mod common;
+
+use std::path::PathBuf;
+use std::process::Command;
+use serde_json::Value;
+use rusty_circuit::circuit::Circuit;
+use rusty_circuit::devices::{Bjt, BjtPolarity, DCVoltageSource, Mosfet, MosfetChannel, Resistor};
+use rusty_circuit::devices::diode::THERMAL_VOLTAGE;
+use rusty_circuit::pins::Orientation;
+use rusty_circuit::schematic::{ElementRecord, Parameters, Schematic, View};
+use rusty_circuit::spice;
+use common::assert_close;
+
+fn record(id: u32, from: [i32; 2], to: [i32; 2], parameters: Parameters) -> ElementRecord {
+    ElementRecord { id, position: from, size: [to[0] - from[0], to[1] - from[1]], parameters, sense: None, orientation: Orientation::default() }
+}
+
+fn source(voltage: f64) -> Parameters {
+    Parameters::DCVoltageSource { voltage, waveform: None, ac: None }
+}
+
+// a 10 V source on the left and a loop of the given element to the right of it
+//
+//   (0,0) -- resistor -- (2,0)
+//     |                    |
+//   source              element
+//     |                    |
+//   (0,2) ---- wire ---- (2,2)
+//
+// the nodes are numbered in the order they come up: (0,2) is 1 and grounded, (0,0) is 2, (2,0) is 3
+fn series_circuit(name: &str, element: Parameters) -> PathBuf {
+    let mut schematic = Schematic::new(View { offset: [0.0, 0.0], grid_step: 20.0 });
+    schematic.elements = vec![
+        record(1, [0, 2], [0, 0], source(10.0)),
+        record(2, [0, 0], [2, 0], Parameters::Resistor { resistance: 1000.0 }),
+        record(3, [2, 0], [2, 2], element),
+        record(4, [2, 2], [0, 2], Parameters::Wire),
+        record(5, [0, 2], [0, 2], Parameters::Ground),
+    ];
+    let path = std::env::temp_dir().join(format!("rusty_circuit_batch_{}_{}.json", std::process::id(), name));
+    schematic.save(&path).expect("the schematic should save");
+    path
+}
+
+fn divider(name: &str) -> PathBuf {
+    series_circuit(name, Parameters::Resistor { resistance: 1000.0 })
+}
+
+fn deck(name: &str, text: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("rusty_circuit_batch_{}_{}.cir", std::process::id(), name));
+    std::fs::write(&path, text).expect("the deck should save");
+    path
+}
+
+// exit code, stdout and stderr
+fn batch(args: &[&str]) -> (i32, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_rusty_circuit_batch")).args(args).output().expect("the batch solver should run");
+    (output.status.code().expect("the batch solver should exit"), String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap())
+}
+
+fn json(output: &str) -> Value {
+    serde_json::from_str(output).expect("the output should be json")
+}
+
+// the values of a column of an analysis in the json output
+fn column(analysis: &Value, name: &str) -> Vec<f64> {
+    let index = analysis["columns"].as_array().unwrap().iter().position(|column| column == name).unwrap_or_else(|| panic!("no column {}", name));
+    analysis["rows"].as_array().unwrap().iter().map(|row| row[index].as_f64().unwrap()).collect()
+}
+
+#[test]
+fn operating_point_in_every_format() {
+    let path = divider("formats");
+    let path = path.to_str().unwrap();
+
+    let (code, output, _) = batch(&[path]);
+    assert_eq!(code, 0);
+    let mut lines = output.lines();
+    assert_eq!(lines.next(), Some("op"));
+    let header: Vec<&str> = lines.next().unwrap().split_whitespace().collect();
+    assert_eq!(header, ["V(2)", "V(3)", "I(1)", "I(2)", "I(3)"]);
+    assert!(lines.next().unwrap().contains("5.000000e0"));
+
+    let (code, output, _) = batch(&[path, "--format", "csv"]);
+    assert_eq!(code, 0);
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines[0], "V(2),V(3),I(1),I(2),I(3)");
+    let values: Vec<f64> = lines[1].split(',').map(|value| value.parse().unwrap()).collect();
+    assert_close(values[0], 10.0, 1.0e-6);
+    assert_close(values[1], 5.0, 1.0e-6);
+    assert_close(values[3], 5.0e-3, 1.0e-6);
+
+    let (code, output, _) = batch(&[path, "--op", "--format", "json"]);
+    assert_eq!(code, 0);
+    let output = json(&output);
+    assert_eq!(output["file"], path);
+    assert_eq!(output["analyses"][0]["analysis"], "op");
+    assert_close(column(&output["analyses"][0], "V(3)")[0], 5.0, 1.0e-6);
+}
+
+#[test]
+fn analyses_run_in_the_order_they_are_given() {
+    let path = divider("order");
+    let (code, output, _) = batch(&[path.to_str().unwrap(), "--dc", "1", "voltage", "0", "10", "3", "--op", "--format", "json"]);
+    assert_eq!(code, 0);
+    let output = json(&output);
+    let analyses = output["analyses"].as_array().unwrap();
+    assert_eq!(analyses.len(), 2);
+    assert_eq!(analyses[0]["analysis"], "dc");
+    assert_eq!(column(&analyses[0], "voltage 1"), [0.0, 5.0, 10.0]);
+    for (value, expected) in column(&analyses[0], "V(3)").into_iter().zip([0.0, 2.5, 5.0]) {
+        assert_close(value, expected, 1.0e-6);
+    }
+    assert_eq!(analyses[1]["analysis"], "op");
+}
+
+#[test]
+fn point_counts_have_to_be_whole_numbers() {
+    let path = divider("counts");
+    let path = path.to_str().unwrap();
+    for count in ["2.5", "-3", "0"] {
+        let (code, output, error) = batch(&[path, "--dc", "1", "voltage", "0", "10", count]);
+        assert_eq!(code, 1, "{} points", count);
+        assert!(output.is_empty());
+        assert!(error.starts_with("error[usage]"), "{}", error);
+    }
+    let (code, _, _) = batch(&[path, "--ac", "dec", "1.5", "1", "1k"]);
+    assert_eq!(code, 1);
+}
+
+#[test]
+fn bad_arguments() {
+    let path = divider("arguments");
+    let path = path.to_str().unwrap();
+    for args in [
+        vec![],
+        vec![path, "--bode"],
+        vec![path, "--format", "xml"],
+        vec![path, path],
+        vec![path, "--tran", "0", "1m"],
+        vec![path, "--tran", "1u"],
+        vec![path, "--dc", "1"],
+        vec![path, "--ac", "log", "10", "1", "1k"],
+    ] {
+        let (code, _, error) = batch(&args);
+        assert_eq!(code, 1, "{:?}", args);
+        assert!(error.contains("usage: rusty_circuit_batch"), "{:?}", args);
+    }
+
+    let (code, _, error) = batch(&[path, "--nonsense", "--format", "json"]);
+    assert_eq!(code, 1);
+    assert_eq!(json(&error)["error"], "usage");
+
+    let (code, output, _) = batch(&["--help"]);
+    assert_eq!(code, 0);
+    assert!(output.starts_with("usage: rusty_circuit_batch"));
+}
+
+#[test]
+fn element_to_sweep_has_to_exist() {
+    let path = divider("sweep_element");
+    let (code, _, error) = batch(&[path.to_str().unwrap(), "--dc", "9", "voltage", "0", "1", "2"]);
+    assert_eq!(code, 1);
+    assert!(error.contains("no element 9"), "{}", error);
+}
+
+#[test]
+fn missing_file() {
+    let (code, output, error) = batch(&["/nonexistent/circuit.json", "--format", "json"]);
+    assert_eq!(code, 2);
+    assert!(output.is_empty());
+    assert_eq!(json(&error)["error"], "load");
+}
+
+#[test]
+fn solver_failure_names_the_elements() {
+    // a wire across the source
+    let mut schematic = Schematic::new(View { offset: [0.0, 0.0], grid_step: 20.0 });
+    schematic.elements = vec![
+        record(1, [0, 2], [0, 0], source(10.0)),
+        record(2, [0, 0], [0, 2], Parameters::Wire),
+        record(3, [0, 2], [0, 2], Parameters::Ground),
+    ];
+    let path = std::env::temp_dir().join(format!("rusty_circuit_batch_{}_shorted.json", std::process::id()));
+    schematic.save(&path).unwrap();
+
+    let (code, output, error) = batch(&[path.to_str().unwrap(), "--format", "json"]);
+    assert_eq!(code, 3);
+    // the analyses that did solve are still printed, there are none here
+    assert_eq!(json(&output)["analyses"], Value::Array(Vec::new()));
+    let error = json(&error);
+    assert_eq!(error["error"], "voltage_source_loop");
+    assert_eq!(error["analysis"], "op");
+    assert_eq!(error["elements"], serde_json::json!(["1"]));
+
+    let (code, _, error) = batch(&[path.to_str().unwrap()]);
+    assert_eq!(code, 3);
+    assert!(error.starts_with("error[voltage_source_loop]: op: "), "{}", error);
+}
+
+#[test]
+fn transient_starts_from_the_operating_point() {
+    // the capacitor is charged to the full 10 V from the start, so nothing moves
+    let path = series_circuit("charged", Parameters::Capacitor { capacitance: 1.0e-6 });
+    let (code, output, _) = batch(&[path.to_str().unwrap(), "--tran", "100u", "1m", "--format", "json"]);
+    assert_eq!(code, 0);
+    let output = json(&output);
+    let tran = &output["analyses"][0];
+    let time = column(tran, "time");
+    assert_eq!(time.len(), 11);
+    assert_eq!(time[0], 0.0);
+    for voltage in column(tran, "V(3)") {
+        assert_close(voltage, 10.0, 1.0e-6);
+    }
+
+    // the inductor carries the full 10 mA from the start
+    let path = series_circuit("fluxed", Parameters::Inductor { inductance: 1.0e-3 });
+    let (code, output, _) = batch(&[path.to_str().unwrap(), "--tran", "100u", "1m", "--format", "json"]);
+    assert_eq!(code, 0);
+    for current in column(&json(&output)["analyses"][0], "I(3)") {
+        assert_close(current, 10.0e-3, 1.0e-6);
+    }
+}
+
+#[test]
+fn spice_deck_runs_its_own_analyses() {
+    let path = deck("divider", "divider\nV1 in 0 10\nR1 in out 1k\nR2 out 0 1k\n.op\n.tran 1m 2m\n.end\n");
+    let (code, output, _) = batch(&[path.to_str().unwrap(), "--format", "json"]);
+    assert_eq!(code, 0);
+    let output = json(&output);
+    let analyses = output["analyses"].as_array().unwrap();
+    assert_eq!(analyses.len(), 2);
+    assert_close(column(&analyses[0], "V(out)")[0], 5.0, 1.0e-6);
+    assert_close(column(&analyses[0], "I(R1)")[0], 5.0e-3, 1.0e-6);
+    assert_eq!(column(&analyses[1], "time"), [0.0, 1.0e-3, 2.0e-3]);
+
+    // analyses on the command line replace the ones of the deck
+    let (code, output, _) = batch(&[path.to_str().unwrap(), "--dc", "V1", "voltage", "0", "2", "2", "--format", "json"]);
+    assert_eq!(code, 0);
+    let output = json(&output);
+    assert_eq!(output["analyses"].as_array().unwrap().len(), 1);
+    assert_eq!(column(&output["analyses"][0], "V(out)"), [0.0, 1.0]);
+}
+
+// one transistor of every kind biased from a 10 V supply at node 1, with parameters away from the defaults
+fn transistor_circuit() -> Circuit {
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 1], 10.0));
+    // npn with its collector at node 2 and its base at node 3
+    circuit.add(Resistor::new(vec![1, 2], 1000.0));
+    circuit.add(Resistor::new(vec![1, 3], 1.0e6));
+    let mut npn = Bjt::new(vec![2, 3, 0], BjtPolarity::Npn);
+    npn.beta = 150.0;
+    npn.early_voltage = 50.0;
+    circuit.add(npn);
+    // pnp with its base at node 4 and its collector at node 5
+    circuit.add(Resistor::new(vec![4, 0], 1.0e6));
+    circuit.add(Resistor::new(vec![5, 0], 1000.0));
+    let mut pnp = Bjt::new(vec![5, 4, 1], BjtPolarity::Pnp);
+    pnp.thermal_voltage = 1.2 * THERMAL_VOLTAGE;
+    pnp.reverse_beta = 2.0;
+    circuit.add(pnp);
+    // both mosfets share the gate at node 6 at half the supply, their drains are nodes 7 and 8
+    circuit.add(Resistor::new(vec![1, 6], 10000.0));
+    circuit.add(Resistor::new(vec![6, 0], 10000.0));
+    circuit.add(Resistor::new(vec![1, 7], 10000.0));
+    circuit.add(Resistor::new(vec![8, 0], 10000.0));
+    let mut nmos = Mosfet::new(vec![7, 6, 0], MosfetChannel::N);
+    nmos.channel_length_modulation = 0.02;
+    circuit.add(nmos);
+    let mut pmos = Mosfet::new(vec![8, 6, 1], MosfetChannel::P);
+    pmos.threshold_voltage = 1.5;
+    pmos.transconductance = 1.0e-3;
+    circuit.add(pmos);
+    circuit
+}
+
+#[test]
+fn transistors_round_trip_through_spice() {
+    let circuit = transistor_circuit();
+    let netlist = spice::parse(&spice::export(&circuit, "transistors")).expect("the export should parse");
+
+    let transistors: Vec<&spice::NetlistElement> = netlist.elements.iter()
+        .filter(|element| matches!(element.parameters, Parameters::Bjt { .. } | Parameters::Mosfet { .. }))
+        .collect();
+    assert_eq!(transistors.len(), 4);
+    for element in transistors {
+        let id: u32 = element.name[1..].parse().unwrap();
+        let original = circuit.element(id).unwrap().parameters();
+        let mut parameters = element.parameters.clone();
+        if let (Parameters::Bjt { thermal_voltage, .. }, Parameters::Bjt { thermal_voltage: expected, .. }) = (&mut parameters, &original) {
+            // the thermal voltage goes through the emission coefficient
+            assert_close(*thermal_voltage, *expected, 1.0e-6);
+            *thermal_voltage = *expected;
+        }
+        // the threshold of the p-channel mosfet comes back positive
+        assert_eq!(parameters, original);
+    }
+
+    let schematic = netlist.to_schematic(View { offset: [0.0, 0.0], grid_step: 20.0 });
+    let (nodes, _) = netlist.names(&schematic);
+    let solution = schematic.to_circuit().operating_point().expect("the imported circuit should solve");
+    let expected = circuit.operating_point().unwrap();
+    assert_eq!(nodes.len(), 8);
+    for (id, name) in nodes {
+        let original: u32 = name[1..].parse().unwrap();
+        assert_close(solution.voltage(id), expected.voltage(original), 1.0e-6);
+    }
+}
+
+#[test]
+fn transistor_models_have_to_match_the_card() {
+    let error = spice::parse("missing\nQ1 c b 0 QX\n.end\n").unwrap_err();
+    assert_eq!(error.to_string(), "Line 2: there is no bjt model qx");
+    let error = spice::parse("wrong kind\nM1 d g 0 0 D1\n.model D1 D(IS=1e-14)\n.end\n").unwrap_err();
+    assert_eq!(error.to_string(), "Line 2: there is no mosfet model d1");
+
+    // with a substrate node the model comes one field later
+    let netlist = spice::parse("substrate\nQ1 c b e 0 Q2N3904\n.model Q2N3904 PNP(BF=80)\n.end\n").unwrap();
+    assert_eq!(netlist.elements[0].nodes, ["c", "b", "e"]);
+    assert!(matches!(netlist.elements[0].parameters, Parameters::Bjt { polarity: BjtPolarity::Pnp, beta: 80.0, .. }));
+}
@@ -0,0 +1,131 @@
+// the circuit checker that runs before solving, and the elements and nodes it points out
+
+use std::collections::BTreeSet;
+use rusty_circuit::circuit::{Circuit, SolverInfo, StepInfo};
+use rusty_circuit::circuit_solver::SolverError;
+use rusty_circuit::devices::{Capacitor, CurrentSource, DCVoltageSource, Ground, Inductor, Resistor, Switch, Wire};
+
+fn set(ids: &[u32]) -> BTreeSet<u32> {
+    ids.iter().copied().collect()
+}
+
+#[test]
+fn solvable_circuit_has_no_warnings() {
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![3, 1], 5.0));
+    circuit.add(Resistor::new(vec![1, 2], 1000.0));
+    circuit.add(Capacitor::new(vec![2, 3], 1.0e-6));
+    circuit.add(Ground::new(vec![3]));
+
+    assert!(circuit.check().is_empty());
+    assert!(circuit.operating_point().is_ok());
+}
+
+#[test]
+fn circuit_without_ground() {
+    let mut circuit = Circuit::new();
+    let source = circuit.add(DCVoltageSource::new(vec![1, 2], 5.0));
+    let resistor = circuit.add(Resistor::new(vec![2, 1], 1000.0));
+
+    let warnings = circuit.check();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].error, SolverError::FloatingSubnet { nodes: vec![1, 2] });
+    assert_eq!(warnings[0].elements, set(&[source, resistor]));
+    assert_eq!(warnings[0].nodes, set(&[1, 2]));
+    assert_eq!(circuit.operating_point().unwrap_err(), warnings[0].error);
+}
+
+#[test]
+fn node_between_capacitors_floats_at_the_operating_point() {
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 1], 5.0));
+    let first = circuit.add(Capacitor::new(vec![1, 2], 1.0e-6));
+    let second = circuit.add(Capacitor::new(vec![2, 0], 1.0e-6));
+
+    let warnings = circuit.check();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].error, SolverError::FloatingSubnet { nodes: vec![2] });
+    assert_eq!(warnings[0].elements, set(&[first, second]));
+    assert_eq!(circuit.operating_point().unwrap_err(), warnings[0].error);
+
+    // in a time step the capacitors conduct through their companion model
+    let step = StepInfo { timestep: 1.0e-6, time: 0.0 };
+    assert!(circuit.check_at(&step).is_empty());
+    assert!(circuit.solve_with_info(&step, &mut SolverInfo::default()).is_ok());
+}
+
+#[test]
+fn voltage_source_shorted_by_wire() {
+    let mut circuit = Circuit::new();
+    let source = circuit.add(DCVoltageSource::new(vec![0, 1], 5.0));
+    circuit.add(Resistor::new(vec![1, 0], 1000.0));
+    let wire = circuit.add(Wire::new(vec![1, 2]));
+    let switch = circuit.add(Switch::new(vec![2, 0], true));
+
+    let warnings = circuit.check();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].error, SolverError::VoltageSourceLoop { elements: vec![source] });
+    assert_eq!(warnings[0].error.to_string(), format!("Voltage source {} is shorted", source));
+    assert_eq!(warnings[0].elements, set(&[source, wire, switch]));
+}
+
+#[test]
+fn parallel_voltage_sources() {
+    let mut circuit = Circuit::new();
+    let first = circuit.add(DCVoltageSource::new(vec![0, 1], 5.0));
+    let second = circuit.add(DCVoltageSource::new(vec![0, 1], 3.0));
+    circuit.add(Resistor::new(vec![1, 0], 1000.0));
+
+    let warnings = circuit.check();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].error, SolverError::VoltageSourceLoop { elements: vec![first, second] });
+    assert_eq!(warnings[0].elements, set(&[first, second]));
+}
+
+#[test]
+fn inductor_across_a_voltage_source() {
+    let mut circuit = Circuit::new();
+    let source = circuit.add(DCVoltageSource::new(vec![0, 1], 5.0));
+    let inductor = circuit.add(Inductor::new(vec![1, 0], 1.0e-3));
+
+    // the inductor is a short at the operating point
+    let warnings = circuit.check();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].error, SolverError::VoltageSourceLoop { elements: vec![source, inductor] });
+    assert_eq!(circuit.operating_point().unwrap_err(), warnings[0].error);
+
+    // in a time step its current builds up through the companion model
+    let step = StepInfo { timestep: 1.0e-6, time: 0.0 };
+    assert!(circuit.check_at(&step).is_empty());
+    assert!(circuit.solve_with_info(&step, &mut SolverInfo::default()).is_ok());
+}
+
+#[test]
+fn current_source_without_return_path() {
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 1], 5.0));
+    circuit.add(Resistor::new(vec![1, 0], 1000.0));
+    let source = circuit.add(CurrentSource::new(vec![0, 2], 1.0e-3));
+    let resistor = circuit.add(Resistor::new(vec![2, 3], 1000.0));
+
+    let warnings = circuit.check();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].error, SolverError::CurrentSourceOpen { element: source, nodes: vec![2, 3] });
+    assert_eq!(warnings[0].elements, set(&[source, resistor]));
+}
+
+#[test]
+fn every_problem_is_reported() {
+    let mut circuit = Circuit::new();
+    let source = circuit.add(DCVoltageSource::new(vec![0, 1], 5.0));
+    circuit.add(Wire::new(vec![1, 0]));
+    circuit.add(Resistor::new(vec![2, 3], 1000.0));
+    circuit.add(Resistor::new(vec![4, 5], 1000.0));
+
+    let errors: Vec<SolverError> = circuit.check().into_iter().map(|warning| warning.error).collect();
+    assert_eq!(errors, vec![
+        SolverError::VoltageSourceLoop { elements: vec![source] },
+        SolverError::FloatingSubnet { nodes: vec![2, 3] },
+        SolverError::FloatingSubnet { nodes: vec![4, 5] },
+    ]);
+}
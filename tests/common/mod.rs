@@ -0,0 +1,17 @@
+// helpers shared by the integration tests, every test file uses its own part of them
+#![allow(dead_code)]
+
+use rusty_circuit::circuit::{Circuit, Solution};
+
+// relative to the expected value, values near 0 are compared against 1e-3 instead so they can not pass
+// on any tiny number
+pub fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+    assert!(
+        (actual - expected).abs() <= tolerance * expected.abs().max(1.0e-3),
+        "expected {}, got {}", expected, actual,
+    );
+}
+
+pub fn solve(circuit: &Circuit) -> Solution {
+    circuit.operating_point().expect("the circuit should solve")
+}
@@ -0,0 +1,77 @@
+// the four controlled sources at the operating point: the sign and size of their output
+//
+// node 0 is ground, a 2 V source drives node 1 through a 1 kOhm load, so its branch current from its first
+// node through the source to its second is 2 mA, every controlled source drives node 2 against ground and
+// node 2 is loaded with 1 kOhm as well
+
+mod common;
+
+use rusty_circuit::circuit::Circuit;
+use rusty_circuit::devices::{Cccs, Ccvs, DCVoltageSource, Resistor, Vccs, Vcvs};
+use rusty_circuit::Device;
+use common::{assert_close, solve};
+
+const TOLERANCE: f64 = 1.0e-9;
+
+// the circuit with the controlled source added by `source`, which gets the id of the 2 V source
+fn controlled(source: impl FnOnce(u32) -> Box<dyn Device>) -> Circuit {
+    let mut circuit = Circuit::new();
+    let control = circuit.add(DCVoltageSource::new(vec![0, 1], 2.0));
+    circuit.add(Resistor::new(vec![1, 0], 1000.0));
+    circuit.add(Resistor::new(vec![2, 0], 1000.0));
+    circuit.insert(10, source(control));
+    circuit
+}
+
+// the current the current controlled sources scale
+#[test]
+fn control_current_of_the_source() {
+    let solution = solve(&controlled(|_| Box::new(Resistor::new(vec![2, 0], 1000.0))));
+    assert_close(solution.current(1).unwrap(), 2.0e-3, TOLERANCE);
+}
+
+#[test]
+fn vcvs_output() {
+    // the second output node is driven to gain times node 1 against ground
+    for gain in [3.0, -3.0] {
+        let solution = solve(&controlled(|_| Box::new(Vcvs::new(vec![0, 2, 1, 0], gain))));
+        assert_close(solution.voltage(2), gain * 2.0, TOLERANCE);
+        // the output feeds the load, so its current flows out of the second node
+        assert_close(solution.current(10).unwrap(), gain * 2.0e-3, TOLERANCE);
+    }
+    // the sense nodes the other way round turn the output around
+    let solution = solve(&controlled(|_| Box::new(Vcvs::new(vec![0, 2, 0, 1], 3.0))));
+    assert_close(solution.voltage(2), -6.0, TOLERANCE);
+}
+
+#[test]
+fn vccs_output() {
+    // 1 mS times 2 V pushed out of the second node into the load
+    let solution = solve(&controlled(|_| Box::new(Vccs::new(vec![0, 2, 1, 0], 1.0e-3))));
+    assert_close(solution.voltage(2), 2.0, TOLERANCE);
+    assert_close(solution.current(10).unwrap(), 2.0e-3, TOLERANCE);
+
+    let solution = solve(&controlled(|_| Box::new(Vccs::new(vec![2, 0, 1, 0], 1.0e-3))));
+    assert_close(solution.voltage(2), -2.0, TOLERANCE);
+}
+
+#[test]
+fn ccvs_output() {
+    // 500 Ohm times 2 mA
+    let solution = solve(&controlled(|control| Box::new(Ccvs::new(vec![0, 2], 500.0, Some(control)))));
+    assert_close(solution.voltage(2), 1.0, TOLERANCE);
+
+    let solution = solve(&controlled(|control| Box::new(Ccvs::new(vec![0, 2], -500.0, Some(control)))));
+    assert_close(solution.voltage(2), -1.0, TOLERANCE);
+}
+
+#[test]
+fn cccs_output() {
+    // twice the 2 mA pushed out of the second node into the load
+    let solution = solve(&controlled(|control| Box::new(Cccs::new(vec![0, 2], 2.0, Some(control)))));
+    assert_close(solution.voltage(2), 4.0, TOLERANCE);
+    assert_close(solution.current(10).unwrap(), 4.0e-3, TOLERANCE);
+
+    let solution = solve(&controlled(|control| Box::new(Cccs::new(vec![2, 0], 2.0, Some(control)))));
+    assert_close(solution.voltage(2), -4.0, TOLERANCE);
+}
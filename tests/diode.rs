@@ -0,0 +1,79 @@
+// the diode against the shockley equation, and what newton reports when it runs out of iterations
+//
+// node 0 is ground, a voltage source drives its second node to the voltage above its first and a current
+// source drives its current out of its second node into the circuit, the first node of a diode is its anode
+
+mod common;
+
+use rusty_circuit::circuit::Circuit;
+use rusty_circuit::circuit_solver::{SolverError, Unknown};
+use rusty_circuit::devices::{CurrentSource, DCVoltageSource, Diode, Resistor};
+use rusty_circuit::devices::diode::THERMAL_VOLTAGE;
+use common::{assert_close, solve};
+
+const SATURATION_CURRENT: f64 = 1.0e-14;
+
+// the current a junction carries at a voltage, without the small conductance that keeps it from floating
+fn shockley(voltage: f64, emission_coefficient: f64) -> f64 {
+    SATURATION_CURRENT * ((voltage / (emission_coefficient * THERMAL_VOLTAGE)).exp() - 1.0)
+}
+
+// 5 V through 1 kOhm into a diode, the anode is node 2
+fn diode_and_resistor(emission_coefficient: f64, series_resistance: f64) -> Circuit {
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 1], 5.0));
+    circuit.add(Resistor::new(vec![1, 2], 1000.0));
+    circuit.add(Diode::new(vec![2, 0], SATURATION_CURRENT, emission_coefficient, series_resistance));
+    circuit
+}
+
+#[test]
+fn diode_and_resistor_follow_shockley() {
+    for emission_coefficient in [1.0, 1.8] {
+        let circuit = diode_and_resistor(emission_coefficient, 0.0);
+        let solution = solve(&circuit);
+        let anode = solution.voltage(2);
+        let current = (5.0 - anode) / 1000.0;
+        // newton only asks the voltage to settle within its tolerance, which the exponential turns into a
+        // somewhat larger error of the current
+        assert_close(shockley(anode, emission_coefficient), current, 1.0e-3);
+        assert_close(solution.current(3).unwrap(), current, 1.0e-3);
+        assert!(anode > 0.5 && anode < 1.5, "the diode should be forward biased, its anode is at {} V", anode);
+    }
+}
+
+#[test]
+fn series_resistance_takes_its_share() {
+    let circuit = diode_and_resistor(1.0, 100.0);
+    let solution = solve(&circuit);
+    let current = (5.0 - solution.voltage(2)) / 1000.0;
+    let junction = solution.voltage(2) - 100.0 * current;
+    assert_close(shockley(junction, 1.0), current, 1.0e-3);
+}
+
+#[test]
+fn reverse_biased_diode_blocks() {
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 1], 5.0));
+    circuit.add(Resistor::new(vec![1, 2], 1000.0));
+    circuit.add(Diode::new(vec![0, 2], SATURATION_CURRENT, 1.0, 0.0));
+    let solution = solve(&circuit);
+    assert_close(solution.voltage(2), 5.0, 1.0e-6);
+}
+
+#[test]
+fn newton_reports_the_unknown_that_does_not_settle() {
+    // 1 mA into a diode, the voltage of node 1 is the only unknown
+    let mut circuit = Circuit::new();
+    circuit.add(CurrentSource::new(vec![0, 1], 1.0e-3));
+    circuit.add(Diode::new(vec![1, 0], SATURATION_CURRENT, 1.0, 0.0));
+    circuit.newton.max_iterations = 3;
+    let error = circuit.operating_point().unwrap_err();
+    assert_eq!(error, SolverError::NoConvergence { iterations: 3, unknown: Unknown::NodeVoltage(1) });
+    assert_eq!(error.to_string(), "No solution found after 3 iterations, the voltage of node 1 does not settle");
+
+    // with the default limit the same circuit solves
+    circuit.newton.max_iterations = 100;
+    let solution = solve(&circuit);
+    assert_close(shockley(solution.voltage(1), 1.0), 1.0e-3, 1.0e-3);
+}
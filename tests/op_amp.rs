@@ -0,0 +1,181 @@
+// op-amp circuits against their textbook gains: the ideal model exactly, the single pole model within its
+// finite gain, and both held at their rails
+//
+// the op-amp nodes are the non-inverting input, the inverting input, the output and optionally the
+// positive and the negative supply
+
+mod common;
+
+use rusty_circuit::ac::{AcSource, Sweep, SweepScale};
+use rusty_circuit::circuit::{Circuit, StepInfo};
+use rusty_circuit::circuit_solver::SolverError;
+use rusty_circuit::devices::{DCVoltageSource, OpAmp, OpAmpModel, Resistor, Wire};
+use common::{assert_close, solve};
+
+// without output resistance the output of the single pole model is not loaded down either
+fn op_amp(nodes: Vec<u32>, model: OpAmpModel, rails: Option<[f64; 2]>) -> OpAmp {
+    let mut op_amp = OpAmp::new(nodes, model);
+    op_amp.rails = rails;
+    op_amp.output_resistance = 0.0;
+    op_amp
+}
+
+// input at node 1, inverting input at node 2, output at node 3
+fn inverting_amplifier(model: OpAmpModel, input: f64, rails: Option<[f64; 2]>) -> (Circuit, u32) {
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 1], input));
+    circuit.add(Resistor::new(vec![1, 2], 1000.0));
+    circuit.add(Resistor::new(vec![2, 3], 10000.0));
+    let id = circuit.add(op_amp(vec![0, 2, 3], model, rails));
+    (circuit, id)
+}
+
+// input at node 1, inverting input at node 2, output at node 3, gain 11
+fn non_inverting_amplifier(circuit: &mut Circuit, op_amp: OpAmp, input: f64) {
+    circuit.add(DCVoltageSource::new(vec![0, 1], input));
+    circuit.add(Resistor::new(vec![3, 2], 10000.0));
+    circuit.add(Resistor::new(vec![2, 0], 1000.0));
+    circuit.add(op_amp);
+}
+
+#[test]
+fn ideal_inverting_amplifier() {
+    let (circuit, id) = inverting_amplifier(OpAmpModel::Ideal, 0.5, None);
+    let solution = solve(&circuit);
+
+    assert_close(solution.voltage(2), 0.0, 1.0e-9);
+    assert_close(solution.voltage(3), -5.0, 1.0e-9);
+    // the feedback current of 0.5 mA flows into the output and down to ground through the op-amp
+    assert_close(solution.branch_current(id).unwrap(), -0.5e-3, 1.0e-9);
+}
+
+#[test]
+fn ideal_non_inverting_amplifier() {
+    let mut circuit = Circuit::new();
+    non_inverting_amplifier(&mut circuit, op_amp(vec![1, 2, 3], OpAmpModel::Ideal, None), 0.3);
+    let solution = solve(&circuit);
+
+    assert_close(solution.voltage(2), 0.3, 1.0e-9);
+    assert_close(solution.voltage(3), 3.3, 1.0e-9);
+}
+
+#[test]
+fn single_pole_follower_has_finite_gain() {
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 1], 2.0));
+    let mut follower = OpAmp::new(vec![1, 2, 2], OpAmpModel::SinglePole);
+    follower.gain = 1000.0;
+    circuit.add(follower);
+    circuit.add(Resistor::new(vec![2, 0], 10000.0));
+    let solution = solve(&circuit);
+
+    // the output resistance of 75 Ohm divides the gain by the load
+    let gain = 1000.0 * 10000.0 / 10075.0;
+    let gain = gain / (1.0 + gain);
+    assert_close(solution.voltage(2), 2.0 * gain, 1.0e-4);
+}
+
+#[test]
+fn single_pole_inverting_amplifier_is_close_to_ideal() {
+    let (circuit, _) = inverting_amplifier(OpAmpModel::SinglePole, 0.5, None);
+    let solution = solve(&circuit);
+
+    // the loop gain of 2e5 / 11 leaves an error of about 55 ppm
+    assert_close(solution.voltage(3), -5.0, 1.0e-4);
+    assert!(solution.voltage(3) > -5.0);
+}
+
+#[test]
+fn output_saturates_at_the_rails() {
+    for model in [OpAmpModel::Ideal, OpAmpModel::SinglePole] {
+        let (circuit, _) = inverting_amplifier(model, 1.0, Some([-4.0, 6.0]));
+        assert_close(solve(&circuit).voltage(3), -4.0, 1.0e-9);
+
+        let (circuit, _) = inverting_amplifier(model, -1.0, Some([-4.0, 6.0]));
+        assert_close(solve(&circuit).voltage(3), 6.0, 1.0e-9);
+
+        // inside the rails the limits change nothing
+        let (circuit, _) = inverting_amplifier(model, 0.2, Some([-4.0, 6.0]));
+        assert_close(solve(&circuit).voltage(3), -2.0, 1.0e-4);
+    }
+}
+
+#[test]
+fn supply_nodes_set_the_rails() {
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 4], 12.0));
+    circuit.add(DCVoltageSource::new(vec![5, 0], 12.0));
+    // the supply overrides rails set on the op-amp
+    non_inverting_amplifier(&mut circuit, op_amp(vec![1, 2, 3, 4, 5], OpAmpModel::Ideal, Some([-1.0, 1.0])), 2.0);
+    let solution = solve(&circuit);
+
+    assert_close(solution.voltage(3), 12.0, 1.0e-9);
+    assert_close(solution.voltage(2), 12.0 / 11.0, 1.0e-9);
+}
+
+#[test]
+fn input_resistance_current_reaches_the_input() {
+    // the wire only carries the current of the input resistance into the non-inverting input
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 1], 1.0));
+    let wire = circuit.add(Wire::new(vec![1, 4]));
+    let mut amplifier = op_amp(vec![4, 2, 3], OpAmpModel::SinglePole, None);
+    amplifier.gain = 10.0;
+    amplifier.input_resistance = 1000.0;
+    let amplifier = circuit.add(amplifier);
+    circuit.add(Resistor::new(vec![3, 2], 10000.0));
+    circuit.add(Resistor::new(vec![2, 0], 1000.0));
+    let solution = solve(&circuit);
+
+    let input = (solution.voltage(4) - solution.voltage(2)) / 1000.0;
+    assert!(input > 1.0e-4);
+    assert_close(solution.current(wire).unwrap(), input, 1.0e-9);
+    // the op-amp takes the power of the input resistance and delivers the output current
+    let output = (solution.voltage(3) - solution.voltage(2)) / 10000.0;
+    let power = input * (solution.voltage(4) - solution.voltage(2)) - solution.voltage(3) * output;
+    assert_close(solution.power(amplifier).unwrap(), power, 1.0e-9);
+}
+
+#[test]
+fn follower_bandwidth_is_the_gain_bandwidth() {
+    let mut circuit = Circuit::new();
+    let mut source = DCVoltageSource::new(vec![0, 1], 0.0);
+    source.ac = Some(AcSource { magnitude: 1.0, phase: 0.0 });
+    circuit.add(source);
+    circuit.add(op_amp(vec![1, 2, 2], OpAmpModel::SinglePole, None));
+    circuit.add(Resistor::new(vec![2, 0], 10000.0));
+
+    let sweep = Sweep { scale: SweepScale::Linear, points: 3, start: 1.0, stop: 2.0e6 };
+    let solutions = circuit.ac_sweep(&sweep).unwrap();
+    // the closed loop pole sits at the gain bandwidth, the open loop gain is 1 there
+    assert_close(solutions[0].voltage(2).norm(), 1.0, 1.0e-4);
+    assert_close(solutions[1].voltage(2).norm(), std::f64::consts::FRAC_1_SQRT_2, 1.0e-3);
+    assert!(solutions[2].voltage(2).norm() < 0.5);
+}
+
+#[test]
+fn follower_step_response() {
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 1], 1.0));
+    circuit.add(op_amp(vec![1, 2, 2], OpAmpModel::SinglePole, None));
+    circuit.add(Resistor::new(vec![2, 0], 10000.0));
+
+    // the closed loop time constant is 1 / (2 pi gain_bandwidth)
+    let time_constant = 1.0 / (std::f64::consts::TAU * 1.0e6);
+    let timestep = time_constant / 200.0;
+    let mut output = 0.0;
+    for index in 1..=200 {
+        output = circuit.step(&StepInfo { timestep, time: timestep * index as f64 }).unwrap().voltage(2);
+    }
+    assert_close(output, 1.0 - (-1.0f64).exp(), 1.0e-2);
+}
+
+#[test]
+fn output_against_a_voltage_source_is_a_loop() {
+    let mut circuit = Circuit::new();
+    let source = circuit.add(DCVoltageSource::new(vec![0, 1], 1.0));
+    let op_amp = circuit.add(op_amp(vec![1, 2, 1], OpAmpModel::Ideal, None));
+    circuit.add(Resistor::new(vec![2, 0], 1000.0));
+
+    assert_eq!(circuit.operating_point().unwrap_err(), SolverError::VoltageSourceLoop { elements: vec![source, op_amp] });
+}
@@ -0,0 +1,148 @@
+// pin layouts of multi-terminal elements, how they turn with their orientation and how they connect in
+// a schematic
+
+mod common;
+
+use rusty_circuit::pins::{pin_positions, placement, supply_positions, Orientation};
+use rusty_circuit::circuit_solver::SolverError;
+use rusty_circuit::schematic::{ElementRecord, Parameters, Schematic};
+use rusty_circuit::ElementType;
+use common::assert_close;
+
+// a rotation or flip of the grid
+type Transform = fn((i32, i32)) -> (i32, i32);
+
+fn orientations() -> Vec<Orientation> {
+    (0..4).flat_map(|turns| [false, true].map(|mirrored| Orientation { turns, mirrored })).collect()
+}
+
+#[test]
+fn op_amp_pins_turn_with_the_element() {
+    let pins = |turns| pin_positions(ElementType::OpAmp, (10, 5), (0, 0), Orientation { turns, mirrored: false });
+    // non-inverting input, inverting input, output
+    assert_eq!(pins(0), vec![(10, 6), (10, 4), (13, 5)]);
+    assert_eq!(pins(1), vec![(9, 5), (11, 5), (10, 8)]);
+    assert_eq!(pins(2), vec![(10, 4), (10, 6), (7, 5)]);
+    assert_eq!(pins(3), vec![(11, 5), (9, 5), (10, 2)]);
+}
+
+#[test]
+fn mirroring_swaps_the_inputs() {
+    let pins = pin_positions(ElementType::OpAmp, (10, 5), (0, 0), Orientation { turns: 0, mirrored: true });
+    assert_eq!(pins, vec![(10, 4), (10, 6), (13, 5)]);
+}
+
+#[test]
+fn supply_pins_turn_with_the_op_amp() {
+    let supply = |turns, mirrored| supply_positions(ElementType::OpAmp, (10, 5), Orientation { turns, mirrored });
+    // positive supply above the body, negative supply below
+    assert_eq!(supply(0, false), vec![(11, 3), (11, 7)]);
+    assert_eq!(supply(1, false), vec![(12, 6), (8, 6)]);
+    assert_eq!(supply(0, true), vec![(11, 7), (11, 3)]);
+    assert!(supply_positions(ElementType::Bjt, (10, 5), Orientation::default()).is_empty());
+}
+
+#[test]
+fn two_terminal_elements_stretch_to_their_size() {
+    assert_eq!(pin_positions(ElementType::Resistor, (1, 2), (3, -1), Orientation::default()), vec![(1, 2), (4, 1)]);
+    // ground only has its anchor however long its stem is
+    assert_eq!(pin_positions(ElementType::Ground, (1, 2), (0, 3), Orientation { turns: 1, mirrored: false }), vec![(1, 2)]);
+}
+
+#[test]
+fn orientation_follows_rotations_and_flips() {
+    let transforms: [Transform; 3] = [|(x, y)| (-y, x), |(x, y)| (-x, y), |(x, y)| (x, -y)];
+    for orientation in orientations() {
+        assert_eq!(Orientation::from_axes(orientation.apply((1, 0)), orientation.apply((0, 1))), orientation);
+        for transform in transforms {
+            let turned = Orientation::from_axes(transform(orientation.apply((1, 0))), transform(orientation.apply((0, 1))));
+            for pin in [(0, 1), (0, -1), (3, 0), (2, 5)] {
+                assert_eq!(turned.apply(pin), transform(orientation.apply(pin)), "{:?} at {:?}", orientation, pin);
+            }
+        }
+    }
+}
+
+#[test]
+fn placement_snaps_laid_out_elements_to_an_axis() {
+    let right = Orientation { turns: 0, mirrored: false };
+    let up = Orientation { turns: 3, mirrored: false };
+    let down = Orientation { turns: 1, mirrored: false };
+    assert_eq!(placement(ElementType::OpAmp, (5, 2)), ((3, 0), right));
+    assert_eq!(placement(ElementType::OpAmp, (-1, -4)), ((0, -3), up));
+    assert_eq!(placement(ElementType::Ground, (2, 5)), ((0, 5), down));
+    assert_eq!(placement(ElementType::Resistor, (2, 5)), ((2, 5), Orientation::default()));
+    // nothing is placed before the drag leaves the anchor
+    assert_eq!(placement(ElementType::OpAmp, (0, 0)), ((0, 0), Orientation::default()));
+}
+
+// an inverting amplifier with gain -10 around an op-amp that points left, its non-inverting input is
+// grounded at (10, 4) and its inverting input is at (10, 6)
+const TURNED_AMPLIFIER: &str = r#"{
+  "version": 1,
+  "view": { "offset": [0.0, 0.0], "grid_step": 35.0 },
+  "elements": [
+    { "id": 1, "position": [10, 5], "size": [-3, 0], "orientation": { "turns": 2, "mirrored": false },
+      "type": "OpAmp", "model": "Ideal", "gain": 200000.0, "gain_bandwidth": 1000000.0,
+      "input_resistance": 2000000.0, "output_resistance": 75.0 },
+    { "id": 2, "position": [10, 4], "size": [0, -1], "type": "Ground" },
+    { "id": 3, "position": [20, 8], "size": [0, -2], "type": "DCVoltageSource", "voltage": 1.0 },
+    { "id": 4, "position": [20, 8], "size": [0, 1], "type": "Ground" },
+    { "id": 5, "position": [20, 6], "size": [-10, 0], "type": "Resistor", "resistance": 1000.0 },
+    { "id": 6, "position": [10, 6], "size": [-3, -1], "type": "Resistor", "resistance": 10000.0 }
+  ]
+}"#;
+
+#[test]
+fn turned_op_amp_connects_at_its_pins() {
+    let schematic = Schematic::from_json(TURNED_AMPLIFIER).unwrap();
+    assert_eq!(schematic.elements[0].node_positions(), vec![(10, 4), (10, 6), (7, 5)]);
+
+    let node_ids = schematic.node_ids();
+    let solution = schematic.to_circuit().operating_point().unwrap();
+    assert_close(solution.voltage(node_ids[&(7, 5)]), -10.0, 1.0e-9);
+    assert_close(solution.voltage(node_ids[&(10, 6)]), 0.0, 1.0e-6);
+}
+
+#[test]
+fn only_turned_elements_save_their_orientation() {
+    let schematic = Schematic::from_json(TURNED_AMPLIFIER).unwrap();
+    let json = schematic.to_json();
+    assert_eq!(json.matches("orientation").count(), 1);
+    assert_eq!(Schematic::from_json(&json).unwrap(), schematic);
+}
+
+// a follower with its supply pins at (11, 3) and (11, 7), the negative supply is left open
+const SUPPLIED_FOLLOWER: &str = r#"{
+  "version": 1,
+  "view": { "offset": [0.0, 0.0], "grid_step": 35.0 },
+  "elements": [
+    { "id": 1, "position": [10, 5], "size": [3, 0], "type": "OpAmp", "model": "Ideal", "gain": 200000.0,
+      "gain_bandwidth": 1000000.0, "input_resistance": 2000000.0, "output_resistance": 75.0, "supply": true },
+    { "id": 2, "position": [8, 6], "size": [2, 0], "type": "DCVoltageSource", "voltage": 20.0 },
+    { "id": 3, "position": [8, 6], "size": [0, 1], "type": "Ground" },
+    { "id": 4, "position": [13, 5], "size": [-3, -1], "type": "Wire" },
+    { "id": 5, "position": [11, 1], "size": [0, 2], "type": "DCVoltageSource", "voltage": 12.0 },
+    { "id": 6, "position": [11, 1], "size": [0, 1], "type": "Ground" }
+  ]
+}"#;
+
+#[test]
+fn supply_pins_connect_and_open_ones_float() {
+    let schematic = Schematic::from_json(SUPPLIED_FOLLOWER).unwrap();
+    assert_eq!(schematic.elements[0].node_positions(), vec![(10, 6), (10, 4), (13, 5), (11, 3), (11, 7)]);
+
+    let node_ids = schematic.node_ids();
+    let warnings = schematic.to_circuit().check();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].error, SolverError::FloatingSubnet { nodes: vec![node_ids[&(11, 7)]] });
+
+    // with the negative supply grounded the output stops at the positive one
+    let mut schematic = schematic;
+    let ground = ElementRecord { id: 7, position: [11, 7], size: [0, 1], parameters: Parameters::Ground, sense: None, orientation: Orientation::default() };
+    schematic.elements.push(ground);
+    let node_ids = schematic.node_ids();
+    let solution = schematic.to_circuit().operating_point().unwrap();
+    assert_close(solution.voltage(node_ids[&(13, 5)]), 12.0, 1.0e-9);
+    assert!(schematic.to_json().contains("\"supply\": true"));
+}
@@ -0,0 +1,102 @@
+// schematic files: what loading refuses, and that saving and loading gives back the same schematic
+
+use rusty_circuit::ac::AcSource;
+use rusty_circuit::devices::{BjtPolarity, OpAmpModel};
+use rusty_circuit::pins::Orientation;
+use rusty_circuit::schematic::{ElementRecord, Parameters, Schematic, SchematicError, View, FORMAT_VERSION};
+use rusty_circuit::waveform::Waveform;
+
+fn record(id: u32, position: [i32; 2], size: [i32; 2], parameters: Parameters) -> ElementRecord {
+    ElementRecord { id, position, size, parameters, sense: None, orientation: Orientation::default() }
+}
+
+// a document with the given version line and elements
+fn document(version: &str, elements: &str) -> String {
+    format!("{{ {} \"view\": {{ \"offset\": [0.0, 0.0], \"grid_step\": 35.0 }}, \"elements\": [{}] }}", version, elements)
+}
+
+const RESISTOR: &str = "{ \"id\": 1, \"position\": [0, 0], \"size\": [2, 0], \"type\": \"Resistor\", \"resistance\": 10.0 }";
+
+#[test]
+fn version_is_required() {
+    let error = Schematic::from_json(&document("", RESISTOR)).unwrap_err();
+    assert!(matches!(error, SchematicError::MissingVersion));
+    assert_eq!(error.to_string(), "The file has no format version");
+
+    // a version that is not a number is as good as none
+    let error = Schematic::from_json(&document("\"version\": \"1\",", RESISTOR)).unwrap_err();
+    assert!(matches!(error, SchematicError::MissingVersion));
+}
+
+#[test]
+fn newer_versions_are_refused() {
+    // the version is checked before the elements, which a newer version may have changed
+    let error = Schematic::from_json(&document(&format!("\"version\": {},", FORMAT_VERSION + 1), "{ \"id\": 1, \"shape\": \"new\" }")).unwrap_err();
+    assert!(matches!(error, SchematicError::NewerVersion { found, supported } if found == FORMAT_VERSION + 1 && supported == FORMAT_VERSION));
+    assert_eq!(error.to_string(), format!("The file has format version {}, but only versions up to {} are supported", FORMAT_VERSION + 1, FORMAT_VERSION));
+
+    assert!(Schematic::from_json(&document(&format!("\"version\": {},", FORMAT_VERSION), RESISTOR)).is_ok());
+}
+
+#[test]
+fn ids_are_unique() {
+    let second = RESISTOR.replace("[0, 0]", "[2, 0]");
+    let error = Schematic::from_json(&document("\"version\": 1,", &format!("{}, {}", RESISTOR, second))).unwrap_err();
+    assert!(matches!(error, SchematicError::DuplicateId(1)));
+    assert_eq!(error.to_string(), "Element id 1 is used more than once");
+}
+
+#[test]
+fn elements_need_a_size() {
+    let empty = RESISTOR.replace("[2, 0]", "[0, 0]");
+    let error = Schematic::from_json(&document("\"version\": 1,", &empty)).unwrap_err();
+    assert!(matches!(error, SchematicError::EmptyElement(1)));
+    assert_eq!(error.to_string(), "Element 1 has no size");
+
+    // ground has its single node at its position and needs no size
+    let ground = "{ \"id\": 1, \"position\": [0, 0], \"size\": [0, 0], \"type\": \"Ground\" }";
+    assert!(Schematic::from_json(&document("\"version\": 1,", ground)).is_ok());
+}
+
+#[test]
+fn files_that_are_not_schematics() {
+    assert!(matches!(Schematic::from_json("not json").unwrap_err(), SchematicError::Parse(_)));
+    let unknown = "{ \"id\": 1, \"position\": [0, 0], \"size\": [2, 0], \"type\": \"Transformer\" }";
+    assert!(matches!(Schematic::from_json(&document("\"version\": 1,", unknown)).unwrap_err(), SchematicError::Parse(_)));
+
+    let path = std::env::temp_dir().join(format!("rusty_circuit_schematic_{}_missing.json", std::process::id()));
+    assert!(matches!(Schematic::load(&path).unwrap_err(), SchematicError::Io(_)));
+}
+
+#[test]
+fn save_and_load_round_trip() {
+    let mut schematic = Schematic::new(View { offset: [12.5, -3.0], grid_step: 20.0 });
+    schematic.elements.push(record(1, [0, 4], [0, -4], Parameters::DCVoltageSource {
+        voltage: 5.0,
+        waveform: Some(Waveform::PiecewiseLinear { points: vec![[0.0, 0.0], [1.0e-3, 5.0]] }),
+        ac: Some(AcSource { magnitude: 1.0, phase: -90.0 }),
+    }));
+    schematic.elements.push(record(2, [0, 0], [4, 0], Parameters::Resistor { resistance: 4700.0 }));
+    schematic.elements.push(record(3, [4, 0], [0, 4], Parameters::Switch { closed: true }));
+    let mut vcvs = record(4, [8, 4], [0, -4], Parameters::Vcvs { gain: -2.0 });
+    vcvs.sense = Some([[4, 4], [0, 4]]);
+    schematic.elements.push(vcvs);
+    schematic.elements.push(record(5, [12, 4], [0, -4], Parameters::Cccs { gain: 3.0, control: Some(1) }));
+    let mut op_amp = record(6, [16, 0], [4, 0], Parameters::OpAmp {
+        model: OpAmpModel::SinglePole, gain: 1.0e5, gain_bandwidth: 1.0e6, input_resistance: 1.0e6, output_resistance: 75.0,
+        rails: Some([-12.0, 12.0]), supply: false,
+    });
+    op_amp.orientation = Orientation { turns: 1, mirrored: true };
+    schematic.elements.push(op_amp);
+    schematic.elements.push(record(7, [24, 0], [2, 0], Parameters::Bjt {
+        polarity: BjtPolarity::Pnp, beta: 80.0, reverse_beta: 2.0, saturation_current: 1.0e-15, thermal_voltage: 0.026, early_voltage: 0.0,
+    }));
+    schematic.elements.push(record(8, [0, 4], [0, 1], Parameters::Ground));
+
+    let path = std::env::temp_dir().join(format!("rusty_circuit_schematic_{}_round_trip.json", std::process::id()));
+    schematic.save(&path).unwrap();
+    let loaded = Schematic::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, schematic);
+    assert_eq!(loaded.version, FORMAT_VERSION);
+}
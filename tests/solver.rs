@@ -0,0 +1,353 @@
+// operating points of small circuits against their analytic values, and kcl on random resistor networks
+//
+// node 0 is ground, a voltage source drives its second node to the voltage above its first and a current
+// source drives its current out of its second node into the circuit
+
+mod common;
+
+use std::collections::BTreeMap;
+use rusty_circuit::circuit::{Circuit, Solution};
+use rusty_circuit::circuit_solver::SolverError;
+use rusty_circuit::devices::{CurrentSource, DCVoltageSource, Ground, Resistor, Switch, Wire};
+use rusty_circuit::schematic::Parameters;
+use common::{assert_close, solve};
+
+const TOLERANCE: f64 = 1.0e-9;
+
+// the current of a resistor from its first to its second node by ohm's law
+fn ohm(solution: &Solution, nodes: [u32; 2], resistance: f64) -> f64 {
+    (solution.voltage(nodes[0]) - solution.voltage(nodes[1])) / resistance
+}
+
+#[test]
+fn voltage_divider() {
+    let mut circuit = Circuit::new();
+    let source = circuit.add(DCVoltageSource::new(vec![0, 1], 10.0));
+    let top = circuit.add(Resistor::new(vec![1, 2], 1000.0));
+    let bottom = circuit.add(Resistor::new(vec![2, 0], 3000.0));
+    let solution = solve(&circuit);
+
+    assert_close(solution.voltage(1), 10.0, TOLERANCE);
+    assert_close(solution.voltage(2), 7.5, TOLERANCE);
+    assert_close(solution.current(top).unwrap(), 2.5e-3, TOLERANCE);
+    assert_close(solution.current(bottom).unwrap(), 2.5e-3, TOLERANCE);
+    // the current flows up through the source, from ground to its positive terminal
+    assert_close(solution.branch_current(source).unwrap(), 2.5e-3, TOLERANCE);
+    assert_close(solution.power(source).unwrap(), -25.0e-3, TOLERANCE);
+    assert_close(solution.power(top).unwrap(), 6.25e-3, TOLERANCE);
+}
+
+#[test]
+fn reversed_voltage_source() {
+    let mut circuit = Circuit::new();
+    let source = circuit.add(DCVoltageSource::new(vec![1, 0], 10.0));
+    circuit.add(Resistor::new(vec![1, 2], 1000.0));
+    circuit.add(Resistor::new(vec![2, 0], 1000.0));
+    let solution = solve(&circuit);
+
+    assert_close(solution.voltage(1), -10.0, TOLERANCE);
+    assert_close(solution.voltage(2), -5.0, TOLERANCE);
+    assert_close(solution.branch_current(source).unwrap(), 5.0e-3, TOLERANCE);
+}
+
+#[test]
+fn loaded_divider() {
+    // 12 V over 2k and (4k || 4k)
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 1], 12.0));
+    circuit.add(Resistor::new(vec![1, 2], 2000.0));
+    circuit.add(Resistor::new(vec![2, 0], 4000.0));
+    let load = circuit.add(Resistor::new(vec![2, 0], 4000.0));
+    let solution = solve(&circuit);
+
+    assert_close(solution.voltage(2), 6.0, TOLERANCE);
+    assert_close(solution.current(load).unwrap(), 1.5e-3, TOLERANCE);
+}
+
+#[test]
+fn balanced_wheatstone_bridge() {
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 1], 5.0));
+    circuit.add(Resistor::new(vec![1, 2], 100.0));
+    circuit.add(Resistor::new(vec![2, 0], 200.0));
+    circuit.add(Resistor::new(vec![1, 3], 300.0));
+    circuit.add(Resistor::new(vec![3, 0], 600.0));
+    let bridge = circuit.add(Resistor::new(vec![2, 3], 50.0));
+    let solution = solve(&circuit);
+
+    assert_close(solution.voltage(2), 10.0 / 3.0, TOLERANCE);
+    assert_close(solution.voltage(3), 10.0 / 3.0, TOLERANCE);
+    assert_close(solution.current(bridge).unwrap(), 0.0, TOLERANCE);
+}
+
+#[test]
+fn unbalanced_wheatstone_bridge() {
+    let (r1, r2, r3, r4, bridge_resistance) = (100.0, 200.0, 300.0, 300.0, 50.0);
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 1], 5.0));
+    circuit.add(Resistor::new(vec![1, 2], r1));
+    circuit.add(Resistor::new(vec![2, 0], r2));
+    circuit.add(Resistor::new(vec![1, 3], r3));
+    circuit.add(Resistor::new(vec![3, 0], r4));
+    let bridge = circuit.add(Resistor::new(vec![2, 3], bridge_resistance));
+    let solution = solve(&circuit);
+
+    // thevenin equivalent of both legs as seen by the bridge resistor
+    let open_voltage = 5.0 * (r2 / (r1 + r2) - r4 / (r3 + r4));
+    let resistance = r1 * r2 / (r1 + r2) + r3 * r4 / (r3 + r4);
+    let current = open_voltage / (resistance + bridge_resistance);
+    assert_close(solution.current(bridge).unwrap(), current, TOLERANCE);
+    assert_close(solution.voltage(2) - solution.voltage(3), current * bridge_resistance, TOLERANCE);
+}
+
+#[test]
+fn current_source_into_resistor_network() {
+    // 1 mA into 2k || (1k + 1k) is 1 V
+    let mut circuit = Circuit::new();
+    let source = circuit.add(CurrentSource::new(vec![0, 1], 1.0e-3));
+    circuit.add(Resistor::new(vec![1, 0], 2000.0));
+    let series = circuit.add(Resistor::new(vec![1, 2], 1000.0));
+    circuit.add(Resistor::new(vec![2, 0], 1000.0));
+    let solution = solve(&circuit);
+
+    assert_close(solution.voltage(1), 1.0, TOLERANCE);
+    assert_close(solution.voltage(2), 0.5, TOLERANCE);
+    assert_close(solution.current(series).unwrap(), 0.5e-3, TOLERANCE);
+    assert_close(solution.current(source).unwrap(), 1.0e-3, TOLERANCE);
+    assert_close(solution.power(source).unwrap(), -1.0e-3, TOLERANCE);
+}
+
+#[test]
+fn reversed_current_source() {
+    let mut circuit = Circuit::new();
+    circuit.add(CurrentSource::new(vec![1, 0], 2.0e-3));
+    circuit.add(Resistor::new(vec![1, 0], 500.0));
+    let solution = solve(&circuit);
+
+    assert_close(solution.voltage(1), -1.0, TOLERANCE);
+}
+
+// a voltage source and a current source driving the same network
+fn superposition_circuit(voltage: f64, current: f64) -> Circuit {
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 1], voltage));
+    circuit.add(Resistor::new(vec![1, 2], 1000.0));
+    circuit.add(Resistor::new(vec![2, 0], 2000.0));
+    circuit.add(Resistor::new(vec![2, 3], 500.0));
+    circuit.add(Resistor::new(vec![3, 0], 1500.0));
+    circuit.add(CurrentSource::new(vec![0, 3], current));
+    circuit
+}
+
+#[test]
+fn superposition() {
+    let both = solve(&superposition_circuit(9.0, 4.0e-3));
+    let voltage_only = solve(&superposition_circuit(9.0, 0.0));
+    let current_only = solve(&superposition_circuit(0.0, 4.0e-3));
+    for node in 1..=3 {
+        assert_close(both.voltage(node), voltage_only.voltage(node) + current_only.voltage(node), TOLERANCE);
+    }
+
+    // node 2 by nodal analysis: (v2 - 9) / 1k + v2 / 2k + (v2 - v3) / 500 = 0
+    // and node 3: (v3 - v2) / 500 + v3 / 1500 = 4 mA
+    let g = [1.0 / 1000.0, 1.0 / 2000.0, 1.0 / 500.0, 1.0 / 1500.0];
+    let (a, b, c, d) = (g[0] + g[1] + g[2], -g[2], -g[2], g[2] + g[3]);
+    let (e, f) = (9.0 * g[0], 4.0e-3);
+    let determinant = a * d - b * c;
+    assert_close(both.voltage(2), (e * d - b * f) / determinant, TOLERANCE);
+    assert_close(both.voltage(3), (a * f - c * e) / determinant, TOLERANCE);
+}
+
+fn switched_circuit(closed: bool) -> (Circuit, u32) {
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 1], 10.0));
+    circuit.add(Resistor::new(vec![1, 2], 1000.0));
+    circuit.add(Resistor::new(vec![2, 0], 1000.0));
+    let switch = circuit.add(Switch::new(vec![2, 3], closed));
+    circuit.add(Resistor::new(vec![3, 0], 1000.0));
+    (circuit, switch)
+}
+
+#[test]
+fn open_switch() {
+    let (circuit, switch) = switched_circuit(false);
+    let solution = solve(&circuit);
+
+    assert_close(solution.voltage(2), 5.0, TOLERANCE);
+    assert_close(solution.voltage(3), 0.0, TOLERANCE);
+    assert_close(solution.current(switch).unwrap_or(0.0), 0.0, TOLERANCE);
+}
+
+#[test]
+fn closed_switch() {
+    let (circuit, switch) = switched_circuit(true);
+    let solution = solve(&circuit);
+
+    assert_close(solution.voltage(2), 10.0 / 3.0, TOLERANCE);
+    assert_close(solution.voltage(3), 10.0 / 3.0, TOLERANCE);
+    assert_close(solution.current(switch).unwrap(), 10.0 / 3.0e3, TOLERANCE);
+}
+
+#[test]
+fn multiple_grounds() {
+    // the source and the load are grounded through separate ground symbols on nodes 4 and 5
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![4, 1], 6.0));
+    circuit.add(Resistor::new(vec![1, 2], 1000.0));
+    circuit.add(Resistor::new(vec![2, 5], 2000.0));
+    circuit.add(Ground::new(vec![4]));
+    circuit.add(Ground::new(vec![5]));
+    let solution = solve(&circuit);
+
+    assert_close(solution.voltage(4), 0.0, TOLERANCE);
+    assert_close(solution.voltage(5), 0.0, TOLERANCE);
+    assert_close(solution.voltage(1), 6.0, TOLERANCE);
+    assert_close(solution.voltage(2), 4.0, TOLERANCE);
+}
+
+#[test]
+fn wires_merge_nodes() {
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 1], 6.0));
+    let wire = circuit.add(Wire::new(vec![1, 2]));
+    circuit.add(Wire::new(vec![3, 0]));
+    circuit.add(Resistor::new(vec![2, 3], 1000.0));
+    let solution = solve(&circuit);
+
+    assert_close(solution.voltage(2), 6.0, TOLERANCE);
+    assert_close(solution.voltage(3), 0.0, TOLERANCE);
+    assert_close(solution.current(wire).unwrap(), 6.0e-3, TOLERANCE);
+}
+
+#[test]
+fn long_wire_chains_carry_the_current() {
+    // 10 V over 1 kOhm, the return path is a chain of wires
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 1], 10.0));
+    circuit.add(Resistor::new(vec![1, 2], 1000.0));
+    let wires: Vec<u32> = (2..20002).map(|node| circuit.add(Wire::new(vec![node, node + 1]))).collect();
+    circuit.add(Wire::new(vec![20002, 0]));
+    let solution = solve(&circuit);
+
+    for wire in wires {
+        assert_close(solution.current(wire).unwrap(), 10.0e-3, TOLERANCE);
+    }
+}
+
+#[test]
+fn element_without_nodes_has_no_current() {
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 1], 10.0));
+    circuit.add(Resistor::new(vec![1, 0], 1000.0));
+    let unconnected = circuit.add(Resistor::new(Vec::new(), 1000.0));
+    let solution = solve(&circuit);
+
+    assert_close(solution.voltage(1), 10.0, TOLERANCE);
+    assert_eq!(solution.current(unconnected), None);
+}
+
+#[test]
+fn floating_subnet() {
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 1], 5.0));
+    circuit.add(Resistor::new(vec![1, 0], 1000.0));
+    circuit.add(Resistor::new(vec![2, 3], 1000.0));
+
+    match circuit.operating_point() {
+        Err(SolverError::FloatingSubnet { nodes }) => assert_eq!(nodes, vec![2, 3]),
+        result => panic!("expected a floating subnet, got {:?}", result),
+    }
+}
+
+#[test]
+fn parallel_voltage_sources() {
+    let mut circuit = Circuit::new();
+    let first = circuit.add(DCVoltageSource::new(vec![0, 1], 5.0));
+    let second = circuit.add(DCVoltageSource::new(vec![0, 1], 3.0));
+    circuit.add(Resistor::new(vec![1, 0], 1000.0));
+
+    match circuit.operating_point() {
+        Err(SolverError::VoltageSourceLoop { elements }) => assert_eq!(elements, vec![first, second]),
+        result => panic!("expected a voltage source loop, got {:?}", result),
+    }
+}
+
+// xorshift, the networks are the same on every run
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: u32) -> u32 {
+        (self.next() % bound as u64) as u32
+    }
+
+    fn range(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+// a connected network of resistors between ground and nodes 1..=node_count, driven by a few sources
+fn random_network(random: &mut Random) -> Circuit {
+    let node_count = 2 + random.below(10);
+    let mut circuit = Circuit::new();
+    // a spanning tree keeps every node connected to ground
+    for node in 1..=node_count {
+        let other = random.below(node);
+        circuit.add(Resistor::new(vec![other, node], random.range(1.0, 1.0e4)));
+    }
+    for _ in 0..random.below(2 * node_count) {
+        let (a, b) = (random.below(node_count + 1), random.below(node_count + 1));
+        if a != b {
+            circuit.add(Resistor::new(vec![a, b], random.range(1.0, 1.0e4)));
+        }
+    }
+    // a voltage source from ground keeps the sources from forming a loop
+    let driven = 1 + random.below(node_count);
+    circuit.add(DCVoltageSource::new(vec![0, driven], random.range(-20.0, 20.0)));
+    for _ in 0..random.below(3) {
+        let (a, b) = (random.below(node_count + 1), random.below(node_count + 1));
+        if a != b {
+            circuit.add(CurrentSource::new(vec![a, b], random.range(-1.0e-2, 1.0e-2)));
+        }
+    }
+    circuit
+}
+
+#[test]
+fn random_networks_satisfy_kcl() {
+    let mut random = Random(0x2545_f491_4f6c_dd1d);
+    for _ in 0..200 {
+        let circuit = random_network(&mut random);
+        let solution = solve(&circuit);
+
+        // the currents out of every node add up to 0
+        let mut scale = 0.0f64;
+        let mut net = BTreeMap::<u32, f64>::new();
+        for (id, element) in circuit.elements() {
+            let nodes = element.get_nodes();
+            let current = solution.current(*id).unwrap();
+            *net.entry(nodes[0]).or_default() += current;
+            *net.entry(nodes[1]).or_default() -= current;
+            scale = scale.max(current.abs());
+        }
+        for (node, current) in net {
+            if node != 0 {
+                assert!(current.abs() <= 1.0e-9 * scale.max(1.0e-3), "{} A leave node {} of {:?}", current, node, circuit);
+            }
+        }
+
+        // and every resistor follows ohm's law
+        for (id, element) in circuit.elements() {
+            if let Parameters::Resistor { resistance } = element.parameters() {
+                let nodes = element.get_nodes();
+                assert_close(solution.current(*id).unwrap(), ohm(&solution, [nodes[0], nodes[1]], resistance), TOLERANCE);
+            }
+        }
+    }
+}
@@ -0,0 +1,159 @@
+// reading and writing spice decks: values, statements, load errors and the export
+mod common;
+
+use rusty_circuit::ac::AcSource;
+use rusty_circuit::circuit::Circuit;
+use rusty_circuit::devices::{Capacitor, Cccs, Ccvs, CurrentSource, DCVoltageSource, Diode, Inductor, Resistor};
+use rusty_circuit::schematic::{Parameters, View};
+use rusty_circuit::spice::{self, parse_value, SpiceError};
+use rusty_circuit::waveform::Waveform;
+use common::assert_close;
+
+#[test]
+fn values_with_suffixes() {
+    assert_eq!(parse_value("4k7"), Some(4700.0));
+    assert_eq!(parse_value("10u"), Some(1.0e-5));
+    assert_eq!(parse_value("10uF"), Some(1.0e-5));
+    assert_eq!(parse_value("1meg"), Some(1.0e6));
+    assert_eq!(parse_value("1MEG"), Some(1.0e6));
+    // m is milli, not mega
+    assert_eq!(parse_value("1m"), Some(1.0e-3));
+    assert_close(parse_value("1mil").unwrap(), 25.4e-6, 1.0e-9);
+    assert_eq!(parse_value("1e-3"), Some(1.0e-3));
+    assert_eq!(parse_value("2.5e+3"), Some(2500.0));
+    // an e without digits after it is not an exponent
+    assert_eq!(parse_value("3e"), Some(3.0));
+}
+
+#[test]
+fn negative_values() {
+    assert_eq!(parse_value("-5"), Some(-5.0));
+    assert_eq!(parse_value("-4k7"), Some(-4700.0));
+    assert_eq!(parse_value("-1.5m"), Some(-1.5e-3));
+    assert_eq!(parse_value("+2"), Some(2.0));
+}
+
+#[test]
+fn invalid_values() {
+    assert_eq!(parse_value(""), None);
+    assert_eq!(parse_value("k"), None);
+    assert_eq!(parse_value("abc"), None);
+    assert_eq!(parse_value("-"), None);
+}
+
+#[test]
+fn continuation_lines_and_comments() {
+    let deck = "statements\n\
+        * a comment line\n\
+        V1 in 0\n\
+        + DC 10 ; the rest of the line is a comment\n\
+        R1 in out 1k $ so is this\n\
+        \n\
+        R2 out 0\n\
+        +\n\
+        + 4k7\n\
+        .op\n\
+        .end\n\
+        R3 out 0 1k\n";
+    let netlist = spice::parse(deck).expect("the deck should parse");
+    assert_eq!(netlist.title, "statements");
+    let names: Vec<&str> = netlist.elements.iter().map(|element| element.name.as_str()).collect();
+    // nothing after .end is read
+    assert_eq!(names, ["V1", "R1", "R2"]);
+    assert!(matches!(netlist.elements[0].parameters, Parameters::DCVoltageSource { voltage: 10.0, .. }));
+    assert_eq!(netlist.elements[1].parameters, Parameters::Resistor { resistance: 1000.0 });
+    assert_eq!(netlist.elements[2].parameters, Parameters::Resistor { resistance: 4700.0 });
+
+    // errors point at the line the statement starts on
+    let error = spice::parse("lines\n* comment\nR1 1 0\n+ abc\n.end\n").unwrap_err();
+    assert_eq!(error.to_string(), "Line 3: abc is not a valid value");
+}
+
+#[test]
+fn elements_shorted_by_their_own_nodes() {
+    let error = spice::parse("shorted\nV1 1 0 5\nR1 1 1 1k\n.end\n").unwrap_err();
+    assert!(matches!(&error, SpiceError::ShortedElement { line: 3, name } if name == "R1"));
+    assert_eq!(error.to_string(), "Line 3: both nodes of R1 are on the same net");
+
+    // every name of ground is the same net
+    let error = spice::parse("shorted\nV1 0 gnd 5\n.end\n").unwrap_err();
+    assert!(matches!(error, SpiceError::ShortedElement { line: 2, .. }));
+
+    // the deck is refused rather than F1 losing its control
+    let error = spice::parse("shorted control\nF1 1 0 V2 2\nR1 1 0 1k\nV2 2 2 5\n.end\n").unwrap_err();
+    assert!(matches!(&error, SpiceError::ShortedElement { line: 4, name } if name == "V2"));
+}
+
+// a bit of everything the export writes besides transistors and controlled sources
+fn mixed_circuit() -> Circuit {
+    let mut circuit = Circuit::new();
+    let mut source = DCVoltageSource::new(vec![0, 1], 0.0);
+    source.waveform = Some(Waveform::Pulse { initial: 0.0, pulsed: 5.0, delay: 1.0e-6, rise: 1.0e-9, fall: 1.0e-9, width: 5.0e-4, period: 1.0e-3 });
+    source.ac = Some(AcSource { magnitude: 1.0, phase: 0.0 });
+    circuit.add(source);
+    circuit.add(Resistor::new(vec![1, 2], 4700.0));
+    circuit.add(Capacitor::new(vec![2, 0], 1.0e-7));
+    circuit.add(Inductor::new(vec![2, 3], 1.0e-3));
+    circuit.add(Diode::new(vec![3, 0], 1.0e-14, 1.5, 10.0));
+    circuit.add(CurrentSource::new(vec![0, 3], 2.0e-3));
+    circuit
+}
+
+#[test]
+fn export_round_trips_through_parse() {
+    let circuit = mixed_circuit();
+    let netlist = spice::parse(&spice::export(&circuit, "mixed")).expect("the export should parse");
+    assert_eq!(netlist.title, "mixed");
+    assert_eq!(netlist.elements.len(), 6);
+    for element in netlist.elements.iter() {
+        let id: u32 = element.name[1..].parse().unwrap();
+        assert_eq!(element.parameters, circuit.element(id).unwrap().parameters(), "{} changed", element.name);
+    }
+
+    let schematic = netlist.to_schematic(View { offset: [0.0, 0.0], grid_step: 20.0 });
+    let (nodes, _) = netlist.names(&schematic);
+    let solution = schematic.to_circuit().operating_point().expect("the imported circuit should solve");
+    let expected = circuit.operating_point().unwrap();
+    assert_eq!(nodes.len(), 3);
+    for (id, name) in nodes {
+        let original: u32 = name[1..].parse().unwrap();
+        assert_close(solution.voltage(id), expected.voltage(original), 1.0e-6);
+    }
+}
+
+// spice measures the current of a voltage source from its positive node through the source, we measure it
+// the other way round, so the gains of h and f elements controlled by one flip their sign and those
+// controlled by an inductor keep it
+#[test]
+fn current_controlled_gains_flip_for_voltage_sources() {
+    let mut circuit = Circuit::new();
+    let source = circuit.add(DCVoltageSource::new(vec![0, 1], 2.0));
+    circuit.add(Resistor::new(vec![1, 2], 1000.0));
+    let inductor = circuit.add(Inductor::new(vec![2, 0], 1.0e-3));
+    let ccvs = circuit.add(Ccvs::new(vec![0, 3], 500.0, Some(source)));
+    circuit.add(Resistor::new(vec![3, 0], 1000.0));
+    let cccs = circuit.add(Cccs::new(vec![0, 4], 2.0, Some(inductor)));
+    circuit.add(Resistor::new(vec![4, 0], 1000.0));
+
+    let deck = spice::export(&circuit, "flip");
+    let lines: Vec<&str> = deck.lines().collect();
+    assert!(lines.contains(&format!("H{} N3 0 V{} -500", ccvs, source).as_str()), "{}", deck);
+    assert!(lines.contains(&format!("F{} 0 N4 L{} 2", cccs, inductor).as_str()), "{}", deck);
+
+    // reading the deck back flips the gain of the h element again
+    let netlist = spice::parse(&deck).expect("the export should parse");
+    let element = |name: String| netlist.elements.iter().find(|element| element.name == name).unwrap();
+    let h = element(format!("H{}", ccvs));
+    assert_eq!(h.parameters, Parameters::Ccvs { transresistance: 500.0, control: None });
+    assert_eq!(h.control.as_deref(), Some(format!("V{}", source).as_str()));
+    assert_eq!(element(format!("F{}", cccs)).parameters, Parameters::Cccs { gain: 2.0, control: None });
+
+    let schematic = netlist.to_schematic(View { offset: [0.0, 0.0], grid_step: 20.0 });
+    let (nodes, _) = netlist.names(&schematic);
+    let solution = schematic.to_circuit().operating_point().expect("the imported circuit should solve");
+    let expected = circuit.operating_point().unwrap();
+    for (id, name) in nodes {
+        let original: u32 = name[1..].parse().unwrap();
+        assert_close(solution.voltage(id), expected.voltage(original), 1.0e-6);
+    }
+}
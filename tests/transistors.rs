@@ -0,0 +1,178 @@
+// bias points of bjt and mosfet circuits against hand calculations
+//
+// node 0 is ground, a voltage source drives its second node to the voltage above its first and a current
+// source drives its current out of its second node into the circuit, the nodes of a bjt are collector,
+// base and emitter and those of a mosfet drain, gate and source
+
+mod common;
+
+use rusty_circuit::circuit::Circuit;
+use rusty_circuit::dc_sweep::{dc_sweep, DcSweep};
+use rusty_circuit::devices::{Bjt, BjtPolarity, CurrentSource, DCVoltageSource, Mosfet, MosfetChannel, Resistor, Wire};
+use rusty_circuit::devices::diode::THERMAL_VOLTAGE;
+use common::{assert_close, solve};
+
+// a transistor with its base fed by a current source and its collector pulled up to 10 V through 1 kOhm,
+// the base is node 1, the collector node 2 and the supply node 3
+fn fixed_base_current(bjt: Bjt, base_current: f64) -> Circuit {
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 3], 10.0));
+    circuit.add(Resistor::new(vec![3, 2], 1000.0));
+    circuit.add(CurrentSource::new(vec![0, 1], base_current));
+    circuit.add(bjt);
+    circuit
+}
+
+#[test]
+fn npn_in_the_active_region() {
+    let circuit = fixed_base_current(Bjt::new(vec![2, 1, 0], BjtPolarity::Npn), 10.0e-6);
+    let solution = solve(&circuit);
+
+    // ic = beta * ib, and the base-emitter junction carries the forward current ic
+    assert_close(solution.voltage(2), 10.0 - 1000.0 * 1.0e-3, 1.0e-6);
+    let base_emitter = THERMAL_VOLTAGE * (1.0e-3f64 / 1.0e-14 + 1.0).ln();
+    // newton stops within a few microvolts of the junction voltage
+    assert_close(solution.voltage(1), base_emitter, 1.0e-5);
+    // the current is that of the last newton step, which newton only asks to agree within its tolerance
+    assert_close(solution.current(4).unwrap(), 1.0e-3, 1.0e-4);
+}
+
+#[test]
+fn pnp_mirrors_npn() {
+    // the emitter sits at ground, the collector is pulled down to -10 V and the base current is drawn out
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![3, 0], 10.0));
+    circuit.add(Resistor::new(vec![2, 3], 1000.0));
+    circuit.add(CurrentSource::new(vec![1, 0], 10.0e-6));
+    let bjt = circuit.add(Bjt::new(vec![2, 1, 0], BjtPolarity::Pnp));
+    let solution = solve(&circuit);
+
+    assert_close(solution.voltage(2), -9.0, 1.0e-6);
+    assert_close(solution.voltage(1), -THERMAL_VOLTAGE * (1.0e-3f64 / 1.0e-14 + 1.0).ln(), 1.0e-5);
+    // the collector current comes out of the collector of a pnp transistor
+    assert_close(solution.current(bjt).unwrap(), -1.0e-3, 1.0e-4);
+}
+
+#[test]
+fn early_effect_raises_the_collector_current() {
+    let mut bjt = Bjt::new(vec![2, 1, 0], BjtPolarity::Npn);
+    bjt.early_voltage = 50.0;
+    let solution = solve(&fixed_base_current(bjt, 10.0e-6));
+
+    // ic = beta * ib * (1 - vbc / va), the reverse currents of the collector junction are negligible
+    let collector_current = (10.0 - solution.voltage(2)) / 1000.0;
+    let base_collector = solution.voltage(1) - solution.voltage(2);
+    assert_close(collector_current, 1.0e-3 * (1.0 - base_collector / 50.0), 1.0e-6);
+    assert!(collector_current > 1.1e-3);
+}
+
+#[test]
+fn npn_saturates() {
+    // 1 mA into the base would ask for 100 mA, the 1 kOhm resistor lets through at most 10 mA
+    let solution = solve(&fixed_base_current(Bjt::new(vec![2, 1, 0], BjtPolarity::Npn), 1.0e-3));
+    assert!(solution.voltage(2) > 0.0 && solution.voltage(2) < 0.2, "{}", solution.voltage(2));
+}
+
+#[test]
+fn base_current_reaches_the_wire_that_feeds_it() {
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 3], 10.0));
+    circuit.add(Resistor::new(vec![3, 2], 1000.0));
+    circuit.add(CurrentSource::new(vec![0, 4], 10.0e-6));
+    let wire = circuit.add(Wire::new(vec![4, 1]));
+    let bjt = circuit.add(Bjt::new(vec![2, 1, 0], BjtPolarity::Npn));
+    let solution = solve(&circuit);
+
+    assert_close(solution.current(wire).unwrap(), 10.0e-6, 1.0e-6);
+    // power goes into the collector at vce and into the base at vbe
+    let collector_current = (10.0 - solution.voltage(2)) / 1000.0;
+    let power = solution.voltage(2) * collector_current + solution.voltage(1) * 10.0e-6;
+    assert_close(solution.power(bjt).unwrap(), power, 1.0e-4);
+}
+
+// an n-channel transistor with its gate at 3 V and its drain pulled up to 10 V through a resistor, the
+// gate is node 1, the drain node 2 and the supply node 3
+fn common_source(mosfet: Mosfet, drain_resistance: f64) -> Circuit {
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 3], 10.0));
+    circuit.add(DCVoltageSource::new(vec![0, 1], 3.0));
+    circuit.add(Resistor::new(vec![3, 2], drain_resistance));
+    circuit.add(mosfet);
+    circuit
+}
+
+#[test]
+fn nmos_in_saturation() {
+    let solution = solve(&common_source(Mosfet::new(vec![2, 1, 0], MosfetChannel::N), 1000.0));
+    // id = k / 2 * (vgs - vt)^2 = 1e-3 * 2^2
+    assert_close(solution.voltage(2), 10.0 - 4.0, 1.0e-6);
+    assert_close(solution.current(4).unwrap(), 4.0e-3, 1.0e-6);
+}
+
+#[test]
+fn nmos_in_the_triode_region() {
+    let solution = solve(&common_source(Mosfet::new(vec![2, 1, 0], MosfetChannel::N), 5000.0));
+    // k * (2 * vds - vds^2 / 2) = (10 - vds) / 5000 is 5 vds^2 - 21 vds + 10 = 0
+    let drain_source = (21.0 - (21.0f64 * 21.0 - 200.0).sqrt()) / 10.0;
+    assert_close(solution.voltage(2), drain_source, 1.0e-6);
+}
+
+#[test]
+fn channel_length_modulation() {
+    let mut mosfet = Mosfet::new(vec![2, 1, 0], MosfetChannel::N);
+    mosfet.channel_length_modulation = 0.02;
+    let solution = solve(&common_source(mosfet, 1000.0));
+    // id = 4e-3 * (1 + 0.02 * (10 - 1000 * id)) gives id = 4.8e-3 / 1.08
+    assert_close(solution.voltage(2), 10.0 - 1000.0 * 4.8e-3 / 1.08, 1.0e-6);
+}
+
+#[test]
+fn pmos_in_saturation() {
+    // the source sits at 10 V and the gate 3 V below it, the drain is pulled down through 1 kOhm
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 3], 10.0));
+    circuit.add(DCVoltageSource::new(vec![0, 1], 7.0));
+    circuit.add(Resistor::new(vec![2, 0], 1000.0));
+    let mosfet = circuit.add(Mosfet::new(vec![2, 1, 3], MosfetChannel::P));
+    let solution = solve(&circuit);
+
+    assert_close(solution.voltage(2), 4.0, 1.0e-6);
+    assert_close(solution.current(mosfet).unwrap(), -4.0e-3, 1.0e-6);
+}
+
+#[test]
+fn drain_and_source_are_interchangeable() {
+    let forward = solve(&common_source(Mosfet::new(vec![2, 1, 0], MosfetChannel::N), 5000.0));
+    let swapped = solve(&common_source(Mosfet::new(vec![0, 1, 2], MosfetChannel::N), 5000.0));
+    assert_close(swapped.voltage(2), forward.voltage(2), 1.0e-9);
+    assert_close(swapped.current(4).unwrap(), -forward.current(4).unwrap(), 1.0e-9);
+}
+
+#[test]
+fn cut_off_nmos_lets_its_drain_rise() {
+    let mut mosfet = Mosfet::new(vec![2, 1, 0], MosfetChannel::N);
+    mosfet.threshold_voltage = 4.0;
+    let solution = solve(&common_source(mosfet, 1000.0));
+    assert_close(solution.voltage(2), 10.0, 1.0e-6);
+}
+
+#[test]
+fn dc_sweep_through_the_active_region_matches_single_solves() {
+    // the base is driven from a swept source through 100 kOhm, so the transistor turns on halfway
+    let mut circuit = Circuit::new();
+    circuit.add(DCVoltageSource::new(vec![0, 3], 10.0));
+    circuit.add(Resistor::new(vec![3, 2], 1000.0));
+    let source = circuit.add(DCVoltageSource::new(vec![0, 4], 0.0));
+    circuit.add(Resistor::new(vec![4, 1], 100.0e3));
+    circuit.add(Bjt::new(vec![2, 1, 0], BjtPolarity::Npn));
+
+    let sweep = DcSweep { element: source, parameter: "voltage".to_string(), start: 0.0, stop: 5.0, points: 51 };
+    let points = dc_sweep(&circuit, &sweep).expect("the sweep should solve");
+    assert_eq!(points.len(), 51);
+    for point in points {
+        let mut single = circuit.clone();
+        single.insert(source, Box::new(DCVoltageSource::new(vec![0, 4], point.value)));
+        let expected = solve(&single);
+        assert_close(point.solution.voltage(2), expected.voltage(2), 1.0e-4);
+    }
+}
@@ -0,0 +1,93 @@
+// values of the source waveforms at their edges and corners, and the breakpoints the stepper lands on
+
+mod common;
+
+use rusty_circuit::waveform::Waveform;
+use common::assert_close;
+
+const TOLERANCE: f64 = 1.0e-9;
+
+// 0 to 5 after 1 ms, rising in 0.1 ms, 0.5 ms on top, falling in 0.2 ms, every 2 ms
+fn pulse() -> Waveform {
+    Waveform::Pulse { initial: 0.0, pulsed: 5.0, delay: 1.0e-3, rise: 1.0e-4, fall: 2.0e-4, width: 5.0e-4, period: 2.0e-3 }
+}
+
+#[test]
+fn pulse_edges() {
+    let pulse = pulse();
+    for (time, value) in [
+        (0.0, 0.0),
+        // the rise starts at the delay
+        (1.0e-3, 0.0),
+        (1.05e-3, 2.5),
+        (1.1e-3, 5.0),
+        // the fall starts after rise and width
+        (1.6e-3, 5.0),
+        (1.7e-3, 2.5),
+        (1.8e-3, 0.0),
+        (2.9e-3, 0.0),
+        // the next period
+        (3.05e-3, 2.5),
+        (3.3e-3, 5.0),
+    ] {
+        assert_close(pulse.value(time), value, TOLERANCE);
+    }
+
+    // a period of 0 is a single pulse
+    let single = Waveform::Pulse { initial: 1.0, pulsed: -1.0, delay: 0.0, rise: 1.0e-4, fall: 1.0e-4, width: 1.0e-4, period: 0.0 };
+    assert_close(single.value(0.0), 1.0, TOLERANCE);
+    assert_close(single.value(1.5e-4), -1.0, TOLERANCE);
+    assert_close(single.value(3.0e-4), 1.0, TOLERANCE);
+    assert_close(single.value(10.0), 1.0, TOLERANCE);
+}
+
+#[test]
+fn pulse_breakpoints() {
+    let pulse = pulse();
+    for (time, breakpoint) in [(0.0, 1.0e-3), (1.0e-3, 1.1e-3), (1.1e-3, 1.6e-3), (1.6e-3, 1.8e-3), (1.8e-3, 3.0e-3), (3.0e-3, 3.1e-3)] {
+        assert_close(pulse.next_breakpoint(time).unwrap(), breakpoint, TOLERANCE);
+    }
+}
+
+#[test]
+fn sine_edges() {
+    // a phase of 90 degrees starts at the top
+    let sine = Waveform::Sine { offset: 1.0, amplitude: 2.0, frequency: 1000.0, delay: 1.0e-3, damping: 0.0, phase: 90.0 };
+    // before the delay the value holds
+    assert_close(sine.value(0.0), 3.0, TOLERANCE);
+    assert_close(sine.value(1.0e-3), 3.0, TOLERANCE);
+    assert_close(sine.value(1.25e-3), 1.0, TOLERANCE);
+    assert_close(sine.value(1.5e-3), -1.0, TOLERANCE);
+    assert_close(sine.value(2.0e-3), 3.0, TOLERANCE);
+    assert_eq!(sine.next_breakpoint(0.0), Some(1.0e-3));
+    assert_eq!(sine.next_breakpoint(1.0e-3), None);
+
+    // the damping counts from the delay on
+    let damped = Waveform::Sine { offset: 1.0, amplitude: 2.0, frequency: 1000.0, delay: 1.0e-3, damping: 100.0, phase: 90.0 };
+    assert_close(damped.value(1.0e-3), 3.0, TOLERANCE);
+    assert_close(damped.value(2.0e-3), 1.0 + 2.0 * (-0.1f64).exp(), TOLERANCE);
+}
+
+#[test]
+fn piecewise_linear_edges() {
+    let table = Waveform::PiecewiseLinear { points: vec![[1.0e-3, 1.0], [2.0e-3, 3.0], [4.0e-3, -1.0]] };
+    for (time, value) in [
+        // the first value holds before the table
+        (0.0, 1.0),
+        (1.0e-3, 1.0),
+        (1.5e-3, 2.0),
+        (2.0e-3, 3.0),
+        (3.0e-3, 1.0),
+        (4.0e-3, -1.0),
+        // and the last one after it
+        (1.0, -1.0),
+    ] {
+        assert_close(table.value(time), value, TOLERANCE);
+    }
+    assert_eq!(table.next_breakpoint(0.0), Some(1.0e-3));
+    assert_eq!(table.next_breakpoint(1.0e-3), Some(2.0e-3));
+    assert_eq!(table.next_breakpoint(4.0e-3), None);
+
+    // a table without points is 0
+    assert_eq!(Waveform::PiecewiseLinear { points: Vec::new() }.value(1.0), 0.0);
+}